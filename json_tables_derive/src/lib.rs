@@ -0,0 +1,163 @@
+#![warn(missing_docs)]
+//! Derive and procedural macros for the
+//! [json_tables](https://lib.rs/crates/json_tables) crate. They are reexported
+//! by it when its `derive` or `embed` features are enabled, so there is no need
+//! to depend on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::{ffi::OsStr, fs, path::PathBuf};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Member, Path};
+
+/// Derive the `Keyed` trait for a struct from the field marked with
+/// `#[table(key)]`. The field must implement `Display`.
+///
+/// The keys can be validated when pushed with `#[table(key, validate)]`, which
+/// checks them with `json_tables::is_safe_key`, or with
+/// `#[table(key, validate = "path::to::function")]`, which uses a custom
+/// `fn(&str) -> bool` function
+#[proc_macro_derive(TableRecord, attributes(table))]
+pub fn derive_table_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match table_record(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// How the keys are validated
+enum Validation {
+    None,
+    Safe,
+    Custom(Path),
+}
+
+fn table_record(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "TableRecord can only be derived for structs",
+            ))
+        }
+    };
+    let mut key = None;
+    for (index, field) in fields.iter().enumerate() {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("table"))
+        {
+            let mut is_key = false;
+            let mut validation = Validation::None;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    is_key = true;
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    validation = if meta.input.peek(syn::Token![=]) {
+                        let function: LitStr = meta.value()?.parse()?;
+                        Validation::Custom(function.parse()?)
+                    } else {
+                        Validation::Safe
+                    };
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `key` or `validate`"))
+                }
+            })?;
+            if !is_key {
+                return Err(Error::new_spanned(attr, "expected `#[table(key)]`"));
+            }
+            if key.is_some() {
+                return Err(Error::new_spanned(attr, "only one field can be the key"));
+            }
+            let member = match (&field.ident, fields) {
+                (Some(ident), Fields::Named(_)) => Member::Named(ident.clone()),
+                _ => Member::Unnamed(index.into()),
+            };
+            key = Some((member, validation));
+        }
+    }
+    let (member, validation) = key.ok_or_else(|| {
+        Error::new_spanned(
+            input,
+            "TableRecord needs a field marked with `#[table(key)]`",
+        )
+    })?;
+    let name: &Ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let validate = match validation {
+        Validation::None => quote! {},
+        Validation::Safe => quote! {
+            fn validate_key(key: &str) -> bool {
+                ::json_tables::is_safe_key(key)
+            }
+        },
+        Validation::Custom(function) => quote! {
+            fn validate_key(key: &str) -> bool {
+                #function(key)
+            }
+        },
+    };
+    Ok(quote! {
+        impl #impl_generics ::json_tables::Keyed for #name #ty_generics #where_clause {
+            fn key(&self) -> ::std::string::String {
+                ::std::string::ToString::to_string(&self.#member)
+            }
+            #validate
+        }
+    })
+}
+
+/// Embed the json files of a table directory in the binary, expanding to a
+/// `Result<Table<T, MemoryStorage>, TableError>` with a read only table built
+/// from them. The
+/// directory is relative to the root of the crate that uses the macro, and the
+/// files managed by json_tables itself are not embedded.
+///
+/// Changes in the embedded files trigger a rebuild, but files added to the
+/// directory are only picked up when the invoking crate is rebuilt for some
+/// other reason
+#[proc_macro]
+pub fn include_table(input: TokenStream) -> TokenStream {
+    let dir = parse_macro_input!(input as LitStr);
+    match embedded_entries(&dir) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn embedded_entries(dir: &LitStr) -> Result<TokenStream2, Error> {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let path = root.join(dir.value());
+    let read_dir = fs::read_dir(&path)
+        .map_err(|e| Error::new_spanned(dir, format!("can't read {}: {e}", path.display())))?;
+    let mut entries = Vec::new();
+    for dir_entry in read_dir {
+        let file = dir_entry
+            .map_err(|e| Error::new_spanned(dir, e.to_string()))?
+            .path();
+        let name = file
+            .file_name()
+            .and_then(OsStr::to_str)
+            .filter(|name| !name.starts_with(".table"))
+            .and_then(|name| name.strip_suffix(".json"));
+        if let (Some(name), Some(full)) = (name, file.to_str()) {
+            if file.is_file() {
+                entries.push((name.to_string(), full.to_string()));
+            }
+        }
+    }
+    entries.sort();
+    let entries = entries
+        .iter()
+        .map(|(name, full)| quote! { (#name, ::std::include_str!(#full)) });
+    Ok(quote! {
+        ::json_tables::Table::from_embedded(&[#(#entries),*])
+    })
+}
@@ -0,0 +1,65 @@
+//! The derive macros are tested through the reexports of json_tables, the
+//! same way that they are used
+#![allow(clippy::assertions_on_constants)]
+
+#[cfg(test)]
+use json_tables::{Deserialize, Keyed, Serialize, Table, TableError, TableRecord};
+
+#[derive(Debug, Serialize, Deserialize, TableRecord)]
+struct User {
+    #[table(key, validate)]
+    name: String,
+    age: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, TableRecord)]
+struct Order(#[table(key)] u64, String);
+
+fn short(key: &str) -> bool {
+    key.len() < 5
+}
+
+#[derive(Debug, Serialize, Deserialize, TableRecord)]
+struct Tag {
+    #[table(key, validate = "short")]
+    tag: String,
+}
+
+#[test]
+fn derived_keys() {
+    let user = User {
+        name: "ana".into(),
+        age: 30,
+    };
+    assert_eq!(user.key(), "ana");
+    assert_eq!(user.age, 30);
+    assert_eq!(Order(42, "pencils".into()).key(), "42");
+    assert!(User::validate_key("ana"));
+    assert!(!User::validate_key("../ana"));
+    assert!(Order::validate_key("../ana"));
+    assert!(Tag::validate_key("tag"));
+    assert!(!Tag::validate_key("a long tag"));
+}
+
+#[test]
+fn push_derived() {
+    let mut table = Table::<User>::builder("tests/derived")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table
+        .push_keyed(User {
+            name: "ana".into(),
+            age: 30,
+        })
+        .unwrap();
+    assert_eq!(table["ana"].info.age, 30);
+    match table.push_keyed(User {
+        name: "ana/bob".into(),
+        age: 30,
+    }) {
+        Err(TableError::KeyError(key)) => assert_eq!(key, "ana/bob"),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/derived").unwrap();
+}
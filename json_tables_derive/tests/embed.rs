@@ -0,0 +1,31 @@
+#![allow(clippy::assertions_on_constants)]
+
+#[cfg(test)]
+use json_tables::{include_table, Deserialize, MemoryStorage, Serialize, Table, TableError};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Color {
+    name: String,
+    hex: String,
+}
+
+#[test]
+fn embedded_table() {
+    let mut table: Table<Color, MemoryStorage> = include_table!("tests/embedded").unwrap();
+    assert_eq!(table.len(), 2);
+    assert_eq!(table["red"].info.hex, "#ff0000");
+    assert!(!table.has_mod_permissions());
+    match table.pop("red") {
+        Err(TableError::NoWritePolicyError) => assert!(true),
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn embedded_errors() {
+    let table: Result<Table<u32, MemoryStorage>, TableError> = include_table!("tests/embedded");
+    match table {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+}
@@ -0,0 +1,44 @@
+use crate::{unix_millis, AuditPolicy, Storage, Table, TableError, RESERVED_PREFIX};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// The name of the item in which the mutations of a table are logged
+pub(crate) fn audit_name() -> String {
+    format!("{RESERVED_PREFIX}_audit.log")
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Append a mutation to the audit log, as a json line with the time in
+    /// milliseconds since the unix epoch, when the audit policy asks for it
+    pub(crate) fn audit(&mut self, mut line: Value) -> Result<(), TableError> {
+        if self.metadata.audit_policy == AuditPolicy::NoAudit {
+            return Ok(());
+        }
+        line["timestamp"] = unix_millis().into();
+        let mut line = serde_json::to_vec(&line)?;
+        line.push(b'\n');
+        Ok(self.storage.append(&audit_name(), &line)?)
+    }
+
+    /// The mutations logged in the audit log of the table, oldest first. Each
+    /// one has an `op`, a `timestamp` and, when it affects an entry, a `key`
+    ///
+    /// # Errors
+    /// 1. The audit log can't be read
+    /// 2. Some line of the log is not valid json
+    pub fn audit_log(&self) -> Result<Vec<Value>, TableError> {
+        let log = match self.storage.read(&audit_name()) {
+            Ok(log) => log,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        log.split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| Ok(serde_json::from_slice(line)?))
+            .collect()
+    }
+}
@@ -1,9 +1,19 @@
-use crate::{Table, TableBuilderError, TableError};
-pub use serde::{de::DeserializeOwned, Serialize};
-use std::path::{Path, PathBuf};
+use crate::{
+    canonical, config, floats, jsonc, schema, unique::Unique, Expiry, FsStorage, LazyTable,
+    Migrations, Storage, Table, TableBuilderError, TableError,
+};
+pub use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::ser::{CompactFormatter, PrettyFormatter};
+use std::{
+    borrow::Cow,
+    io,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+};
 use std::{fmt::Debug, marker::PhantomData};
+use unicode_normalization::UnicodeNormalization;
 /// Whether the write operation is performed on drop or not
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum WriteType {
     /// You have to manually write back into the files. If the table structure
     /// is dropped without writing back no changes will be applied.
@@ -15,7 +25,7 @@ pub enum WriteType {
 }
 
 /// Weather you can write or not with a table.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum RWPolicy {
     /// No write can or will occur, it will send back an error when write
     /// operations occur
@@ -31,7 +41,7 @@ impl Default for RWPolicy {
 }
 
 /// How to treat the file extensions
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum ExtensionPolicy {
     /// Give an error if a non json file or a directory is found in the table's
     /// directory
@@ -43,7 +53,7 @@ pub enum ExtensionPolicy {
 
 /// Whether to give an error when a file can't be deserialized to the intended
 /// structure
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum ContentPolicy {
     /// Ignore deserialization fails
     IgnoreSerdeErrors,
@@ -52,8 +62,308 @@ pub enum ContentPolicy {
     PromoteSerdeErrors,
 }
 
+/// How the entries are formatted when they are written
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum FormatPolicy {
+    /// Indented json, easy to read and edit by hand
+    #[default]
+    Pretty,
+    /// Json without any whitespace, smaller and faster to write
+    Compact,
+    /// Canonical json, as defined by RFC 8785, so that the same elements are
+    /// always written as the same bytes and can be hashed or signed
+    Canonical,
+    /// Entries that change keep the formatting and comments of their files,
+    /// with only the values that changed replaced. New entries are written as
+    /// indented json
+    Preserve,
+}
+
+impl FormatPolicy {
+    /// Serialize an element in this format
+    pub(crate) fn to_vec<T: Serialize>(self, value: &T) -> serde_json::Result<Vec<u8>> {
+        match self {
+            FormatPolicy::Pretty | FormatPolicy::Preserve => serde_json::to_vec_pretty(value),
+            FormatPolicy::Compact => serde_json::to_vec(value),
+            FormatPolicy::Canonical => canonical::to_vec(value, FloatPolicy::ShortestRoundTrip),
+        }
+    }
+}
+
+/// The syntax that the entries are read with
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SyntaxPolicy {
+    /// Strict json
+    #[default]
+    Json,
+    /// Json with comments and trailing commas, as in jsonc. The entries that
+    /// have them are only written back when they change, losing them
+    Jsonc,
+}
+
+/// Whether the entries written in json5 are loaded, and how they are written
+/// back, which they are only when they change or when they are converted
+#[cfg(feature = "json5")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum Json5Policy {
+    /// They aren't read as json5, so they follow the syntax policy
+    #[default]
+    Disabled,
+    /// Rewrite them as json on the next write back
+    ConvertToJson,
+    /// Leave them as they are, with their comments, until they change
+    Preserve,
+}
+
+/// What to do with the objects of the entries that repeat a key
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum DuplicateKeyPolicy {
+    /// The last value of the key is kept, as serde_json does
+    #[default]
+    KeepLast,
+    /// The load fails, telling the entry and the path of the key
+    Error,
+}
+
+/// How the floats of the entries are written
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum FloatPolicy {
+    /// The shortest representation that reads back as the same float, which
+    /// can be noisy, as in `0.30000000000000004`
+    #[default]
+    ShortestRoundTrip,
+    /// Rounded to this many significant digits
+    MaxPrecision(u8),
+    /// Rounded to this many decimals
+    FixedDecimals(u8),
+}
+
+/// How the numeric ids of the elements are turned into keys
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum IdPolicy {
+    /// The id as is, as in `7`
+    #[default]
+    Plain,
+    /// The id padded with zeros to this many digits, as in `007`, so that the
+    /// files sort in numeric order
+    ZeroPadded(u8),
+}
+
+impl IdPolicy {
+    /// The key of an id
+    pub fn key(self, id: u64) -> String {
+        match self {
+            Self::Plain => id.to_string(),
+            Self::ZeroPadded(width) => format!("{id:0width$}", width = width as usize),
+        }
+    }
+
+    /// The id of a key, or `None` if the key isn't written as an id
+    pub fn id(self, key: &str) -> Option<u64> {
+        key.parse().ok().filter(|id| self.key(*id) == key)
+    }
+}
+
+/// What to do with the keys that are too long to be file names
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum LongKeyPolicy {
+    /// Keys are always the names of their files
+    #[default]
+    Keep,
+    /// The files of the keys longer than this many bytes are named after a
+    /// hash of the key, which is kept in a file of its own
+    Hash(usize),
+}
+
+/// The unicode normalization of the keys, so that keys that look the same are
+/// the same key whatever form they arrive in
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum NormalizationPolicy {
+    /// Keys are used as they are
+    #[default]
+    Preserve,
+    /// Keys are normalized to the composed form, as most systems write them
+    Nfc,
+    /// Keys are normalized to the decomposed form, as older macOS file
+    /// systems write them
+    Nfd,
+}
+
+impl NormalizationPolicy {
+    /// The normalized form of a key
+    pub fn normalize(self, key: &str) -> Cow<'_, str> {
+        let normalized: String = match self {
+            _ if key.is_ascii() => return Cow::Borrowed(key),
+            Self::Preserve => return Cow::Borrowed(key),
+            Self::Nfc => key.nfc().collect(),
+            Self::Nfd => key.nfd().collect(),
+        };
+        match normalized == key {
+            true => Cow::Borrowed(key),
+            false => Cow::Owned(normalized),
+        }
+    }
+}
+
+/// Whether a failed write back can leave some entries written and others not
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum AtomicityPolicy {
+    /// Every entry is written in place, so a write back that fails partway
+    /// leaves some entries new and the rest old
+    #[default]
+    PerEntry,
+    /// Every entry is written to a staging item first, and they are only
+    /// renamed into place once all of them are written, so a write back that
+    /// fails while writing leaves every entry old
+    WholeTable,
+}
+
+/// How hard the table tries to make its writes survive a crash
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum DurabilityPolicy {
+    /// Leave it to the operating system to write the files when it sees fit
+    #[default]
+    NoSync,
+    /// Sync the content of the written files, but not their metadata
+    Flush,
+    /// Sync the written files, both content and metadata
+    SyncFile,
+    /// Sync the written files and the directory, so that newly created files
+    /// are also kept
+    SyncFileAndDir,
+}
+
+impl DurabilityPolicy {
+    /// Sync the written items according to the policy
+    pub(crate) fn apply<'a, S, I>(self, storage: &S, names: I) -> io::Result<()>
+    where
+        S: Storage,
+        I: IntoIterator<Item = &'a str>,
+    {
+        if self == DurabilityPolicy::NoSync {
+            return Ok(());
+        }
+        for name in names {
+            match self {
+                DurabilityPolicy::Flush => storage.sync_data(name)?,
+                _ => storage.sync(name)?,
+            }
+        }
+        match self {
+            DurabilityPolicy::SyncFileAndDir => storage.sync_dir(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How to treat hidden files, whose name starts with a dot, such as the ones
+/// that editors and sync tools leave around
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum HiddenFilePolicy {
+    /// Treat hidden files like any other file
+    #[default]
+    Include,
+    /// Skip hidden files whatever their extension, so that they are neither
+    /// entries nor break the `OnlyJsonFiles` extension policy
+    Skip,
+}
+
+/// How to treat the entries whose files are too big, checked before reading
+/// them. The sizes are in bytes
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SizePolicy {
+    /// Read the entries whatever their size
+    #[default]
+    Unlimited,
+    /// Leave out of the table the entries bigger than the size
+    Skip(u64),
+    /// Give an error if an entry is bigger than the size
+    Error(u64),
+}
+
+/// How to treat the entries that are symbolic links
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SymlinkPolicy {
+    /// Load linked entries from the files they point to. Popping them only
+    /// removes the link
+    #[default]
+    Follow,
+    /// Load linked entries from the files they point to. Popping them removes
+    /// both the link and the file it points to
+    FollowAndPopTarget,
+    /// Leave linked entries out of the table
+    Ignore,
+    /// Give an error if an entry is a link
+    Error,
+}
+
+/// Whether old versions of the entries are kept
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum HistoryPolicy {
+    /// Entries are simply overwritten
+    #[default]
+    NoHistory,
+    /// Before a write back changes an entry, its old content is kept as a
+    /// version in the table's directory, keeping at most this many versions
+    /// of each entry
+    KeepVersions(usize),
+}
+
+/// Whether the mutations of a table are logged
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum AuditPolicy {
+    /// Don't log anything
+    #[default]
+    NoAudit,
+    /// Append every push, pop, rename and write back as a json line to the
+    /// `.table_audit.log` file of the table's directory
+    AppendLog,
+}
+
+/// How writes of an entry by several instances of a table are reconciled
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ConcurrencyPolicy {
+    /// The last write of an entry replaces the others
+    #[default]
+    LastWriteWins,
+    /// Every entry has a version that increases with each of its writes. It's
+    /// kept in a `.table_version.{key}` file of the table's directory, named
+    /// after the hash of the key instead when the key is too long. Updates
+    /// made with `update_if_version` fail when the version has advanced
+    Optimistic,
+}
+
+/// What a write back does with the entries that were edited elsewhere since
+/// they were last read or written, when they were also changed in the table
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ExternalEditPolicy {
+    /// The entries are overwritten with the elements of the table, without
+    /// checking for edits
+    #[default]
+    PreferOurs,
+    /// The edited entries are reloaded into the table, discarding its changes
+    PreferTheirs,
+    /// Both versions are merged with the callback set with `Table::set_merge`,
+    /// and the write back fails as with `Error` when there's none
+    Merge,
+    /// The write back fails
+    Error,
+}
+
+/// The unix modes given to the files and directories that a table creates.
+/// Other platforms can only make them read only, which they do when the mode
+/// has no write permission
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct TablePermissions {
+    /// The mode of the entries and the other files, such as `0o600`
+    pub file_mode: u32,
+    /// The mode of the directory of the table, such as `0o700`
+    pub dir_mode: u32,
+}
+
 /// A compilation of all the policies of a Table
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TableMetadata {
     /// The read write policy for the table
     pub rw_policy: RWPolicy,
@@ -61,6 +371,112 @@ pub struct TableMetadata {
     pub extension_policy: ExtensionPolicy,
     /// The content policy for the table
     pub content_policy: ContentPolicy,
+    /// The format of the entries of the table
+    pub format_policy: FormatPolicy,
+    /// The syntax that the entries are read with
+    pub syntax_policy: SyntaxPolicy,
+    /// What to do with the objects of the entries that repeat a key
+    pub duplicate_key_policy: DuplicateKeyPolicy,
+    /// How the floats of the entries are written
+    pub float_policy: FloatPolicy,
+    /// Whether the entries written in json5 are loaded, and how they are
+    /// written back
+    #[cfg(feature = "json5")]
+    pub json5_policy: Json5Policy,
+    /// Whether a failed write back can leave some entries written and others
+    /// not
+    pub atomicity_policy: AtomicityPolicy,
+    /// Whether the writes of the table are synced to durable storage
+    pub durability_policy: DurabilityPolicy,
+    /// How to treat hidden files
+    pub hidden_file_policy: HiddenFilePolicy,
+    /// How to treat the entries whose files are too big
+    pub size_policy: SizePolicy,
+    /// How to treat the entries that are symbolic links
+    pub symlink_policy: SymlinkPolicy,
+    /// Whether old versions of the entries are kept
+    pub history_policy: HistoryPolicy,
+    /// Whether the mutations of the table are logged
+    pub audit_policy: AuditPolicy,
+    /// How writes of an entry by several instances of the table are reconciled
+    pub concurrency_policy: ConcurrencyPolicy,
+    /// What a write back does with the entries that were edited elsewhere
+    pub external_edit_policy: ExternalEditPolicy,
+    /// How the numeric ids of the elements are turned into keys
+    pub id_policy: IdPolicy,
+    /// The unicode normalization of the keys
+    pub normalization_policy: NormalizationPolicy,
+    /// What to do with the keys that are too long to be file names
+    pub long_key_policy: LongKeyPolicy,
+    /// The permissions of the files and directories created by the table,
+    /// which are left to the platform defaults when there are none
+    pub permissions: Option<TablePermissions>,
+}
+
+impl TableMetadata {
+    /// Serialize an element with the format and float policies of the table
+    pub(crate) fn to_vec<T: Serialize>(self, value: &T) -> serde_json::Result<Vec<u8>> {
+        match (self.format_policy, self.float_policy) {
+            (format, FloatPolicy::ShortestRoundTrip) => format.to_vec(value),
+            (FormatPolicy::Compact, floats) => floats::to_vec(value, CompactFormatter, floats),
+            (FormatPolicy::Canonical, floats) => canonical::to_vec(value, floats),
+            (FormatPolicy::Pretty | FormatPolicy::Preserve, floats) => {
+                floats::to_vec(value, PrettyFormatter::new(), floats)
+            }
+        }
+    }
+
+    /// Serialize an element into a writer like `to_vec`, without holding its
+    /// serialization in memory. The canonical format needs the whole element,
+    /// so it's serialized in memory first
+    pub(crate) fn to_writer<W: io::Write, T: Serialize>(
+        self,
+        mut writer: W,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        match (self.format_policy, self.float_policy) {
+            (FormatPolicy::Compact, floats) => {
+                floats::to_writer(writer, value, CompactFormatter, floats)
+            }
+            (FormatPolicy::Pretty | FormatPolicy::Preserve, floats) => {
+                floats::to_writer(writer, value, PrettyFormatter::new(), floats)
+            }
+            (FormatPolicy::Canonical, _) => writer
+                .write_all(&self.to_vec(value)?)
+                .map_err(serde_json::Error::io),
+        }
+    }
+
+    /// Deserialize an element with the syntax policy of the table, which is
+    /// what loads do without migrations. Also gives whether the element was
+    /// written in another syntax that is preserved until it changes
+    pub(crate) fn parse<T: DeserializeOwned>(self, bytes: &[u8]) -> serde_json::Result<(T, bool)> {
+        // entries that aren't json are given another chance as jsonc
+        let (info, preserved) = match serde_json::from_slice(bytes) {
+            Ok(info) => (Ok(info), false),
+            Err(e) => match self.syntax_policy {
+                SyntaxPolicy::Jsonc => match serde_json::from_slice(&jsonc::strip(bytes)) {
+                    Ok(info) => (Ok(info), true),
+                    Err(_) => (Err(e), false),
+                },
+                SyntaxPolicy::Json => (Err(e), false),
+            },
+        };
+        // and then as json5
+        #[cfg(feature = "json5")]
+        let (info, preserved) = match (info, self.json5_policy) {
+            (Err(e), Json5Policy::Disabled) => (Err(e), false),
+            (Err(e), policy) => match crate::json5_to_json(bytes) {
+                Some(json) => (
+                    serde_json::from_slice(&json),
+                    policy == Json5Policy::Preserve,
+                ),
+                None => (Err(e), false),
+            },
+            (info, _) => (info, preserved),
+        };
+        Ok((info?, preserved))
+    }
 }
 
 /// A builder that creates new tables and opens existing tables.
@@ -72,82 +488,481 @@ pub struct TableMetadata {
 pub struct TableBuilder<T> {
     data: PhantomData<T>,
     dir: PathBuf,
-    metadata: TableMetadata,
+    overrides: MetadataOverrides,
+    schema: Option<String>,
+    options: LoadOptions,
+    unique_constraints: Vec<Unique<T>>,
+    expiry: Option<Expiry<T>>,
+}
+
+/// A policy change made through a builder
+type MetadataOverride = Box<dyn Fn(&mut TableMetadata) + Send + Sync>;
+
+/// The policy changes made through a builder, in order. They are applied on
+/// top of the metadata stored in the table when loading, and on top of the
+/// default metadata when building
+#[derive(Default)]
+struct MetadataOverrides(Vec<MetadataOverride>);
+
+impl MetadataOverrides {
+    fn push<F: Fn(&mut TableMetadata) + Send + Sync + 'static>(&mut self, f: F) {
+        self.0.push(Box::new(f));
+    }
+
+    fn apply(&self, mut metadata: TableMetadata) -> TableMetadata {
+        self.0.iter().for_each(|f| f(&mut metadata));
+        metadata
+    }
+}
+
+impl Debug for MetadataOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} overrides", self.0.len())
+    }
+}
+
+/// A callback told how many entries have been loaded out of the total
+pub(crate) struct Progress(pub(crate) Box<dyn Fn(usize, usize) + Send + Sync>);
+
+impl Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "progress callback")
+    }
+}
+
+/// The options of a builder that only affect how a table is loaded
+#[derive(Debug, Default)]
+pub(crate) struct LoadOptions {
+    /// Migrations applied to the entries before deserializing them
+    pub(crate) migrations: Option<Migrations>,
+    /// Whether to deserialize the files of read only tables from memory maps
+    pub(crate) mmap: bool,
+    /// A glob that the keys of the loaded entries must match
+    pub(crate) key_filter: Option<String>,
+    /// The only keys to load, instead of every entry
+    pub(crate) only_keys: Option<Vec<String>>,
+    /// The most entries that can be read
+    pub(crate) max_entries: Option<usize>,
+    /// Called as the entries are loaded
+    pub(crate) progress: Option<Progress>,
+    /// When set, the load is cancelled
+    pub(crate) cancel: Option<Arc<AtomicBool>>,
 }
 
 impl<T> TableBuilder<T> {
-    /// Create a new tableBuilder from a directory
-    /// ## Panics
-    /// - if dir can't be converted into a string
+    /// Create a new tableBuilder from a directory, given as anything that can
+    /// be seen as a path, such as `&str`, `Path` or `PathBuf`
     pub fn new<Q: AsRef<Path>>(dir: Q) -> Self {
         Self {
             data: PhantomData,
             dir: dir.as_ref().to_path_buf(),
-            metadata: TableMetadata {
-                rw_policy: RWPolicy::Write(WriteType::Automatic),
-                extension_policy: ExtensionPolicy::IgnoreNonJson,
-                content_policy: ContentPolicy::PromoteSerdeErrors,
-            },
+            overrides: MetadataOverrides::default(),
+            schema: None,
+            options: LoadOptions::default(),
+            unique_constraints: Vec::new(),
+            expiry: None,
         }
     }
 
     /// Set the writeback to be manual
     pub fn set_manual_write(mut self) -> Self {
-        self.metadata.rw_policy = RWPolicy::Write(WriteType::Manual);
+        self.overrides
+            .push(|metadata| metadata.rw_policy = RWPolicy::Write(WriteType::Manual));
         self
     }
 
     /// Set the writeback to be automatic on drops
     pub fn set_auto_write(mut self) -> Self {
-        self.metadata.rw_policy = RWPolicy::Write(WriteType::Automatic);
+        self.overrides
+            .push(|metadata| metadata.rw_policy = RWPolicy::Write(WriteType::Automatic));
         self
     }
 
     /// Set the table so that it won't be written over
     pub fn set_read_only(mut self) -> Self {
-        self.metadata.rw_policy = RWPolicy::ReadOnly;
+        self.overrides
+            .push(|metadata| metadata.rw_policy = RWPolicy::ReadOnly);
         self
     }
 
     /// Set the table so that non json files in the table's directory provoke
     /// an error on loading
     pub fn set_read_non_json_is_error(mut self) -> Self {
-        self.metadata.extension_policy = ExtensionPolicy::OnlyJsonFiles;
+        self.overrides
+            .push(|metadata| metadata.extension_policy = ExtensionPolicy::OnlyJsonFiles);
         self
     }
 
     /// When a read file does **not** contain a valid json for the type T just
     /// ignore it
     pub fn set_ignore_de_errors(mut self) -> Self {
-        self.metadata.content_policy = ContentPolicy::IgnoreSerdeErrors;
+        self.overrides
+            .push(|metadata| metadata.content_policy = ContentPolicy::IgnoreSerdeErrors);
+        self
+    }
+
+    /// Write the entries as compact json, without any whitespace
+    pub fn set_compact_format(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.format_policy = FormatPolicy::Compact);
+        self
+    }
+
+    /// Write the entries as canonical json, as defined by RFC 8785
+    pub fn set_canonical_format(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.format_policy = FormatPolicy::Canonical);
+        self
+    }
+
+    /// Keep the formatting and comments of the entries, only replacing the
+    /// values that change
+    pub fn set_preserve_format(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.format_policy = FormatPolicy::Preserve);
+        self
+    }
+
+    /// Set how the floats of the entries are written
+    pub fn set_float_policy(mut self, policy: FloatPolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.float_policy = policy);
         self
     }
 
-    /// Load an existing table
+    /// Set how the numeric ids of the elements are turned into keys
+    pub fn set_id_policy(mut self, policy: IdPolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.id_policy = policy);
+        self
+    }
+
+    /// Set the unicode normalization of the keys
+    pub fn set_normalization_policy(mut self, policy: NormalizationPolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.normalization_policy = policy);
+        self
+    }
+
+    /// Store the entries whose keys are longer than `max` bytes under a hash
+    /// of the key, so that they don't exceed the limits of the file system
+    pub fn set_long_key_limit(mut self, max: usize) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.long_key_policy = LongKeyPolicy::Hash(max));
+        self
+    }
+
+    /// Write back every entry to a staging item before moving any of them
+    /// into place, so that failed write backs leave the table as it was
+    pub fn set_atomic_write_back(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.atomicity_policy = AtomicityPolicy::WholeTable);
+        self
+    }
+
+    /// Set how the writes of the table are synced to durable storage
+    pub fn set_durability(mut self, durability: DurabilityPolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.durability_policy = durability);
+        self
+    }
+
+    /// Set the unix modes of the files and directories created by the table
+    pub fn set_permissions(mut self, file_mode: u32, dir_mode: u32) -> Self {
+        self.overrides.push(move |metadata| {
+            metadata.permissions = Some(TablePermissions {
+                file_mode,
+                dir_mode,
+            })
+        });
+        self
+    }
+
+    /// Skip the hidden files in the table's directory, whatever their extension
+    pub fn set_skip_hidden(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.hidden_file_policy = HiddenFilePolicy::Skip);
+        self
+    }
+
+    /// Set how to treat the entries whose files are too big
+    pub fn set_size_policy(mut self, size_policy: SizePolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.size_policy = size_policy);
+        self
+    }
+
+    /// Set how to treat the entries that are symbolic links
+    pub fn set_symlink_policy(mut self, symlink_policy: SymlinkPolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.symlink_policy = symlink_policy);
+        self
+    }
+
+    /// Keep up to `versions` old versions of every entry
+    pub fn set_history(mut self, versions: usize) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.history_policy = HistoryPolicy::KeepVersions(versions));
+        self
+    }
+
+    /// Log every mutation of the table in its directory
+    pub fn set_audit_log(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.audit_policy = AuditPolicy::AppendLog);
+        self
+    }
+
+    /// Keep a version for every entry, to update them with `update_if_version`
+    pub fn set_optimistic_concurrency(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.concurrency_policy = ConcurrencyPolicy::Optimistic);
+        self
+    }
+
+    /// Choose what a write back does with the entries that were edited
+    /// elsewhere
+    pub fn set_external_edit_policy(mut self, policy: ExternalEditPolicy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.external_edit_policy = policy);
+        self
+    }
+
+    /// Fail to load the entries with objects that repeat a key, instead of
+    /// keeping the last value of the key
+    pub fn set_reject_duplicate_keys(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.duplicate_key_policy = DuplicateKeyPolicy::Error);
+        self
+    }
+
+    /// Allow comments and trailing commas in the entries, as in jsonc
+    pub fn set_lenient_syntax(mut self) -> Self {
+        self.overrides
+            .push(|metadata| metadata.syntax_policy = SyntaxPolicy::Jsonc);
+        self
+    }
+
+    /// Load the entries written in json5 too, choosing how they are written
+    /// back
+    #[cfg(feature = "json5")]
+    pub fn set_json5_policy(mut self, policy: Json5Policy) -> Self {
+        self.overrides
+            .push(move |metadata| metadata.json5_policy = policy);
+        self
+    }
+
+    /// Set all the policies at once, replacing the ones set before in the
+    /// builder and the ones stored in the table
+    pub fn with_metadata(mut self, metadata: TableMetadata) -> Self {
+        self.overrides.push(move |current| *current = metadata);
+        self
+    }
+
+    /// The directory of the table
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The policies set in the builder, applied on top of the default ones.
+    /// When loading a table they are applied on top of the stored ones instead
+    pub fn metadata(&self) -> TableMetadata {
+        self.overrides.apply(TableMetadata::default())
+    }
+
+    /// Record a schema version in the table when it's built, and check that
+    /// the table was built with the same version when it's loaded
+    pub fn set_schema_version<S: Into<String>>(mut self, version: S) -> Self {
+        self.schema = Some(version.into());
+        self
+    }
+
+    /// Same as `set_schema_version` but using a fingerprint of the json
+    /// schema of `T` as the version, so adding, removing or retyping its
+    /// fields changes it. The names of the types are part of the schema, so
+    /// renaming them changes it too
+    #[cfg(feature = "schemars")]
+    pub fn set_type_fingerprint(mut self) -> Self
+    where
+        T: schemars::JsonSchema,
+    {
+        let schema = schemars::schema_for!(T);
+        let hash = crate::long_keys::stable_hash(&schema.as_value().to_string());
+        self.schema = Some(format!("schema {hash:016x}"));
+        self
+    }
+
+    /// Upgrade the entries written with older versions of `T` with a registry
+    /// of migrations when loading the table
+    pub fn set_migrations(mut self, migrations: Migrations) -> Self {
+        self.options.migrations = Some(migrations);
+        self
+    }
+
+    /// Deserialize the files from memory maps instead of reading them, which
+    /// lowers the peak memory and speeds up cold loads of big tables. It only
+    /// applies to read only tables kept in a local directory, the rest read
+    /// their files as usual. The files must not be modified while the table
+    /// is loading
+    #[cfg(feature = "mmap")]
+    pub fn set_mmap(mut self) -> Self {
+        self.options.mmap = true;
+        self
+    }
+
+    /// Only load the entries whose key matches `pattern`, a glob in which `*`
+    /// matches any run of characters and `?` any single character, such as
+    /// `2024-*`. The other files are not even read
+    pub fn filter_keys<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.options.key_filter = Some(pattern.into());
+        self
+    }
+
+    /// Only load the entries with these keys, without even listing the rest.
+    /// Loading fails if some of them doesn't exist
+    pub fn only_keys<K: AsRef<str>>(mut self, keys: &[K]) -> Self {
+        self.options.only_keys = Some(keys.iter().map(|key| key.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Fail instead of reading more than `max_entries` entries, to guard
+    /// against loading a huge directory by mistake
+    pub fn set_max_entries(mut self, max_entries: usize) -> Self {
+        self.options.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Call `progress` with the number of entries loaded and the total, once
+    /// the directory has been scanned and then after loading every entry.
+    /// Useful to show a progress bar when loading big tables
+    pub fn on_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.options.progress = Some(Progress(Box::new(progress)));
+        self
+    }
+
+    /// Check `cancel` before loading every entry, and give up on the load
+    /// when it's set, so that long loads can be aborted from other threads
+    pub fn set_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.options.cancel = Some(cancel);
+        self
+    }
+
+    /// Set the rule by which stale elements are removed. See `Table::expire_now`
+    pub fn expiry(mut self, expiry: Expiry<T>) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Enforce that the field called `name`, as extracted by `extract`, is
+    /// unique among the elements of the table. See `Table::unique`
+    pub fn unique<S, F>(mut self, name: S, extract: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&T) -> String + Send + Sync + 'static,
+    {
+        self.unique_constraints.push(Unique::new(name, extract));
+        self
+    }
+
+    /// Load an existing table. The policies set in the builder are applied on
+    /// top of the ones stored in the table when it was created
     ///
     /// # Errors
     /// 1. Whenever there's a file in the directory which you don't have
-    /// permission to read, or is not a file or directory
+    ///    permission to read, or is not a file or directory
     /// 2. Couldn't open a file with the required permissions
     /// 3. There is a deserialization error and the policy was `PromoteSerdeErrors`
     /// 4. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
+    /// 5. The stored policies can't be read
+    /// 6. The table was built with a different schema version than the one set
+    /// 7. The loaded elements violate a unique constraint
+    /// 8. Some of the keys set with `only_keys` doesn't exist
+    /// 9. There are more entries than the maximum set
+    /// 10. The load was cancelled
+    /// 11. Stale elements can't be removed, when removing them on load
     pub fn load(self) -> Result<Table<T>, TableError>
     where
         T: Serialize + DeserializeOwned,
     {
-        Table::load(&self.dir, Some(self.metadata))
+        if let Some(schema) = &self.schema {
+            schema::check_schema(&self.dir, schema)?;
+        }
+        let metadata = config::read_metadata(&self.dir)?.unwrap_or_default();
+        let metadata = self.overrides.apply(metadata);
+        let mut table = Table::load_with(&self.dir, metadata, &self.options)?;
+        for constraint in self.unique_constraints {
+            table.add_unique(constraint)?;
+        }
+        if let Some(expiry) = self.expiry {
+            let on_load = expiry.on_load;
+            table.set_expiry(expiry);
+            if on_load {
+                table.expire_now()?;
+            }
+        }
+        Ok(table)
+    }
+
+    /// Open an existing table lazily, reading only the names of its entries.
+    /// The elements are read when accessed and kept in memory up to `budget`
+    /// bytes of json. Migrations and unique constraints are not applied to
+    /// lazy tables
+    ///
+    /// # Errors
+    /// 1. The names of the entries can't be read
+    /// 2. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
+    /// 3. The stored policies can't be read
+    /// 4. The table was built with a different schema version than the one set
+    pub fn load_lazy(self, budget: usize) -> Result<LazyTable<T>, TableError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some(schema) = &self.schema {
+            schema::check_schema(&self.dir, schema)?;
+        }
+        let metadata = config::read_metadata(&self.dir)?.unwrap_or_default();
+        let metadata = self.overrides.apply(metadata);
+        LazyTable::with_storage(FsStorage::new(&self.dir), metadata, budget)
     }
 
-    /// Create a new table. In order to do so a write policy must be in place
+    /// Create a new table. In order to do so a write policy must be in place.
+    /// The policies are stored in the table, so that they are used by default
+    /// when it's loaded
     ///
     /// # Errors
     /// 1. There was already a table in that directory
     /// 2. Couldn't create a path to the table
+    /// 3. Couldn't record the schema version
     pub fn build(self) -> Result<Table<T>, TableBuilderError>
     where
         T: Serialize + DeserializeOwned,
     {
-        Table::new(&self.dir, self.metadata)
+        let metadata = self.overrides.apply(TableMetadata::default());
+        let mut table = Table::new(&self.dir, metadata)?;
+        if let Some(schema) = &self.schema {
+            schema::write_schema(&self.dir, schema)?;
+        }
+        table.unique_constraints = self.unique_constraints;
+        table.expiry = self.expiry;
+        Ok(table)
+    }
+
+    /// Load the table if its directory exists, or create it otherwise
+    ///
+    /// # Errors
+    /// 1. Whenever there's an error with the `load` or the `build`
+    pub fn open_or_create(self) -> Result<Table<T>, TableError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if self.dir.exists() {
+            self.load()
+        } else {
+            Ok(self.build()?)
+        }
     }
 }
 
@@ -156,11 +971,11 @@ impl<T> Default for TableBuilder<T> {
         Self {
             data: PhantomData,
             dir: "".into(),
-            metadata: TableMetadata {
-                rw_policy: RWPolicy::Write(WriteType::Automatic),
-                extension_policy: ExtensionPolicy::IgnoreNonJson,
-                content_policy: ContentPolicy::PromoteSerdeErrors,
-            },
+            overrides: MetadataOverrides::default(),
+            schema: None,
+            options: LoadOptions::default(),
+            unique_constraints: Vec::new(),
+            expiry: None,
         }
     }
 }
@@ -0,0 +1,79 @@
+use crate::FloatPolicy;
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// Serialize an element as canonical json, following RFC 8785: no whitespace,
+/// object keys sorted by their utf-16 code units, and numbers written as
+/// ECMAScript writes doubles, once rounded as the float policy says
+pub(crate) fn to_vec<T: Serialize>(value: &T, floats: FloatPolicy) -> serde_json::Result<Vec<u8>> {
+    let mut value = serde_json::to_value(value)?;
+    floats.round_value(&mut value);
+    let mut canonical = Vec::new();
+    write(&value, &mut canonical)?;
+    Ok(canonical)
+}
+
+fn write(value: &Value, out: &mut Vec<u8>) -> serde_json::Result<()> {
+    match value {
+        Value::Null | Value::Bool(_) => out.extend(serde_json::to_vec(value)?),
+        // the escapes of serde_json are the ones required by the RFC
+        Value::String(string) => out.extend(serde_json::to_vec(string)?),
+        Value::Number(number) => out.extend(number_to_string(number).bytes()),
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write(item, out)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(map) => {
+            let mut fields: Vec<_> = map.iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push(b'{');
+            for (i, (key, value)) in fields.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                out.extend(serde_json::to_vec(key)?);
+                out.push(b':');
+                write(value, out)?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+/// A number as written by ECMAScript's `Number.prototype.toString`, which
+/// treats every number as a double
+fn number_to_string(number: &Number) -> String {
+    // numbers of serde_json are always finite
+    let double = number.as_f64().unwrap_or_default();
+    if double == 0.0 {
+        return "0".to_string();
+    }
+    let sign = if double < 0.0 { "-" } else { "" };
+    // the shortest digits that round trip, and the exponent of the first one
+    let scientific = format!("{:e}", double.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap_or_default() + 1;
+    let written = if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        format!("{integer}.{fraction}")
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(-n as usize))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        let exponent_sign = if n > 0 { "+" } else { "-" };
+        format!("{first}{point}{rest}e{exponent_sign}{}", (n - 1).abs())
+    };
+    format!("{sign}{written}")
+}
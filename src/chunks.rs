@@ -0,0 +1,63 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{ops::Bound, sync::Arc};
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Go through the elements in batches of at most `chunk_size`, in the
+    /// order of their keys, giving `f` each batch to change. When `flush`,
+    /// the table is written back before the first batch and every batch is
+    /// written right after `f` is done with it, so a long transformation never
+    /// holds more than a batch of unwritten changes. Frozen and evicted
+    /// elements are skipped
+    ///
+    /// # Errors
+    /// 1. When flushing, whenever there is an error with the write backs
+    ///
+    /// # Panics
+    /// When `chunk_size` is 0
+    pub fn process_in_chunks<F>(
+        &mut self,
+        chunk_size: usize,
+        flush: bool,
+        mut f: F,
+    ) -> Result<(), TableError>
+    where
+        F: FnMut(&mut [(&str, &mut T)]),
+    {
+        assert!(chunk_size > 0, "chunks must hold some element");
+        if flush {
+            self.write_back()?;
+        }
+        let keys: Vec<Arc<str>> = self
+            .content
+            .keys()
+            .filter(|key| !self.frozen.contains(&***key))
+            .cloned()
+            .collect();
+        for chunk in keys.chunks(chunk_size) {
+            // chunks are never empty, therefore the unwraps
+            let first = chunk.first().unwrap();
+            let last = chunk.last().unwrap();
+            let range = (Bound::Included(&**first), Bound::Included(&**last));
+            let frozen = &self.frozen;
+            let mut batch: Vec<_> = self
+                .content
+                .range_mut::<str, _>(range)
+                .filter(|(key, _)| !frozen.contains(&***key))
+                .map(|(key, element)| (&**key, &mut element.info))
+                .collect();
+            f(&mut batch);
+            self.is_modified = true;
+            if flush {
+                if let Some(staged) = self.stage_range(None, false, range)? {
+                    self.commit_staged(staged)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{Storage, TableKey, TypedTable};
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::Bound;
+
+/// Separates the parts of a composite key in its file name
+const SEPARATOR: &str = "~";
+
+/// Escape the separator and the escape character itself, so that parts that
+/// contain them can be told apart when the key is split
+fn escape(part: &str) -> String {
+    part.replace('%', "%25").replace(SEPARATOR, "%7E")
+}
+
+/// Undo `escape`, or `None` if the part has escapes that `escape` never writes
+fn unescape(part: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(part.len());
+    let mut rest = part;
+    while let Some(i) = rest.find('%') {
+        unescaped.push_str(&rest[..i]);
+        match rest.get(i..i + 3)? {
+            "%25" => unescaped.push('%'),
+            "%7E" => unescaped.push_str(SEPARATOR),
+            _ => return None,
+        }
+        rest = &rest[i + 3..];
+    }
+    unescaped.push_str(rest);
+    Some(unescaped)
+}
+
+/// Keys made of several parts, that can be looked up by their first part
+pub trait CompositeKey: TableKey {
+    /// The first part of the key
+    type Head: TableKey;
+}
+
+macro_rules! tuple_keys {
+    ($head:ident $(, $part:ident)+) => {
+        impl<$head: TableKey $(, $part: TableKey)+> TableKey for ($head, $($part),+) {
+            /// The parts are escaped and joined by `~`, as in `tenant~name`
+            #[allow(non_snake_case)]
+            fn to_key(&self) -> String {
+                let ($head, $($part),+) = self;
+                [escape(&$head.to_key()) $(, escape(&$part.to_key()))+].join(SEPARATOR)
+            }
+
+            #[allow(non_snake_case)]
+            fn from_key(key: &str) -> Option<Self> {
+                let mut parts = key.split(SEPARATOR);
+                let $head = $head::from_key(&unescape(parts.next()?)?)?;
+                $(let $part = $part::from_key(&unescape(parts.next()?)?)?;)+
+                match parts.next() {
+                    Some(_) => None,
+                    None => Some(($head, $($part),+)),
+                }
+            }
+        }
+
+        impl<$head: TableKey $(, $part: TableKey)+> CompositeKey for ($head, $($part),+) {
+            type Head = $head;
+        }
+    };
+}
+
+tuple_keys!(A, B);
+tuple_keys!(A, B, C);
+tuple_keys!(A, B, C, D);
+
+impl<K, T, S> TypedTable<K, T, S>
+where
+    K: CompositeKey,
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// The keys and information of the elements whose key starts with `head`,
+    /// such as all the entries of a tenant
+    pub fn with_prefix(&self, head: &K::Head) -> impl Iterator<Item = (K, &T)> {
+        let prefix = format!("{}{SEPARATOR}", escape(&head.to_key()));
+        self.table()
+            .content
+            .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(&prefix))
+            .filter_map(|(key, element)| Some((K::from_key(key)?, &element.info)))
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    content_hash, staging_name, unix_millis, ConcurrencyPolicy, Storage, Table, TableError,
+    TableMetadata, RESERVED_PREFIX,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, time::Duration};
+
+/// The name of the item in which the version of an entry is kept
+fn version_name(metadata: &TableMetadata, key: &str) -> String {
+    format!("{RESERVED_PREFIX}_version.{}", metadata.item_stem(key))
+}
+
+/// The name of the item that is held while the version of an entry is checked
+/// and replaced. It holds the id of the process that took it and when, in
+/// milliseconds since the unix epoch
+fn lock_name(metadata: &TableMetadata, key: &str) -> String {
+    format!("{RESERVED_PREFIX}_lock.{}", metadata.item_stem(key))
+}
+
+/// The version of an entry as stored, which is 0 if it was never written with
+/// the `Optimistic` concurrency policy
+pub(crate) fn read_version<S: Storage>(
+    storage: &S,
+    metadata: &TableMetadata,
+    key: &str,
+) -> io::Result<u64> {
+    match storage.read(&version_name(metadata, key)) {
+        Ok(content) => String::from_utf8_lossy(&content)
+            .trim()
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Store the version that follows both the stored one and the one that is
+/// known, returning it. The version is staged and renamed into place so that
+/// other instances never read it half written
+pub(crate) fn bump_version<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    key: &str,
+    known: u64,
+) -> io::Result<u64> {
+    let version = read_version(storage, metadata, key)?.max(known) + 1;
+    let name = version_name(metadata, key);
+    let staging = staging_name(&name);
+    storage.write(&staging, version.to_string().as_bytes())?;
+    storage.rename(&staging, &name)?;
+    Ok(version)
+}
+
+/// Remove the version of an entry, if it has one
+pub(crate) fn remove_version<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    key: &str,
+) -> io::Result<()> {
+    match storage.delete(&version_name(metadata, key)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Move the version of an entry to a new key, if it has one
+pub(crate) fn rename_version<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    from: &str,
+    to: &str,
+) -> io::Result<()> {
+    match storage.rename(&version_name(metadata, from), &version_name(metadata, to)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// The version of an element as of its last load or write. Versions are
+    /// only kept with the `Optimistic` concurrency policy, otherwise every
+    /// element is at version 0
+    pub fn version(&self, key: &str) -> Option<u64> {
+        self.get_element(key).map(|element| element.version)
+    }
+
+    /// Replace an element and write it right away, but only if its stored
+    /// version is still `version`, so that changes made by other instances of
+    /// the table are never overwritten. The check and the write are done while
+    /// holding a lock item, and an instance that finds it held gets a conflict.
+    /// Locks left behind by processes that died are removed with
+    /// `clear_stale_locks`. Without the `Optimistic` concurrency policy the
+    /// version isn't checked
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element doesn't exist
+    /// 3. The element is frozen
+    /// 4. The stored version isn't `version` or another instance is updating it
+    /// 5. The new element violates a unique constraint
+    /// 6. There are problems with serialization or writing
+    pub fn update_if_version(
+        &mut self,
+        key: &str,
+        version: u64,
+        info: T,
+    ) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let key = &*self.metadata.normalization_policy.normalize(key);
+        self.restore(key)?;
+        if !self.content.contains_key(key) {
+            return Err(TableError::PopError(key.to_string()));
+        }
+        self.check_frozen(key)?;
+        if self.metadata.concurrency_policy == ConcurrencyPolicy::LastWriteWins {
+            return self.update_unchecked(key, None, info);
+        }
+        let lock = lock_name(&self.metadata, key);
+        match self.storage.create(&lock) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let stored = read_version(&self.storage, &self.metadata, key)?;
+                return Err(TableError::ConflictError(key.to_string(), stored));
+            }
+            result => result?,
+        }
+        let owner = format!("{} {}", std::process::id(), unix_millis());
+        if let Err(e) = self.storage.write(&lock, owner.as_bytes()) {
+            let _ = self.storage.delete(&lock);
+            return Err(e.into());
+        }
+        let result = match read_version(&self.storage, &self.metadata, key) {
+            Ok(stored) if stored != version => {
+                Err(TableError::ConflictError(key.to_string(), stored))
+            }
+            Ok(_) => self.update_unchecked(key, Some(version), info),
+            Err(e) => Err(e.into()),
+        };
+        // the lock is released whatever happened, but its error is only
+        // reported when the update went well
+        let released = self.storage.delete(&lock);
+        result?;
+        Ok(released?)
+    }
+
+    /// Remove the locks of `update_if_version` taken at least `age` ago,
+    /// returning how many were removed. A process that dies while updating an
+    /// element leaves its lock behind, and until it's removed every update of
+    /// the element is a conflict. Locks are held for a single write, so
+    /// any age well above the time a write takes is safe, while removing a lock
+    /// that is still held lets two instances update the element at once.
+    /// Locks whose owner can't be read are always removed
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The locks can't be listed, read or removed
+    pub fn clear_stale_locks(&mut self, age: Duration) -> Result<usize, TableError> {
+        self.mod_permissions()?;
+        let prefix = format!("{RESERVED_PREFIX}_lock.");
+        let now = unix_millis();
+        let mut cleared = 0;
+        for name in self.storage.list()? {
+            if !name.starts_with(&prefix) {
+                continue;
+            }
+            let taken_at = match self.storage.read(&name) {
+                Ok(owner) => String::from_utf8_lossy(&owner)
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|taken_at| taken_at.parse::<u64>().ok()),
+                // released in between
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if taken_at
+                .is_none_or(|taken_at| now.saturating_sub(taken_at) >= age.as_millis() as u64)
+            {
+                match self.storage.delete(&name) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => cleared += 1,
+                }
+            }
+        }
+        Ok(cleared)
+    }
+
+    /// Replace and write an element whose version, if any, has been checked
+    fn update_unchecked(
+        &mut self,
+        key: &str,
+        version: Option<u64>,
+        info: T,
+    ) -> Result<(), TableError> {
+        let old_info = std::mem::replace(&mut self[key].info, info);
+        if let Err(e) = self.check_unique() {
+            self[key].info = old_info;
+            return Err(e);
+        }
+        let name = self.metadata.entry_name(key);
+        let content = self.metadata.to_vec(&self[key].info)?;
+        self.storage.write(&name, &content)?;
+        self.metrics.bytes_written += content.len() as u64;
+        let new_version = match version {
+            Some(version) => bump_version(&mut self.storage, &self.metadata, key, version)?,
+            None => 0,
+        };
+        // the element has just been checked, therefore the unwrap
+        let element = self.content.get_mut(key).unwrap();
+        element.written = Some(content_hash(&content));
+        element.version = new_version;
+        self.metadata
+            .durability_policy
+            .apply(&self.storage, [name.as_str()])?;
+        self.audit(serde_json::json!({"op": "update", "key": key, "version": new_version}))
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{TableError, TableMetadata, RESERVED_PREFIX};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file in which the policies of a table are stored
+pub(crate) fn metadata_name() -> String {
+    format!("{RESERVED_PREFIX}.json")
+}
+
+/// The path of the file in which the policies of a table are stored
+pub(crate) fn metadata_path<Q: AsRef<Path>>(dir: Q) -> PathBuf {
+    dir.as_ref().join(metadata_name())
+}
+
+/// Store the policies of a table
+pub(crate) fn write_metadata<Q: AsRef<Path>>(dir: Q, metadata: &TableMetadata) -> io::Result<()> {
+    fs::write(metadata_path(dir), serde_json::to_vec_pretty(metadata)?)
+}
+
+/// Read the stored policies of a table, if there are any
+pub(crate) fn read_metadata<Q: AsRef<Path>>(dir: Q) -> Result<Option<TableMetadata>, TableError> {
+    match fs::read(metadata_path(dir)) {
+        Ok(metadata) => Ok(Some(serde_json::from_slice(&metadata)?)),
+        Err(e) => match e.kind() {
+            io::ErrorKind::NotFound => Ok(None),
+            _ => Err(e.into()),
+        },
+    }
+}
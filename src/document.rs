@@ -0,0 +1,99 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Get the json value that a [json pointer](https://www.rfc-editor.org/rfc/rfc6901)
+    /// such as `"/nested/field"` points to inside an element. It returns
+    /// `None` when either the element or the pointed value don't exist
+    ///
+    /// # Errors
+    /// 1. The element can't be serialized into a json value
+    pub fn get_pointer(
+        &self,
+        entry_name: &str,
+        pointer: &str,
+    ) -> Result<Option<Value>, TableError> {
+        match self.get_element(entry_name) {
+            Some(element) => Ok(serde_json::to_value(&element.info)?
+                .pointer(pointer)
+                .cloned()),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the json value that a json pointer points to for every element of
+    /// the table. The elements in which the pointer doesn't resolve are left out
+    ///
+    /// # Errors
+    /// 1. Some element can't be serialized into a json value
+    pub fn query_pointer(&self, pointer: &str) -> Result<HashMap<&str, Value>, TableError> {
+        let mut query = HashMap::new();
+        for (name, element) in self.all_elements() {
+            if let Some(value) = serde_json::to_value(&element.info)?.pointer(pointer) {
+                query.insert(&**name, value.clone());
+            }
+        }
+        Ok(query)
+    }
+
+    /// Apply a [json patch](https://www.rfc-editor.org/rfc/rfc6902) to an
+    /// element. The patch is applied to the json representation of the element
+    /// and the element is only updated if the result still deserializes into
+    /// `T`, otherwise the element is left untouched
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element is frozen
+    /// 3. The element doesn't exist
+    /// 4. The patch isn't a valid json patch document
+    /// 5. Some operation of the patch fails
+    /// 6. The patched document can't be deserialized into `T`
+    pub fn patch(&mut self, entry_name: &str, json_patch: &Value) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let entry_name = &*self.metadata.normalization_policy.normalize(entry_name);
+        self.check_frozen(entry_name)?;
+        self.restore(entry_name)?;
+        let element = self
+            .content
+            .get_mut(entry_name)
+            .ok_or_else(|| TableError::PopError(entry_name.to_string()))?;
+        let json_patch: json_patch::Patch = serde_json::from_value(json_patch.clone())?;
+        let mut document = serde_json::to_value(&element.info)?;
+        json_patch::patch(&mut document, &json_patch)?;
+        element.info = serde_json::from_value(document)?;
+        self.is_modified = true;
+        Ok(())
+    }
+
+    /// Deep merge a partial json object into an element following
+    /// [json merge patch](https://www.rfc-editor.org/rfc/rfc7386) semantics,
+    /// where `null` members remove fields. As with `patch` the element is only
+    /// updated if the result still deserializes into `T`
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element is frozen
+    /// 3. The element doesn't exist
+    /// 4. The merged document can't be deserialized into `T`
+    pub fn merge_patch(&mut self, entry_name: &str, merge: &Value) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let entry_name = &*self.metadata.normalization_policy.normalize(entry_name);
+        self.check_frozen(entry_name)?;
+        self.restore(entry_name)?;
+        let element = self
+            .content
+            .get_mut(entry_name)
+            .ok_or_else(|| TableError::PopError(entry_name.to_string()))?;
+        let mut document = serde_json::to_value(&element.info)?;
+        json_patch::merge(&mut document, merge);
+        element.info = serde_json::from_value(document)?;
+        self.is_modified = true;
+        Ok(())
+    }
+}
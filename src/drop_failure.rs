@@ -0,0 +1,76 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// A function given the error of a failed write back and the elements that
+/// weren't saved
+type Rescue<T> = dyn FnOnce(TableError, Vec<(String, T)>) + Send + Sync;
+
+/// What happens when the write back of a table with automatic write back fails
+/// as the table is dropped
+#[derive(Default)]
+pub enum DropFailure<T> {
+    /// Panic, unless the thread is already panicking
+    #[default]
+    Panic,
+    /// Log the error and lose the changes. It's logged with tracing when the
+    /// feature is enabled, and to stderr otherwise
+    Log,
+    /// Call a function with the error and the elements of the table, so that
+    /// they can be saved somewhere else
+    Callback(Box<Rescue<T>>),
+}
+
+impl<T> fmt::Debug for DropFailure<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Panic => write!(f, "Panic"),
+            Self::Log => write!(f, "Log"),
+            Self::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Choose what happens when the automatic write back fails as the table
+    /// is dropped
+    pub fn on_drop_failure(&mut self, drop_failure: DropFailure<T>) {
+        self.drop_failure = drop_failure;
+    }
+
+    /// Handle the error of the write back done as the table is dropped
+    pub(crate) fn drop_failed(&mut self, error: TableError) {
+        let content = &mut self.content;
+        std::mem::take(&mut self.drop_failure).handle(error, || {
+            std::mem::take(content)
+                .into_iter()
+                .map(|(key, element)| (key.to_string(), element.info))
+                .collect()
+        });
+    }
+}
+
+impl<T> DropFailure<T> {
+    /// Handle the error of a write back done on drop, getting the unsaved
+    /// elements from `unsaved` only when a callback asks for them
+    pub(crate) fn handle<F>(self, error: TableError, unsaved: F)
+    where
+        F: FnOnce() -> Vec<(String, T)>,
+    {
+        match self {
+            DropFailure::Panic if !std::thread::panicking() => {
+                panic!("The table couldn't be written back: {error}")
+            }
+            DropFailure::Panic | DropFailure::Log => {
+                event!(error, %error, "the table couldn't be written back");
+                #[cfg(not(feature = "tracing"))]
+                eprintln!("The table couldn't be written back: {error}");
+            }
+            DropFailure::Callback(rescue) => rescue(error, unsaved()),
+        }
+    }
+}
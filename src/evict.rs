@@ -0,0 +1,160 @@
+use crate::{
+    concurrency, content_hash, ConcurrencyPolicy, Storage, Table, TableElement, TableError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::{Arc, OnceLock};
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Drop the information of an element from memory while keeping its key in
+    /// the table. It's read again from its file the next time it's accessed by
+    /// key, so elements that differ from their files are written first,
+    /// unless they are frozen. Evicted elements still count in `len`, but the
+    /// iterators, queries and write backs skip them until they are read again,
+    /// while unique constraints, serialization and the rest of the views of
+    /// the whole table read them when needed
+    ///
+    /// # Errors
+    /// 1. The element doesn't exist
+    /// 2. The element must be written and you don't have permission to write
+    /// 3. There are problems with serialization or with the file
+    pub fn evict(&mut self, key: &str) -> Result<(), TableError> {
+        let key = self
+            .metadata
+            .normalization_policy
+            .normalize(key)
+            .into_owned();
+        // evicted elements that were read again are dropped again
+        if let Some(cell) = self.evicted.get_mut(key.as_str()) {
+            cell.take();
+            return Ok(());
+        }
+        let Some(element) = self.content.get(key.as_str()) else {
+            return Err(TableError::MissingKeyError(key));
+        };
+        if !self.frozen.contains(&key) {
+            let content = self.metadata.to_vec(&element.info)?;
+            if element.written != Some(content_hash(&content)) {
+                self.mod_permissions()?;
+                let name = self.metadata.entry_name(&key);
+                self.storage.write(&name, &content)?;
+                self.metrics.bytes_written += content.len() as u64;
+                self.metadata
+                    .durability_policy
+                    .apply(&self.storage, [name.as_str()])?;
+            }
+        }
+        // the element has just been checked, therefore the unwrap
+        let (key, _) = self.content.remove_entry(key.as_str()).unwrap();
+        self.evicted.insert(key, OnceLock::new());
+        Ok(())
+    }
+
+    /// Evict every element that is in memory
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `evict` of an element
+    pub fn evict_all(&mut self) -> Result<(), TableError> {
+        let keys: Vec<_> = self.content.keys().cloned().collect();
+        for key in keys {
+            self.evict(&key)?;
+        }
+        // the ones that were read again without being changed are dropped too
+        for cell in self.evicted.values_mut() {
+            cell.take();
+        }
+        Ok(())
+    }
+
+    /// Whether an element has been evicted and isn't in memory
+    pub fn is_evicted(&self, key: &str) -> bool {
+        let key = self.metadata.normalization_policy.normalize(key);
+        self.evicted
+            .get(key.as_ref())
+            .is_some_and(|cell| cell.get().is_none())
+    }
+
+    /// Read every evicted element again, so that the whole table is in memory
+    ///
+    /// # Errors
+    /// 1. Some element can't be read or deserialized
+    pub fn reload_evicted(&mut self) -> Result<(), TableError> {
+        let keys: Vec<_> = self.evicted.keys().cloned().collect();
+        for key in keys {
+            self.restore(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Bring an evicted element back into the table, if it was evicted
+    pub(crate) fn restore(&mut self, key: &str) -> Result<(), TableError> {
+        let Some(cell) = self.evicted.get_mut(key) else {
+            return Ok(());
+        };
+        let element = match cell.take() {
+            Some(element) => element,
+            None => self.read_element(key)?,
+        };
+        // the key has just been checked, therefore the unwrap
+        let (key, _) = self.evicted.remove_entry(key).unwrap();
+        self.content.insert(key, element);
+        Ok(())
+    }
+
+    /// The keys of every element, the evicted ones too, in order
+    pub(crate) fn all_keys(&self) -> impl Iterator<Item = &Arc<str>> {
+        let mut keys: Vec<_> = self.content.keys().chain(self.evicted.keys()).collect();
+        keys.sort_unstable();
+        keys.into_iter()
+    }
+
+    /// Every element with its key, in order, reading the evicted ones that
+    /// aren't in memory. Evicted elements that can't be read are left out
+    pub(crate) fn all_elements(&self) -> impl Iterator<Item = (&Arc<str>, &TableElement<T>)> {
+        self.all_keys().filter_map(|key| {
+            let element = match self.content.get(key) {
+                Some(element) => element,
+                None => self.evicted_element(key)?,
+            };
+            Some((key, element))
+        })
+    }
+
+    /// An evicted element, reading it if it isn't in memory. Elements that
+    /// can't be read are reported as missing
+    pub(crate) fn evicted_element(&self, key: &str) -> Option<&TableElement<T>> {
+        let cell = self.evicted.get(key)?;
+        if cell.get().is_none() {
+            // whoever else set it in between read the same element
+            let _ = cell.set(self.read_element(key).ok()?);
+        }
+        cell.get()
+    }
+
+    /// Read an element from its file like the table is loaded, but without
+    /// migrations
+    pub(crate) fn read_element(&self, key: &str) -> Result<TableElement<T>, TableError> {
+        let bytes = self.storage.read(&self.metadata.entry_name(key))?;
+        let (info, preserved) = self.metadata.parse(&bytes)?;
+        // preserved entries are taken as already written in the format of the
+        // table, as when they are loaded
+        let written = match preserved {
+            true => content_hash(&self.metadata.to_vec(&info)?),
+            false => content_hash(&bytes),
+        };
+        let version = match self.metadata.concurrency_policy {
+            ConcurrencyPolicy::LastWriteWins => 0,
+            ConcurrencyPolicy::Optimistic => {
+                concurrency::read_version(&self.storage, &self.metadata, key)?
+            }
+        };
+        Ok(TableElement {
+            info,
+            written: Some(written),
+            version,
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+/// When an element becomes stale
+enum ExpiryRule<T> {
+    /// A time after the last modification of its file
+    After(Duration),
+    /// The time given by a function of the element
+    At(Box<dyn Fn(&T) -> SystemTime + Send + Sync>),
+}
+
+/// A rule by which stale elements are removed from a table, for tables of
+/// records that must age out, such as sessions or caches
+pub struct Expiry<T> {
+    rule: ExpiryRule<T>,
+    soft: bool,
+    pub(crate) on_load: bool,
+}
+
+impl<T> Expiry<T> {
+    /// Elements become stale `duration` after their file was last modified.
+    /// Elements in storages that don't keep modification times never expire
+    pub fn after(duration: Duration) -> Self {
+        Self {
+            rule: ExpiryRule::After(duration),
+            soft: false,
+            on_load: false,
+        }
+    }
+
+    /// Elements become stale at the time returned by `expires_at`
+    pub fn at<F>(expires_at: F) -> Self
+    where
+        F: Fn(&T) -> SystemTime + Send + Sync + 'static,
+    {
+        Self {
+            rule: ExpiryRule::At(Box::new(expires_at)),
+            soft: false,
+            on_load: false,
+        }
+    }
+
+    /// Soft pop stale elements instead of popping them
+    pub fn soft(mut self) -> Self {
+        self.soft = true;
+        self
+    }
+
+    /// Remove stale elements as soon as the table is loaded
+    pub fn on_load(mut self) -> Self {
+        self.on_load = true;
+        self
+    }
+}
+
+impl<T> fmt::Debug for Expiry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rule = match &self.rule {
+            ExpiryRule::After(duration) => format!("after {duration:?}"),
+            ExpiryRule::At(_) => "at a time of the element".to_string(),
+        };
+        f.debug_struct("Expiry")
+            .field("rule", &rule)
+            .field("soft", &self.soft)
+            .field("on_load", &self.on_load)
+            .finish()
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Set the rule by which `expire_now` removes stale elements
+    pub fn set_expiry(&mut self, expiry: Expiry<T>) {
+        self.expiry = Some(expiry);
+    }
+
+    /// Pop, or soft pop, the elements that are stale according to the expiry
+    /// rule of the table, returning their keys. Nothing is removed when there
+    /// is no rule, and frozen elements are never removed
+    ///
+    /// # Errors
+    /// 1. The modification times of the files can't be read
+    /// 2. Whenever there's an error with the `pop` or `soft_pop`
+    pub fn expire_now(&mut self) -> Result<Vec<String>, TableError> {
+        let Some(expiry) = &self.expiry else {
+            return Ok(Vec::new());
+        };
+        let now = SystemTime::now();
+        let mut stale = Vec::new();
+        for (key, element) in self.all_elements() {
+            if self.frozen.contains(&**key) {
+                continue;
+            }
+            let expires_at = match &expiry.rule {
+                ExpiryRule::After(duration) => self
+                    .storage
+                    .modified(&self.metadata.entry_name(key))?
+                    .map(|modified| modified + *duration),
+                ExpiryRule::At(expires_at) => Some(expires_at(&element.info)),
+            };
+            if expires_at.is_some_and(|expires_at| expires_at <= now) {
+                stale.push(key.to_string());
+            }
+        }
+        let soft = expiry.soft;
+        for key in &stale {
+            match soft {
+                true => self.soft_pop(key, None)?,
+                false => self.pop(key)?,
+            }
+        }
+        Ok(stale)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{
+    content_hash, ExternalEditPolicy, Storage, Table, TableElement, TableError, TableMetadata,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, io};
+
+/// A function of the key, the element as kept by the table and the element as
+/// edited, that returns the element to write
+type MergeFn<T> = dyn Fn(&str, &T, &T) -> T + Send + Sync;
+
+/// A callback that merges an element, as kept by the table, with the content
+/// that its item was edited to
+pub(crate) struct Merge<T>(Box<MergeFn<T>>);
+
+impl<T> fmt::Debug for Merge<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "merge callback")
+    }
+}
+
+/// Settle the differences between an element that is about to be written as
+/// `content` and its item, when the item was edited since it was last read or
+/// written, as the external edit policy says. Returns whether `content` must
+/// still be written
+pub(crate) fn resolve_edit<T, S>(
+    storage: &S,
+    metadata: &TableMetadata,
+    merge: Option<&Merge<T>>,
+    key: &str,
+    element: &mut TableElement<T>,
+    content: &mut Vec<u8>,
+) -> Result<bool, TableError>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    let policy = metadata.external_edit_policy;
+    // new elements have no content of their own to be edited
+    let Some(written) = element.written else {
+        return Ok(true);
+    };
+    if policy == ExternalEditPolicy::PreferOurs {
+        return Ok(true);
+    }
+    let stored = match storage.read(&metadata.entry_name(key)) {
+        Ok(stored) => stored,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e.into()),
+    };
+    if content_hash(&stored) == written {
+        return Ok(true);
+    }
+    match (policy, merge) {
+        (ExternalEditPolicy::PreferOurs, _) => Ok(true),
+        (ExternalEditPolicy::PreferTheirs, _) => {
+            element.info = serde_json::from_slice(&stored)?;
+            element.written = Some(content_hash(&stored));
+            Ok(false)
+        }
+        (ExternalEditPolicy::Merge, Some(merge)) => {
+            let theirs = serde_json::from_slice(&stored)?;
+            element.info = (merge.0)(key, &element.info, &theirs);
+            *content = metadata.to_vec(&element.info)?;
+            Ok(true)
+        }
+        (ExternalEditPolicy::Error | ExternalEditPolicy::Merge, _) => {
+            Err(TableError::ExternalEditError(key.to_string()))
+        }
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Set the callback that merges the elements whose items were edited
+    /// elsewhere when the external edit policy is `Merge`. It's given the key,
+    /// the element as kept by the table and the element as edited, and returns
+    /// the element to write
+    pub fn set_merge<F>(&mut self, merge: F)
+    where
+        F: Fn(&str, &T, &T) -> T + Send + Sync + 'static,
+    {
+        self.merge = Some(Merge(Box::new(merge)));
+    }
+}
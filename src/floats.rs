@@ -0,0 +1,136 @@
+use crate::FloatPolicy;
+use serde::Serialize;
+use serde_json::{ser::Formatter, Number, Value};
+use std::io;
+
+impl FloatPolicy {
+    /// Round a float as the policy says
+    fn round(self, float: f64) -> f64 {
+        let rounded = match self {
+            FloatPolicy::ShortestRoundTrip => return float,
+            FloatPolicy::MaxPrecision(digits) => {
+                format!("{float:.*e}", usize::from(digits.max(1)) - 1)
+            }
+            FloatPolicy::FixedDecimals(decimals) => format!("{float:.*}", usize::from(decimals)),
+        };
+        rounded.parse().unwrap_or(float)
+    }
+
+    /// Round the floats of a value as the policy says
+    pub(crate) fn round_value(self, value: &mut Value) {
+        match value {
+            Value::Number(number) if number.is_f64() => {
+                if let Some(rounded) = number
+                    .as_f64()
+                    .and_then(|f| Number::from_f64(self.round(f)))
+                {
+                    *number = rounded;
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.round_value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.round_value(item)),
+            _ => {}
+        }
+    }
+}
+
+/// A formatter that rounds the floats that another formatter writes
+struct Rounding<F> {
+    inner: F,
+    policy: FloatPolicy,
+}
+
+impl<F: Formatter> Formatter for Rounding<F> {
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        self.write_f64(writer, value.into())
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        self.inner.write_f64(writer, self.policy.round(value))
+    }
+
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.inner.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.inner.begin_object_key(writer, first)
+    }
+
+    fn end_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object_key(writer)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object_value(writer)
+    }
+}
+
+/// Serialize an element with a formatter, rounding its floats as the policy
+/// says
+pub(crate) fn to_vec<T, F>(
+    value: &T,
+    formatter: F,
+    policy: FloatPolicy,
+) -> serde_json::Result<Vec<u8>>
+where
+    T: Serialize,
+    F: Formatter,
+{
+    let mut content = Vec::new();
+    to_writer(&mut content, value, formatter, policy)?;
+    Ok(content)
+}
+
+/// Serialize an element into a writer like `to_vec`
+pub(crate) fn to_writer<W, T, F>(
+    writer: W,
+    value: &T,
+    formatter: F,
+    policy: FloatPolicy,
+) -> serde_json::Result<()>
+where
+    W: io::Write,
+    T: Serialize,
+    F: Formatter,
+{
+    let formatter = Rounding {
+        inner: formatter,
+        policy,
+    };
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        writer, formatter,
+    ))
+}
@@ -0,0 +1,56 @@
+use crate::{Storage, Table, TableElement, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Make an element read only. Frozen elements can't be gotten mutably,
+    /// patched, updated, popped nor renamed, and write backs, compactions and
+    /// rollbacks skip them, so the changes made through the mutable iterators
+    /// are never written
+    ///
+    /// # Errors
+    /// 1. The element doesn't exist
+    pub fn freeze(&mut self, key: &str) -> Result<(), TableError> {
+        if self.get_element(key).is_none() {
+            return Err(TableError::MissingKeyError(key.to_string()));
+        }
+        let key = self.metadata.normalization_policy.normalize(key);
+        self.frozen.insert(key.into_owned());
+        Ok(())
+    }
+
+    /// Make a frozen element writable again, returning whether it was frozen
+    pub fn unfreeze(&mut self, key: &str) -> bool {
+        let key = self.metadata.normalization_policy.normalize(key);
+        self.frozen.remove(key.as_ref())
+    }
+
+    /// Whether an element is frozen
+    pub fn is_frozen(&self, key: &str) -> bool {
+        let key = self.metadata.normalization_policy.normalize(key);
+        self.frozen.contains(key.as_ref())
+    }
+
+    /// Get an individual mutable element of the table by key, telling why it
+    /// can't be gotten
+    ///
+    /// # Errors
+    /// 1. The element doesn't exist
+    /// 2. The element is frozen
+    pub fn try_get_mut_element(&mut self, key: &str) -> Result<&mut TableElement<T>, TableError> {
+        self.check_frozen(key)?;
+        self.get_mut_element(key)
+            .ok_or_else(|| TableError::MissingKeyError(key.to_string()))
+    }
+
+    /// Fail if an element is frozen
+    pub(crate) fn check_frozen(&self, key: &str) -> Result<(), TableError> {
+        match self.is_frozen(key) {
+            true => Err(TableError::FrozenError(key.to_string())),
+            false => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, path::Path, process::Command};
+
+/// Run git in a directory
+fn git(dir: &Path, args: &[&str]) -> io::Result<()> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )),
+    }
+}
+
+/// Stage every change of a directory and commit it, if there's any
+pub(crate) fn commit(dir: &Path, message: &str) -> io::Result<()> {
+    git(dir, &["add", "-A", "--", "."])?;
+    let unchanged = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--cached", "--quiet", "--", "."])
+        .status()?
+        .success();
+    match unchanged {
+        true => Ok(()),
+        false => git(dir, &["commit", "-q", "-m", message, "--", "."]),
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Commit the changes of the table's directory with git after every write
+    /// back, when the directory is in a git repository. In the `template` of
+    /// the commit message `{entries}` is replaced by the number of written
+    /// entries and `{dir}` by the directory
+    pub fn commit_on_write_back<M: Into<String>>(&mut self, template: M) {
+        self.git_template = Some(template.into());
+    }
+
+    /// Commit the changes after a write back, if it's configured
+    pub(crate) fn git_commit(&self, entries: usize) -> Result<(), TableError> {
+        let (Some(template), Some(dir)) = (&self.git_template, self.storage.dir()) else {
+            return Ok(());
+        };
+        let message = template
+            .replace("{entries}", &entries.to_string())
+            .replace("{dir}", &dir.to_string_lossy());
+        Ok(commit(dir, &message)?)
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{StagedWrite, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{any::Any, collections::BTreeMap, fmt};
+
+/// The operations that a group needs from its tables, regardless of their type
+trait GroupMember {
+    fn contains(&self, key: &str) -> bool;
+    fn write_back(&mut self) -> Result<(), TableError>;
+    fn stage(&mut self) -> Result<Option<StagedWrite>, TableError>;
+    fn commit_staged(&mut self, staged: StagedWrite) -> Result<(), TableError>;
+    fn discard_staged(&mut self, staged: StagedWrite);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T> GroupMember for Table<T>
+where
+    T: Serialize + DeserializeOwned + 'static,
+{
+    fn contains(&self, key: &str) -> bool {
+        self.content.contains_key(key) || self.evicted.contains_key(key)
+    }
+
+    fn write_back(&mut self) -> Result<(), TableError> {
+        Table::write_back(self)
+    }
+
+    fn stage(&mut self) -> Result<Option<StagedWrite>, TableError> {
+        Table::stage(self, None, true)
+    }
+
+    fn commit_staged(&mut self, staged: StagedWrite) -> Result<(), TableError> {
+        Table::commit_staged(self, staged)
+    }
+
+    fn discard_staged(&mut self, staged: StagedWrite) {
+        Table::discard_staged(self, staged);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Extracts the referencing field from an element, if it's of the right type
+type Extract = Box<dyn Fn(&dyn Any) -> Option<String> + Send + Sync>;
+
+/// Extracts the pairs of key and referencing field from a table, if it's of the
+/// right type
+type ExtractAll = Box<dyn Fn(&dyn Any) -> Option<Vec<(String, String)>> + Send + Sync>;
+
+/// A field of the elements of one table that must be a key of another table
+struct Reference {
+    from: String,
+    field: String,
+    to: String,
+    extract: Extract,
+    extract_all: ExtractAll,
+}
+
+/// A reference from an element to a key that doesn't exist
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BrokenReference {
+    /// The table of the element with the reference
+    pub table: String,
+    /// The key of the element with the reference
+    pub key: String,
+    /// The name of the field with the reference
+    pub field: String,
+    /// The table that's referenced
+    pub target: String,
+    /// The key that was referenced but doesn't exist
+    pub value: String,
+}
+
+/// A group of named tables of possibly different types, that allows declaring
+/// references between them, such as `orders.customer_id -> customers`. The
+/// references are checked when pushing through the group and when writing back
+/// the group
+#[derive(Default)]
+pub struct TableGroup {
+    tables: BTreeMap<String, Box<dyn GroupMember>>,
+    references: Vec<Reference>,
+}
+
+impl TableGroup {
+    /// Create an empty group
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a table to the group under a name, replacing the table that was
+    /// under that name, if any
+    pub fn insert<T, S>(&mut self, name: S, table: Table<T>)
+    where
+        T: Serialize + DeserializeOwned + 'static,
+        S: Into<String>,
+    {
+        self.tables.insert(name.into(), Box::new(table));
+    }
+
+    /// Take a table out of the group, as long as it has the right type
+    pub fn remove<T>(&mut self, name: &str) -> Option<Table<T>>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        if !self.tables.get(name)?.as_any().is::<Table<T>>() {
+            return None;
+        }
+        let table = self.tables.remove(name)?.into_any();
+        // the type has just been checked, therefore the unwrap
+        Some(*table.downcast().unwrap())
+    }
+
+    /// Get a table of the group
+    pub fn get<T>(&self, name: &str) -> Option<&Table<T>>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        self.tables.get(name)?.as_any().downcast_ref()
+    }
+
+    /// Get a mutable table of the group. Keep in mind that the references are
+    /// not checked for operations made directly on the table until the group
+    /// is written back
+    pub fn get_mut<T>(&mut self, name: &str) -> Option<&mut Table<T>>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        self.tables.get_mut(name)?.as_any_mut().downcast_mut()
+    }
+
+    /// The names of the tables in the group
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.tables.keys()
+    }
+
+    /// Declare that `field`, as extracted by `extract` from the elements of the
+    /// table `from`, must be a key of the table `to`
+    pub fn references<T, F>(&mut self, from: &str, field: &str, to: &str, extract: F)
+    where
+        T: Serialize + DeserializeOwned + 'static,
+        F: Fn(&T) -> String + Send + Sync + Clone + 'static,
+    {
+        let extract_all = extract.clone();
+        self.references.push(Reference {
+            from: from.to_string(),
+            field: field.to_string(),
+            to: to.to_string(),
+            extract: Box::new(move |info| info.downcast_ref::<T>().map(&extract)),
+            extract_all: Box::new(move |table| {
+                table.downcast_ref::<Table<T>>().map(|table| {
+                    table
+                        .all_elements()
+                        .map(|(key, element)| (key.to_string(), extract_all(&element.info)))
+                        .collect()
+                })
+            }),
+        });
+    }
+
+    /// Push an element into a table of the group, checking first that its
+    /// references exist
+    ///
+    /// # Errors
+    /// 1. There is no table with that name and type in the group
+    /// 2. Some reference of the element doesn't exist
+    /// 3. Whenever there is an error with the `push`
+    pub fn push<T>(&mut self, table: &str, fname: &str, info_elem: T) -> Result<(), TableError>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        for reference in self.references.iter().filter(|r| r.from == table) {
+            if let Some(value) = (reference.extract)(&info_elem) {
+                if !self.contains(&reference.to, &value) {
+                    return Err(TableError::ReferenceError(
+                        format!("{}.{}", reference.from, reference.field),
+                        value,
+                    ));
+                }
+            }
+        }
+        self.get_mut(table)
+            .ok_or_else(|| TableError::GroupError(table.to_string()))?
+            .push(fname, info_elem)
+    }
+
+    /// Check every reference of the group, reporting all the broken ones
+    pub fn check_references(&self) -> Vec<BrokenReference> {
+        let mut broken = Vec::new();
+        for reference in &self.references {
+            let pairs = self
+                .tables
+                .get(&reference.from)
+                .and_then(|table| (reference.extract_all)(table.as_any()))
+                .unwrap_or_default();
+            for (key, value) in pairs {
+                if !self.contains(&reference.to, &value) {
+                    broken.push(BrokenReference {
+                        table: reference.from.clone(),
+                        key,
+                        field: reference.field.clone(),
+                        target: reference.to.clone(),
+                        value,
+                    });
+                }
+            }
+        }
+        broken
+    }
+
+    /// Write back every table of the group, as long as no reference is broken
+    ///
+    /// # Errors
+    /// 1. Some reference is broken, in which case nothing is written
+    /// 2. Whenever there is an error with an individual `write_back`
+    pub fn write_back(&mut self) -> Result<(), TableError> {
+        self.check_unbroken()?;
+        for table in self.tables.values_mut() {
+            table.write_back()?;
+        }
+        Ok(())
+    }
+
+    /// Write back every table of the group so that they all change together.
+    /// Every table writes its modified entries to staging items first, and
+    /// they are only moved into place once all the tables are staged, table
+    /// by table in the order of their names. When some table can't be staged
+    /// nothing is written and every table stays modified
+    ///
+    /// # Errors
+    /// 1. Some reference is broken, in which case nothing is written
+    /// 2. Some table can't be staged, in which case nothing is written
+    /// 3. Some staged entry can't be moved into place. The error names the
+    ///    tables that were already committed and the one that failed, which
+    ///    stays modified along with the ones that weren't committed
+    pub fn flush_all_atomic(&mut self) -> Result<(), TableError> {
+        self.check_unbroken()?;
+        let mut staged = Vec::new();
+        for (name, table) in self.tables.iter_mut() {
+            match table.stage() {
+                Ok(Some(staged_write)) => staged.push((name.clone(), staged_write)),
+                Ok(None) => {}
+                Err(e) => {
+                    for (name, staged_write) in staged {
+                        // the names have just been taken from the tables,
+                        // therefore the unwrap
+                        self.tables
+                            .get_mut(&name)
+                            .unwrap()
+                            .discard_staged(staged_write);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        let mut committed = Vec::new();
+        let mut staged = staged.into_iter();
+        while let Some((name, staged_write)) = staged.next() {
+            // the names have just been taken from the tables, therefore the
+            // unwraps
+            if let Err(e) = self
+                .tables
+                .get_mut(&name)
+                .unwrap()
+                .commit_staged(staged_write)
+            {
+                for (name, staged_write) in staged {
+                    self.tables
+                        .get_mut(&name)
+                        .unwrap()
+                        .discard_staged(staged_write);
+                }
+                return Err(TableError::PartialCommitError(committed, name, Box::new(e)));
+            }
+            committed.push(name);
+        }
+        Ok(())
+    }
+
+    /// Fail with the first broken reference, if there's any
+    fn check_unbroken(&self) -> Result<(), TableError> {
+        match self.check_references().into_iter().next() {
+            Some(broken) => Err(TableError::ReferenceError(
+                format!("{}.{}", broken.table, broken.field),
+                broken.value,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn contains(&self, table: &str, key: &str) -> bool {
+        self.tables
+            .get(table)
+            .is_some_and(|table| table.contains(key))
+    }
+}
+
+impl fmt::Debug for TableGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TableGroup")
+            .field("tables", &self.tables.keys().collect::<Vec<_>>())
+            .field(
+                "references",
+                &self
+                    .references
+                    .iter()
+                    .map(|r| format!("{}.{} -> {}", r.from, r.field, r.to))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
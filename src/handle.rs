@@ -0,0 +1,155 @@
+use crate::{Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    sync::mpsc::{self, Sender},
+    thread::{self, JoinHandle},
+};
+
+/// A job sent to the thread that owns a table
+type Job<T> = Box<dyn FnOnce(&mut Table<T>) + Send>;
+
+/// A handle to a table owned by a worker thread. Each method sends a message
+/// to that thread and waits for its response, so handles can be cloned and
+/// used from any thread without dealing with locks. The worker stops, dropping
+/// the table, when every handle has been dropped
+#[derive(Debug)]
+pub struct TableHandle<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    sender: Sender<Job<T>>,
+}
+
+impl<T> Clone for TableHandle<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    /// Move the table into a worker thread, returning a handle to it and the
+    /// join handle of the thread. Joining the thread after dropping every
+    /// handle ensures that the table has been dropped, and so written back when
+    /// the write back is automatic
+    pub fn spawn(self) -> (TableHandle<T>, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel::<Job<T>>();
+        let worker = thread::spawn(move || {
+            let mut table = self;
+            for job in receiver {
+                job(&mut table);
+            }
+        });
+        (TableHandle { sender }, worker)
+    }
+}
+
+impl<T> TableHandle<T>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    /// Run a function with the table in the worker thread and wait for its
+    /// result
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone, usually because a previous job panicked
+    pub fn with<R, F>(&self, f: F) -> Result<R, TableError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Table<T>) -> R + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.sender
+            .send(Box::new(move |table| {
+                // the receiver waits for this answer, so sending can't fail
+                let _ = sender.send(f(table));
+            }))
+            .map_err(|_| TableError::WorkerError)?;
+        receiver.recv().map_err(|_| TableError::WorkerError)
+    }
+
+    /// Get a copy of an element
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    pub fn get(&self, entry_name: &str) -> Result<Option<T>, TableError>
+    where
+        T: Clone,
+    {
+        let entry_name = entry_name.to_string();
+        self.with(move |table| {
+            table
+                .get_element(&entry_name)
+                .map(|element| element.info.clone())
+        })
+    }
+
+    /// Push an element into the table
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    /// 2. Whenever there is an error with the `push`
+    pub fn push(&self, fname: &str, info_elem: T) -> Result<(), TableError> {
+        let fname = fname.to_string();
+        self.with(move |table| table.push(&fname, info_elem))?
+    }
+
+    /// Pop an element from the table
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    /// 2. Whenever there is an error with the `pop`
+    pub fn pop(&self, fname: &str) -> Result<(), TableError> {
+        let fname = fname.to_string();
+        self.with(move |table| table.pop(&fname))?
+    }
+
+    /// Update an element with a function
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    /// 2. The element doesn't exist
+    pub fn update<R, F>(&self, entry_name: &str, f: F) -> Result<R, TableError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut T) -> R + Send + 'static,
+    {
+        let entry_name = entry_name.to_string();
+        self.with(move |table| match table.get_mut_element(&entry_name) {
+            Some(element) => Ok(f(&mut element.info)),
+            None => Err(TableError::PopError(entry_name)),
+        })?
+    }
+
+    /// Write the changes of the table into its files
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    /// 2. Whenever there is an error with the `write_back`
+    pub fn write_back(&self) -> Result<(), TableError> {
+        self.with(|table| table.write_back())?
+    }
+
+    /// The number of elements in the table
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    pub fn len(&self) -> Result<usize, TableError> {
+        self.with(|table| table.len())
+    }
+
+    /// Whether the table is empty
+    ///
+    /// # Errors
+    /// 1. The worker thread is gone
+    pub fn is_empty(&self) -> Result<bool, TableError> {
+        self.with(|table| table.is_empty())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{HistoryPolicy, Storage, Table, TableError, TableMetadata, RESERVED_PREFIX};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+
+/// The prefix of the items in which old versions of the entries are kept
+fn history_prefix(metadata: &TableMetadata, key: &str) -> String {
+    format!("{RESERVED_PREFIX}_history.{}.v", metadata.item_stem(key))
+}
+
+/// The name of the item in which a version of an entry is kept
+fn version_name(metadata: &TableMetadata, key: &str, version: u64) -> String {
+    format!("{}{version}.json", history_prefix(metadata, key))
+}
+
+/// The versions of an entry that are kept, oldest first
+fn versions<S: Storage>(storage: &S, metadata: &TableMetadata, key: &str) -> io::Result<Vec<u64>> {
+    let prefix = history_prefix(metadata, key);
+    let mut versions: Vec<u64> = storage
+        .list()?
+        .iter()
+        .filter_map(|name| {
+            name.strip_prefix(&prefix)?
+                .strip_suffix(".json")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    versions.sort();
+    Ok(versions)
+}
+
+/// Keep the current content of an entry as a new version if it's about to be
+/// replaced by a different one in its item `name`, removing the oldest versions beyond `keep`
+pub(crate) fn save_version<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    key: &str,
+    name: &str,
+    new_content: &[u8],
+    keep: usize,
+) -> io::Result<()> {
+    let old_content = match storage.read(name) {
+        Ok(old_content) => old_content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if old_content.is_empty() || old_content == new_content || keep == 0 {
+        return Ok(());
+    }
+    let versions = versions(storage, metadata, key)?;
+    let next = versions.last().map_or(1, |last| last + 1);
+    storage.write(&version_name(metadata, key, next), &old_content)?;
+    for &version in versions.iter().rev().skip(keep - 1) {
+        storage.delete(&version_name(metadata, key, version))?;
+    }
+    Ok(())
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// The kept versions of an element, oldest first, with their numbers.
+    /// Versions are kept by write backs when the history policy asks for it
+    ///
+    /// # Errors
+    /// 1. The versions can't be listed or read
+    /// 2. Some version can't be deserialized
+    pub fn history(&self, key: &str) -> Result<Vec<(u64, T)>, TableError> {
+        let key = &*self.metadata.normalization_policy.normalize(key);
+        versions(&self.storage, &self.metadata, key)?
+            .into_iter()
+            .map(|version| {
+                let content = self
+                    .storage
+                    .read(&version_name(&self.metadata, key, version))?;
+                Ok((version, serde_json::from_slice(&content)?))
+            })
+            .collect()
+    }
+
+    /// Replace an element by one of its kept versions. The replaced content
+    /// becomes a new version on the next write back
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element doesn't exist
+    /// 3. The element is frozen
+    /// 4. The version isn't kept
+    /// 5. The version can't be deserialized
+    pub fn restore_version(&mut self, key: &str, version: u64) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let key = &*self.metadata.normalization_policy.normalize(key);
+        self.restore(key)?;
+        if !self.content.contains_key(key) {
+            return Err(TableError::PopError(key.to_string()));
+        }
+        self.check_frozen(key)?;
+        let content = match self
+            .storage
+            .read(&version_name(&self.metadata, key, version))
+        {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(TableError::VersionError(key.to_string(), version))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let info = serde_json::from_slice(&content)?;
+        self[key].info = info;
+        Ok(())
+    }
+
+    /// The number of versions to keep for every element
+    pub(crate) fn kept_versions(&self) -> Option<usize> {
+        match self.metadata.history_policy {
+            HistoryPolicy::NoHistory => None,
+            HistoryPolicy::KeepVersions(keep) => Some(keep),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Add an element under a numeric id, written as the id policy says
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `push`
+    pub fn push_id(&mut self, id: u64, info_elem: T) -> Result<(), TableError> {
+        self.push(&self.metadata.id_policy.key(id), info_elem)
+    }
+
+    /// Remove the element with a numeric id
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `pop`
+    pub fn pop_id(&mut self, id: u64) -> Result<(), TableError> {
+        self.pop(&self.metadata.id_policy.key(id))
+    }
+
+    /// Get the information of the element with a numeric id
+    pub fn get_id(&self, id: u64) -> Option<&T> {
+        self.get_element(&self.metadata.id_policy.key(id))
+            .map(|element| &element.info)
+    }
+
+    /// Get the mutable information of the element with a numeric id
+    pub fn get_mut_id(&mut self, id: u64) -> Option<&mut T> {
+        let key = self.metadata.id_policy.key(id);
+        self.get_mut_element(&key).map(|element| &mut element.info)
+    }
+
+    /// The ids of the elements in numeric order. Keys that aren't written as
+    /// ids by the id policy are skipped
+    pub fn ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self
+            .all_keys()
+            .filter_map(|key| self.metadata.id_policy.id(key))
+            .collect();
+        // padded ids are already sorted, but plain ones sort as strings
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The greatest id of the elements
+    pub fn max_id(&self) -> Option<u64> {
+        self.all_keys()
+            .filter_map(|key| self.metadata.id_policy.id(key))
+            .max()
+    }
+}
@@ -0,0 +1,55 @@
+/// The content of an item written in jsonc, without its comments and trailing
+/// commas, so that it can be read as json
+pub(crate) fn strip(content: &[u8]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(content.len());
+    // the position of the last comma, while it's only followed by whitespace
+    // and comments, which makes it a trailing comma if an object or an array
+    // ends next
+    let mut comma = None;
+    let mut i = 0;
+    while i < content.len() {
+        match (content[i], content.get(i + 1)) {
+            (b'"', _) => {
+                let start = i;
+                i += 1;
+                while i < content.len() && content[i] != b'"' {
+                    if content[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(content.len());
+                stripped.extend_from_slice(&content[start..i]);
+                comma = None;
+                continue;
+            }
+            (b'/', Some(b'/')) => {
+                while i < content.len() && content[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            (b'/', Some(b'*')) => {
+                i += 2;
+                while i < content.len() && !content[i..].starts_with(b"*/") {
+                    i += 1;
+                }
+                i += 2;
+                // a comment keeps the tokens around it apart
+                stripped.push(b' ');
+                continue;
+            }
+            (b',', _) => comma = Some(stripped.len()),
+            (b'}' | b']', _) => {
+                if let Some(comma) = comma.take() {
+                    stripped[comma] = b' ';
+                }
+            }
+            (byte, _) if byte.is_ascii_whitespace() => {}
+            _ => comma = None,
+        }
+        stripped.push(content[i]);
+        i += 1;
+    }
+    stripped
+}
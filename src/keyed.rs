@@ -0,0 +1,108 @@
+use crate::{Storage, Table, TableError, RESERVED_PREFIX};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+
+/// Values that know the key under which they are stored in a table, usually
+/// because they carry an ID field. It can be derived with `TableRecord` when
+/// the `derive` feature is enabled
+pub trait Keyed {
+    /// The key of the value, that is, the name of its file without the
+    /// `.json` extension
+    fn key(&self) -> String;
+
+    /// Whether a key is valid for this type. It's checked when pushing with
+    /// `push_keyed` and by default every key is valid
+    fn validate_key(_key: &str) -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+}
+
+/// Whether a key can be used as a file name in any platform without surprises.
+/// That is, it's not empty, it isn't a hidden or reserved name and it doesn't
+/// contain path separators or characters forbidden in some file systems
+pub fn is_safe_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('.')
+        && !key.starts_with(RESERVED_PREFIX)
+        && !key.chars().any(|c| {
+            c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        })
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned + Keyed,
+    S: Storage,
+{
+    /// Push an element under its own key
+    ///
+    /// # Errors
+    /// 1. The key of the element isn't valid for its type
+    /// 2. Whenever there is an error with the `push`
+    pub fn push_keyed(&mut self, info_elem: T) -> Result<(), TableError> {
+        let key = info_elem.key();
+        if !T::validate_key(&key) {
+            return Err(TableError::KeyError(key));
+        }
+        self.push(&key, info_elem)
+    }
+
+    /// Push several elements each one under its own key
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with an individual `push_keyed`
+    pub fn append_keyed<I: IntoIterator<Item = T>>(
+        &mut self,
+        elements: I,
+    ) -> Result<(), TableError> {
+        for element in elements {
+            self.push_keyed(element)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Push an element under a generated key, and return that key. Keys are
+    /// taken from a counter that is persisted in the table's directory, so
+    /// they keep increasing between runs. Keys that are already in use are
+    /// skipped
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The counter can't be read or written
+    /// 3. Whenever there is an error with the `push`
+    pub fn push_auto(&mut self, info_elem: T) -> Result<String, TableError> {
+        self.mod_permissions()?;
+        let counter_name = format!("{RESERVED_PREFIX}_counter");
+        let mut counter: u64 = match self.storage.read(&counter_name) {
+            Ok(counter) => String::from_utf8_lossy(&counter)
+                .trim()
+                .parse()
+                .unwrap_or(0),
+            Err(e) => match e.kind() {
+                io::ErrorKind::NotFound => 0,
+                _ => return Err(e.into()),
+            },
+        };
+        let names = self.storage.list()?;
+        let mut key = counter.to_string();
+        while self.content.contains_key(key.as_str())
+            || names.contains(&self.metadata.entry_name(&key))
+        {
+            counter += 1;
+            key = counter.to_string();
+        }
+        self.push(&key, info_elem)?;
+        self.storage
+            .write(&counter_name, (counter + 1).to_string().as_bytes())?;
+        Ok(key)
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{
+    entry_keys, long_keys, ContentPolicy, DropFailure, FsStorage, RWPolicy, Storage, TableError,
+    TableMetadata, WriteType,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// An element that has been deserialized and kept in memory
+#[derive(Debug)]
+struct Cached<T> {
+    info: T,
+    /// The size of its json representation, used as an estimate of its size
+    size: usize,
+    /// When it was last accessed
+    tick: u64,
+    is_modified: bool,
+}
+
+/// A table that only deserializes its elements when they are accessed, keeping
+/// them in memory up to a budget. The budget is measured in bytes of the json
+/// representation of the elements, and when it's exceeded the least recently
+/// used elements are evicted, to be read again on their next access. Modified
+/// elements are written before being evicted, so huge tables can be traversed
+/// and changed with bounded memory
+#[derive(Debug)]
+pub struct LazyTable<T, S = FsStorage>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    storage: S,
+    metadata: TableMetadata,
+    keys: HashSet<String>,
+    cache: HashMap<String, Cached<T>>,
+    /// The cached keys by the time of their last access
+    recency: BTreeMap<u64, String>,
+    budget: usize,
+    used: usize,
+    tick: u64,
+    drop_failure: DropFailure<T>,
+}
+
+impl<T, S> LazyTable<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Open a table from any storage, reading only the names of its entries
+    ///
+    /// # Errors
+    /// 1. The items of the storage can't be listed
+    /// 2. There was a non .json item in a table with the `OnlyJsonFiles` extension policy
+    pub fn with_storage(
+        storage: S,
+        metadata: TableMetadata,
+        budget: usize,
+    ) -> Result<Self, TableError> {
+        let keys = entry_keys(&storage, &metadata)?.into_iter().collect();
+        Ok(Self {
+            storage,
+            metadata,
+            keys,
+            cache: HashMap::new(),
+            recency: BTreeMap::new(),
+            budget,
+            used: 0,
+            tick: 0,
+            drop_failure: DropFailure::Panic,
+        })
+    }
+
+    /// Get an element, reading it if it's not in memory. Elements that can't
+    /// be deserialized are reported as missing when the policy is
+    /// `IgnoreSerdeErrors`
+    ///
+    /// # Errors
+    /// 1. The element can't be read
+    /// 2. The element can't be deserialized and the policy is `PromoteSerdeErrors`
+    /// 3. Some evicted element can't be written back
+    pub fn get(&mut self, key: &str) -> Result<Option<&T>, TableError> {
+        Ok(self.fetch(key)?.map(|cached| &cached.info))
+    }
+
+    /// Get a mutable element, reading it if it's not in memory. It's written
+    /// on the next write back, or when it's evicted
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. Whenever there's an error with the `get`
+    pub fn get_mut(&mut self, key: &str) -> Result<Option<&mut T>, TableError> {
+        self.mod_permissions()?;
+        Ok(self.fetch(key)?.map(|cached| {
+            cached.is_modified = true;
+            &mut cached.info
+        }))
+    }
+
+    /// Add an element and create its file, keeping it in memory until it's
+    /// written
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. If you cant create a new file
+    /// 3. If there's a problem with serialization
+    pub fn push(&mut self, fname: &str, info_elem: T) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        if self.keys.contains(fname) {
+            return Err(TableError::PushError(fname.into()));
+        }
+        let size = self.metadata.to_vec(&info_elem)?.len();
+        self.storage.create(&self.metadata.entry_name(fname))?;
+        long_keys::record_key(&mut self.storage, &self.metadata, fname)?;
+        self.keys.insert(fname.to_string());
+        self.insert(fname, info_elem, size, true);
+        self.evict()
+    }
+
+    /// Remove an element and delete its file
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. You try to delete a non existing element
+    /// 3. If you cant delete the file
+    pub fn pop(&mut self, fname: &str) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        if !self.keys.remove(fname) {
+            return Err(TableError::PopError(fname.to_string()));
+        }
+        if let Some(cached) = self.cache.remove(fname) {
+            self.recency.remove(&cached.tick);
+            self.used -= cached.size;
+        }
+        self.storage.delete(&self.metadata.entry_name(fname))?;
+        Ok(long_keys::forget_key(
+            &mut self.storage,
+            &self.metadata,
+            fname,
+        )?)
+    }
+
+    /// Write the modified elements that are in memory
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. There are problems with serialization
+    pub fn write_back(&mut self) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        for (name, cached) in self.cache.iter_mut().filter(|(_, c)| c.is_modified) {
+            let content = self.metadata.to_vec(&cached.info)?;
+            self.storage
+                .write(&self.metadata.entry_name(name), &content)?;
+            self.used = self.used - cached.size + content.len();
+            cached.size = content.len();
+            cached.is_modified = false;
+        }
+        Ok(())
+    }
+
+    /// Write the modified elements of a table that can be written and drop
+    /// it, so that the errors of the write back can be handled instead of
+    /// panicking on drop. Whatever happens, nothing more is written when it's
+    /// dropped
+    ///
+    /// # Errors
+    /// 1. Whenever there's an error with the `write_back`
+    pub fn close(mut self) -> Result<(), TableError> {
+        let result = match self.metadata.rw_policy {
+            RWPolicy::Write(_) => self.write_back(),
+            RWPolicy::ReadOnly => Ok(()),
+        };
+        self.cache.clear();
+        result
+    }
+
+    /// Choose what happens when the automatic write back fails as the table
+    /// is dropped. A callback is given only the modified elements
+    pub fn on_drop_failure(&mut self, drop_failure: DropFailure<T>) {
+        self.drop_failure = drop_failure;
+    }
+
+    /// The names of all the elements, whether they are in memory or not
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.keys.iter()
+    }
+
+    /// Whether there's an element with that name
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// The number of elements in the table
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The number of elements in memory
+    pub fn cached_len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// The estimated size of the elements in memory, in bytes of json
+    pub fn cached_size(&self) -> usize {
+        self.used
+    }
+
+    /// The memory budget, in bytes of json
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The policies in effect for the table
+    pub fn metadata(&self) -> &TableMetadata {
+        &self.metadata
+    }
+
+    fn mod_permissions(&self) -> Result<(), TableError> {
+        match self.metadata.rw_policy {
+            RWPolicy::Write(_) => Ok(()),
+            RWPolicy::ReadOnly => Err(TableError::NoWritePolicyError),
+        }
+    }
+
+    /// Mark an element as the most recently used one
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some(cached) = self.cache.get_mut(key) {
+            self.recency.remove(&cached.tick);
+            cached.tick = self.tick;
+            self.recency.insert(self.tick, key.to_string());
+        }
+    }
+
+    fn insert(&mut self, key: &str, info: T, size: usize, is_modified: bool) {
+        self.tick += 1;
+        self.used += size;
+        self.recency.insert(self.tick, key.to_string());
+        self.cache.insert(
+            key.to_string(),
+            Cached {
+                info,
+                size,
+                tick: self.tick,
+                is_modified,
+            },
+        );
+    }
+
+    /// Get an element from memory, reading it first if needed
+    fn fetch(&mut self, key: &str) -> Result<Option<&mut Cached<T>>, TableError> {
+        if !self.keys.contains(key) {
+            return Ok(None);
+        }
+        if self.cache.contains_key(key) {
+            self.touch(key);
+        } else {
+            let content = self.storage.read(&self.metadata.entry_name(key))?;
+            match self.metadata.parse(&content) {
+                Ok((info, _)) => self.insert(key, info, content.len(), false),
+                Err(serde_error) => match self.metadata.content_policy {
+                    ContentPolicy::IgnoreSerdeErrors => return Ok(None),
+                    ContentPolicy::PromoteSerdeErrors => return Err(serde_error.into()),
+                },
+            }
+            self.evict()?;
+        }
+        Ok(self.cache.get_mut(key))
+    }
+
+    /// Evict the least recently used elements until the budget is met, always
+    /// keeping the most recent one
+    fn evict(&mut self) -> Result<(), TableError> {
+        while self.used > self.budget && self.recency.len() > 1 {
+            // the loop condition ensures that there's an entry, therefore the
+            // unwraps
+            let (_, name) = self.recency.first_key_value().unwrap();
+            let name = name.clone();
+            let cached = &self.cache[&name];
+            // the element is written before it leaves memory, so that a failed
+            // write doesn't lose its changes
+            if cached.is_modified {
+                let content = self.metadata.to_vec(&cached.info)?;
+                self.storage
+                    .write(&self.metadata.entry_name(&name), &content)?;
+            }
+            self.recency.pop_first();
+            let cached = self.cache.remove(&name).unwrap();
+            self.used -= cached.size;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> Drop for LazyTable<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Writes back in case the write back is set to automatic. Use `close` to
+    /// handle the errors of the write back, or `on_drop_failure` to choose
+    /// what happens with them
+    ///
+    /// # Panics
+    /// - When there are problems with the write back, unless the thread is
+    ///   already panicking or another `DropFailure` was chosen, mainly when
+    ///     - There are problems with file handles
+    ///     - There are problems with serialization
+    fn drop(&mut self) {
+        if RWPolicy::Write(WriteType::Automatic) == self.metadata.rw_policy {
+            if let Err(error) = self.write_back() {
+                let cache = &mut self.cache;
+                std::mem::take(&mut self.drop_failure).handle(error, || {
+                    cache
+                        .drain()
+                        .filter(|(_, cached)| cached.is_modified)
+                        .map(|(key, cached)| (key, cached.info))
+                        .collect()
+                });
+            }
+        }
+    }
+}
@@ -9,60 +9,442 @@
 //! deserialized by [serde](https://serde.rs/). For that purpose the traits and
 //! derive macros are reexported. (So that there is no need to explicitly depend
 //! on serde to use this crate)
+//!
+//! The crate also builds for `wasm32-wasip1`, where the tables live in the
+//! directories that the host preopens for the module. Since that target has no
+//! threads, `TableHandle` is not available there
+//!
+//! With the `tracing` feature, loads and write backs emit
+//! [tracing](https://docs.rs/tracing) spans and events, with the time taken by
+//! every entry and the keys of the entries that fail. With the `git` feature,
+//! tables in a git repository can commit their changes after every write back.
+//! With the `json5` feature, tables can opt into loading entries written in
+//! [json5](https://json5.org), with comments and unquoted keys. With the `render`
+//! feature, tables can be rendered as aligned text tables for terminals. With
+//! the `schemars` feature, tables can record a fingerprint of the schema of
+//! their type and check it when they are loaded
 
 use serde::de::DeserializeOwned;
 pub use serde::{Deserialize, Serialize};
 use std::{
-    collections::hash_map::{HashMap, Iter, Keys, Values, ValuesMut},
+    borrow::Cow,
+    collections::{
+        btree_map::{BTreeMap, Iter, IterMut, Keys, Range, Values, ValuesMut},
+        BTreeSet, HashMap,
+    },
     ffi::OsStr,
     fmt::Debug,
-    fs::{self, File},
-    io::{prelude::*, SeekFrom},
-    ops::{Index, IndexMut},
+    fs,
+    hash::{DefaultHasher, Hasher},
+    io,
+    iter::{Skip, Take},
+    ops::{Bound, Index, IndexMut},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::Instant,
 };
 
+/// Emit a `tracing` event when the feature is enabled, and nothing otherwise
+macro_rules! event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)*);
+    };
+}
+
 mod table_error;
 pub use table_error::{TableBuilderError, TableError};
 
 mod aux;
-pub use aux::{ContentPolicy, ExtensionPolicy, RWPolicy, TableBuilder, TableMetadata, WriteType};
+#[cfg(feature = "json5")]
+pub use aux::Json5Policy;
+use aux::LoadOptions;
+pub use aux::{
+    AtomicityPolicy, AuditPolicy, ConcurrencyPolicy, ContentPolicy, DuplicateKeyPolicy,
+    DurabilityPolicy, ExtensionPolicy, ExternalEditPolicy, FloatPolicy, FormatPolicy,
+    HiddenFilePolicy, HistoryPolicy, IdPolicy, LongKeyPolicy, NormalizationPolicy, RWPolicy,
+    SizePolicy, SymlinkPolicy, SyntaxPolicy, TableBuilder, TableMetadata, TablePermissions,
+    WriteType,
+};
+
+mod document;
+
+mod schema;
+
+mod config;
+
+mod storage;
+pub use storage::{FsStorage, MemoryStorage, Storage};
+
+mod mmap;
+
+mod jsonc;
+
+mod spans;
+
+mod splice;
+
+mod canonical;
+
+mod floats;
+
+mod drop_failure;
+pub use drop_failure::DropFailure;
+
+mod summary;
+pub use summary::TableSummary;
+
+mod serialization;
+
+#[cfg(feature = "render")]
+mod render;
+#[cfg(feature = "render")]
+pub use render::Column;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+
+#[cfg(feature = "test-util")]
+mod test_util;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteLayout;
+
+#[cfg(feature = "parquet")]
+mod parquet;
+
+mod lazy;
+pub use lazy::LazyTable;
+
+mod stream;
+pub use stream::TableStream;
+
+mod maintenance;
+pub use maintenance::VacuumPolicy;
+use maintenance::SOFT_DELETE_EXTENSION;
+
+#[cfg(feature = "object_store")]
+mod object;
+#[cfg(feature = "object_store")]
+pub use object::ObjectStorage;
+#[cfg(feature = "object_store")]
+pub use object_store;
+
+mod migrations;
+pub use migrations::Migrations;
+
+mod raw;
+pub use raw::RawTable;
+
+mod unique;
+use unique::Unique;
+
+mod metrics;
+pub use metrics::TableMetrics;
+
+mod report;
+pub use report::{AppendReport, PopOutcome};
+
+mod audit;
+
+mod history;
+
+#[cfg(feature = "git")]
+mod git;
+
+mod stamped;
+pub use stamped::Stamped;
+use stamped::{unix_millis, Stamper};
+
+mod expiry;
+pub use expiry::Expiry;
+
+mod concurrency;
+
+mod external;
+use external::Merge;
+
+mod query;
+
+mod snapshot;
+pub use snapshot::TableSnapshot;
+
+mod savepoint;
+pub use savepoint::Savepoint;
+
+mod streaming;
+
+mod memory;
+pub use memory::MemoryEstimate;
+
+mod shared;
+pub use shared::SharedTable;
+
+#[cfg(not(target_os = "wasi"))]
+mod handle;
+#[cfg(not(target_os = "wasi"))]
+pub use handle::TableHandle;
 
-/// The structure that's stored in the internal `hash_map`. It contains a file and
-/// the content of the file. You can only access the information and not the file
+mod watch;
+pub use watch::{TableEvent, TableWatcher};
+
+mod group;
+pub use group::{BrokenReference, TableGroup};
+
+mod temporary;
+
+mod freeze;
+
+mod evict;
+
+mod chunks;
+
+mod ndjson;
+
+mod ids;
+
+mod long_keys;
+
+mod typed;
+pub use typed::{TableKey, TypedTable};
+
+mod composite;
+pub use composite::CompositeKey;
+
+mod keyed;
+#[cfg(feature = "embed")]
+pub use json_tables_derive::include_table;
+#[cfg(feature = "derive")]
+pub use json_tables_derive::TableRecord;
+pub use keyed::{is_safe_key, Keyed};
+pub use serde_json::Value;
+
+/// Files in the directory of a table whose name starts with this prefix are
+/// managed by the crate itself, and they are never considered entries
+const RESERVED_PREFIX: &str = ".table";
+
+/// Whether a path of a table's directory is managed by the crate itself
+fn is_reserved(path: &Path) -> bool {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| name.starts_with(RESERVED_PREFIX))
+}
+
+/// The name of the item in which an entry is stored
+fn entry_name(key: &str) -> String {
+    format!("{key}.json")
+}
+
+/// The name of the item in which an entry is staged by write backs that
+/// write the whole table at once
+fn staging_name(name: &str) -> String {
+    format!("{RESERVED_PREFIX}_staging.{name}")
+}
+
+/// Create an empty item with the permissions of a table
+fn create_item<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    name: &str,
+) -> io::Result<()> {
+    storage.create(name)?;
+    if let Some(permissions) = metadata.permissions {
+        storage.set_mode(name, permissions.file_mode)?;
+    }
+    Ok(())
+}
+
+/// The key of an item of a storage if it's an entry. Reserved items are never
+/// entries, and the rest of the items follow the hidden file, extension and
+/// symlink policies. Items named after the hash of a long key give that key
+///
+/// # Errors
+/// 1. The item is not an entry and the policy is `OnlyJsonFiles`
+/// 2. The item is a link and the policy is `Error`
+/// 3. It can't be checked whether the item is a link
+/// 4. The key of a hashed item can't be read
+fn entry_key<'a, S: Storage>(
+    storage: &S,
+    name: &'a str,
+    metadata: &TableMetadata,
+) -> Result<Option<Cow<'a, str>>, TableError> {
+    if name.starts_with(RESERVED_PREFIX)
+        || (name.starts_with('.') && metadata.hidden_file_policy == HiddenFilePolicy::Skip)
+    {
+        return Ok(None);
+    }
+    let key = match name.strip_suffix(".json").filter(|key| !key.is_empty()) {
+        Some(key) => match metadata.symlink_policy {
+            SymlinkPolicy::Follow | SymlinkPolicy::FollowAndPopTarget => key,
+            SymlinkPolicy::Ignore => match storage.link_target(name)? {
+                Some(_) => return Ok(None),
+                None => key,
+            },
+            SymlinkPolicy::Error => match storage.link_target(name)? {
+                Some(_) => return Err(TableError::SymlinkError(key.to_string())),
+                None => key,
+            },
+        },
+        None => {
+            return match metadata.extension_policy {
+                ExtensionPolicy::OnlyJsonFiles => Err(TableError::JsonError),
+                ExtensionPolicy::IgnoreNonJson => Ok(None),
+            }
+        }
+    };
+    Ok(Some(match long_keys::hashed_key(storage, metadata, key)? {
+        Some(long_key) => Cow::Owned(long_key),
+        None => Cow::Borrowed(key),
+    }))
+}
+
+/// Whether `text` matches a glob `pattern`, in which `*` matches any run of
+/// characters and `?` any single character
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // the position of the last star and of the text when it was found, to
+    // backtrack when the rest of the pattern doesn't match
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The keys of the entries of a storage, in order
+///
+/// # Errors
+/// 1. The items of the storage can't be listed
+/// 2. There was a non .json item in a table with the `OnlyJsonFiles` extension policy
+fn entry_keys<S: Storage>(
+    storage: &S,
+    metadata: &TableMetadata,
+) -> Result<Vec<String>, TableError> {
+    let mut keys = Vec::new();
+    for name in storage.list()? {
+        if let Some(key) = entry_key(storage, &name, metadata)? {
+            keys.push(key.to_string());
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+/// The structure that's stored in the internal map. It contains the
+/// content of an entry, whose file is handled by the storage of the table
 #[derive(Debug)]
 pub struct TableElement<T> {
-    /// The file in which the element is read
-    file: File,
     /// The element that you actually want stored/read
     pub info: T,
+    /// The hash of what was last read from or written to its item, if known
+    written: Option<u64>,
+    /// The version of the entry as of its last load or write
+    version: u64,
+}
+
+impl<T> TableElement<T> {
+    /// An element whose item content is unknown, so it's always written
+    fn new(info: T) -> Self {
+        Self {
+            info,
+            written: None,
+            version: 0,
+        }
+    }
+}
+
+/// A write back that has written its items, or staged them, but hasn't been
+/// finished yet
+#[derive(Debug)]
+pub(crate) struct StagedWrite {
+    started: Instant,
+    /// The names of the items written
+    written: Vec<String>,
+    /// The keys of the staged entries, with the names of their staging items
+    /// and the hashes of their content
+    staged: Vec<(Arc<str>, String, u64)>,
+}
+
+/// The json equivalent of the content of an item, if it's written in json5
+#[cfg(feature = "json5")]
+fn json5_to_json(content: &[u8]) -> Option<Vec<u8>> {
+    let value: serde_json::Value = json5::from_str(std::str::from_utf8(content).ok()?).ok()?;
+    serde_json::to_vec(&value).ok()
+}
+
+/// The hash of the content of an item, to tell whether it changed
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // written without a length prefix, so that content that is streamed in
+    // pieces gets the same hash
+    hasher.write(content);
+    hasher.finish()
 }
 
 /// Main structure of this crate. Holds the information from the table. It
-/// reads all at once, so huge tables will be slow and memory intensive
+/// reads all at once, so huge tables will be slow and memory intensive. The
+/// entries are kept in a directory unless another `Storage` is used
 #[derive(Debug)]
-pub struct Table<T>
+pub struct Table<T, S = FsStorage>
 where
     T: Serialize + DeserializeOwned,
+    S: Storage,
 {
-    /// A string instead of a ReadDir, because it's easier to modify and write
-    /// (new files from). ReadDir doesn't implement clone or copy so it's just
-    /// annoying to deal with)
-    dir: PathBuf,
-    content: HashMap<String, TableElement<T>>,
+    /// Where the entries are read from and written to
+    storage: S,
+    content: BTreeMap<Arc<str>, TableElement<T>>,
     metadata: TableMetadata,
     is_modified: bool,
+    unique_constraints: Vec<Unique<T>>,
+    metrics: TableMetrics,
+    /// Keeps the timestamps of stamped elements
+    stamper: Option<Stamper<T>>,
+    /// What happens when the write back on drop fails
+    drop_failure: DropFailure<T>,
+    /// Merges the elements whose items were edited elsewhere
+    merge: Option<Merge<T>>,
+    /// The rule by which stale elements are removed
+    expiry: Option<Expiry<T>>,
+    /// The directory of a temporary table, that is removed on drop
+    temporary: Option<PathBuf>,
+    /// The keys of the elements that can't be changed
+    frozen: BTreeSet<String>,
+    /// The elements dropped from memory, with the ones read again since
+    evicted: BTreeMap<Arc<str>, OnceLock<TableElement<T>>>,
+    /// The message of the commits made after write backs
+    #[cfg(feature = "git")]
+    git_template: Option<String>,
 }
 
 impl<T> Table<T>
 where
     T: Serialize + DeserializeOwned,
 {
-    /// Create a new table
+    /// Create a new table, storing its policies in the table's directory
     ///
     /// # Errors
     /// 1. There was already a table in that directory
     /// 2. Couldn't create a path to the table
+    /// 3. Couldn't store the policies
     pub fn new<Q: AsRef<Path>>(dir: Q, metadata: TableMetadata) -> Result<Self, TableBuilderError> {
         if metadata.rw_policy == RWPolicy::ReadOnly {
             return Err(TableBuilderError::CreateWithoutWriteError);
@@ -75,104 +457,447 @@ where
             Ok(_) => return Err(TableBuilderError::TableAlreadyExistsError),
         };
         fs::create_dir_all(&dir)?;
+        config::write_metadata(&dir, &metadata)?;
+        if let Some(permissions) = metadata.permissions {
+            storage::set_mode(dir.as_ref(), permissions.dir_mode)?;
+            storage::set_mode(&config::metadata_path(&dir), permissions.file_mode)?;
+        }
         Ok(Table {
-            dir: dir.as_ref().to_path_buf(),
-            content: HashMap::new(),
+            storage: FsStorage::new(dir),
+            content: BTreeMap::new(),
             metadata,
             is_modified: false,
+            unique_constraints: Vec::new(),
+            metrics: TableMetrics::default(),
+            stamper: None,
+            drop_failure: DropFailure::Panic,
+            merge: None,
+            expiry: None,
+            temporary: None,
+            frozen: BTreeSet::new(),
+            evicted: BTreeMap::new(),
+            #[cfg(feature = "git")]
+            git_template: None,
         })
     }
 
+    /// Create a new table with the elements of a map, keyed by their names,
+    /// and write them
+    ///
+    /// # Errors
+    /// 1. The table can't be created
+    /// 2. Some element can't be pushed or written
+    pub fn from_map<Q: AsRef<Path>>(
+        dir: Q,
+        map: HashMap<String, T>,
+        metadata: TableMetadata,
+    ) -> Result<Self, TableError> {
+        let mut table = Table::new(dir, metadata)?;
+        for (name, info) in map {
+            table.push(&name, info)?;
+        }
+        table.write_back()?;
+        Ok(table)
+    }
+
     /// Generate a `TableBuilder` to open or load a table
     pub fn builder<Q: AsRef<Path>>(dir: Q) -> TableBuilder<T> {
         TableBuilder::new(dir)
     }
 
-    /// Load an exiting table, it can also be loaded through a builder
+    /// Load an exiting table, it can also be loaded through a builder. When no
+    /// metadata is given, the one stored in the table is used
     ///
     /// # Errors
     /// 1. Whenever there's a file in the directory which you don't have
-    /// permission to read, or is not a file or directory
-    /// 2. Couldn't open a file with the required permissions
-    /// 3. There is a deserialization error and the policy was `PromoteSerdeErrors`
-    /// 4. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
-    ///
-    /// # Panics
-    /// If somehow you have a file without a name, or with an name that is not utf-8
-    /// compatible
+    ///    permission to read, or is not a file or directory
+    /// 2. There is a deserialization error and the policy was `PromoteSerdeErrors`
+    /// 3. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
     pub fn load<Q: AsRef<Path>>(
         dir: Q,
         metadata: Option<TableMetadata>,
     ) -> Result<Self, TableError> {
-        let metadata = metadata.unwrap_or_default();
-        let mut content = HashMap::<String, TableElement<T>>::new();
-        fs::read_dir(&dir)?.try_for_each(|dir_entry| {
-            let path = dir_entry?.path();
-            let jstr = OsStr::new("json");
-            if path.is_file() && Some(jstr) == path.extension() {
-                // we know it has a name, because it's a file therefore the unwraps
-                let name = path.file_name().unwrap().to_str().unwrap();
-                let (name, _) = name.rsplit_once('.').unwrap();
-                let file = match metadata.rw_policy {
-                    RWPolicy::ReadOnly => File::open(&path),
-                    RWPolicy::Write(_) => File::options().read(true).write(true).open(&path),
-                };
-                match file {
-                    Ok(fi) => match serde_json::from_reader(&fi) {
-                        Ok(info) => {
-                            content.insert(name.to_string(), TableElement { file: fi, info });
-                            Ok(())
+        let metadata = match metadata {
+            Some(metadata) => metadata,
+            None => config::read_metadata(&dir)?.unwrap_or_default(),
+        };
+        Self::load_with(dir, metadata, &LoadOptions::default())
+    }
+
+    /// Whether there's a directory in `dir` that could be loaded as a table
+    pub fn exists<Q: AsRef<Path>>(dir: Q) -> bool {
+        dir.as_ref().is_dir()
+    }
+
+    /// Whether `dir` is a table created by this crate, that is, a directory
+    /// with the policies of the table stored in it
+    pub fn is_table<Q: AsRef<Path>>(dir: Q) -> bool {
+        config::metadata_path(dir).is_file()
+    }
+
+    /// The keys of the table in `dir`, in order, without opening any of its
+    /// files. The extension policy stored in the table is honored
+    ///
+    /// # Errors
+    /// 1. The stored policies can't be read
+    /// 2. The directory can't be read
+    /// 3. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
+    pub fn list_keys<Q: AsRef<Path>>(dir: Q) -> Result<Vec<String>, TableError> {
+        let metadata = config::read_metadata(&dir)?.unwrap_or_default();
+        entry_keys(&FsStorage::new(dir), &metadata)
+    }
+
+    /// Load an existing table with the options of a builder that are not part
+    /// of the metadata
+    pub(crate) fn load_with<Q: AsRef<Path>>(
+        dir: Q,
+        metadata: TableMetadata,
+        options: &LoadOptions,
+    ) -> Result<Self, TableError> {
+        Self::load_from(FsStorage::new(dir), metadata, options)
+    }
+}
+
+impl<T> Table<T, MemoryStorage>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Create a table that lives only in memory, with no directory and no
+    /// files. It has the full API and the default policies, but every change
+    /// is kept in RAM and lost when the table is dropped, which makes it handy
+    /// for testing code that takes a table. Its storage is `MemoryStorage`
+    /// rather than the default `FsStorage`, so that code has to take a
+    /// `Table<T, S>` generic over `S: Storage` instead of a `Table<T>`
+    pub fn in_memory() -> Self {
+        Table {
+            storage: MemoryStorage::new(),
+            content: BTreeMap::new(),
+            metadata: TableMetadata::default(),
+            is_modified: false,
+            unique_constraints: Vec::new(),
+            metrics: TableMetrics::default(),
+            stamper: None,
+            drop_failure: DropFailure::Panic,
+            merge: None,
+            expiry: None,
+            temporary: None,
+            frozen: BTreeSet::new(),
+            evicted: BTreeMap::new(),
+            #[cfg(feature = "git")]
+            git_template: None,
+        }
+    }
+
+    /// Build a read only table from the entries of a directory that was
+    /// embedded in the binary, given as pairs of name and json content. This
+    /// is what `include_table!` expands to, so there's usually no need to
+    /// call it directly
+    ///
+    /// # Errors
+    /// 1. Some entry can't be deserialized
+    pub fn from_embedded(entries: &[(&str, &str)]) -> Result<Self, TableError> {
+        let storage = MemoryStorage::with_items(
+            entries
+                .iter()
+                .map(|(name, json)| (entry_name(name), json.as_bytes())),
+        );
+        let metadata = TableMetadata {
+            rw_policy: RWPolicy::ReadOnly,
+            ..Default::default()
+        };
+        Self::load_from(storage, metadata, &LoadOptions::default())
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Load a table from any storage, with the given policies
+    ///
+    /// # Errors
+    /// 1. The items of the storage can't be listed or read
+    /// 2. There is a deserialization error and the policy was `PromoteSerdeErrors`
+    /// 3. There was a non .json item in a table with the `OnlyJsonFiles` extension policy
+    pub fn with_storage(storage: S, metadata: TableMetadata) -> Result<Self, TableError> {
+        Self::load_from(storage, metadata, &LoadOptions::default())
+    }
+
+    /// Load a table from a storage with the options of a builder that are not
+    /// part of the metadata
+    pub(crate) fn load_from(
+        mut storage: S,
+        metadata: TableMetadata,
+        options: &LoadOptions,
+    ) -> Result<Self, TableError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("load", dir = ?storage.dir()).entered();
+        let mut content = BTreeMap::<Arc<str>, TableElement<T>>::new();
+        let mut metrics = TableMetrics::default();
+        let mut is_modified = false;
+        let read_only = metadata.rw_policy == RWPolicy::ReadOnly;
+        let names = match &options.only_keys {
+            Some(keys) => keys.iter().map(|key| metadata.entry_name(key)).collect(),
+            None => storage.list()?,
+        };
+        // the keys asked for with `only_keys` are known to be missing when
+        // their files can't be found
+        let missing = |e: io::Error, key: &str| match e.kind() {
+            io::ErrorKind::NotFound if options.only_keys.is_some() => {
+                TableError::MissingKeyError(key.to_string())
+            }
+            _ => e.into(),
+        };
+        let mut entries = Vec::new();
+        let mut normalized = BTreeSet::new();
+        for name in &names {
+            let Some(key) = entry_key(&storage, name, &metadata)? else {
+                continue;
+            };
+            // keys that are the same once normalized would be written to the
+            // same file
+            let key = metadata.normalization_policy.normalize(&key).into_owned();
+            if !normalized.insert(key.clone()) {
+                return Err(TableError::NormalizationError(key));
+            }
+            if let Some(pattern) = &options.key_filter {
+                if !glob_match(pattern, &key) {
+                    continue;
+                }
+            }
+            if metadata.size_policy != SizePolicy::Unlimited {
+                let size = storage.size(name).map_err(|e| missing(e, &key))?;
+                match metadata.size_policy {
+                    SizePolicy::Skip(max_size) if size > max_size => continue,
+                    SizePolicy::Error(max_size) if size > max_size => {
+                        return Err(TableError::SizeError(key))
+                    }
+                    _ => {}
+                }
+            }
+            entries.push((name, key));
+            if let Some(max_entries) = options.max_entries {
+                if entries.len() > max_entries {
+                    return Err(TableError::TooManyEntriesError(max_entries));
+                }
+            }
+        }
+        let report = |done| {
+            if let Some(progress) = &options.progress {
+                (progress.0)(done, entries.len());
+            }
+        };
+        event!(debug, entries = entries.len(), "scanned the table");
+        report(0);
+        for (done, (name, key)) in entries.iter().enumerate() {
+            let key = key.as_str();
+            if let Some(cancel) = &options.cancel {
+                if cancel.load(Ordering::Relaxed) {
+                    event!(debug, "load cancelled");
+                    return Err(TableError::CancelledError);
+                }
+            }
+            #[cfg(feature = "tracing")]
+            let started = std::time::Instant::now();
+            // items whose names aren't normalized are renamed, so that they
+            // are written back to the same item
+            let name = match metadata.entry_name(key) {
+                normalized if normalized != **name && !read_only => {
+                    storage.rename(name, &normalized)?;
+                    Cow::Owned(normalized)
+                }
+                _ => Cow::Borrowed(name.as_str()),
+            };
+            let bytes = mmap::read(&storage, &name, options.mmap && read_only)
+                .map_err(|e| missing(e, key))?;
+            metrics.files_loaded += 1;
+            if metadata.duplicate_key_policy == DuplicateKeyPolicy::Error {
+                if let Some(path) = spans::duplicate_key(&bytes) {
+                    event!(error, key, path, "entry repeats a key");
+                    return Err(TableError::DuplicateKeyError(key.to_string(), path));
+                }
+            }
+            let written = Some(content_hash(&bytes));
+            // whether the entry must be rewritten because it was migrated is
+            // returned along with it
+            let parse = |bytes: &[u8]| -> (serde_json::Result<T>, bool) {
+                match &options.migrations {
+                    None => (serde_json::from_slice(bytes), false),
+                    Some(migrations) => match serde_json::from_slice(bytes) {
+                        Ok(value) => {
+                            let (value, migrated) = migrations.migrate(value);
+                            (
+                                serde_json::from_value(value),
+                                migrated && migrations.rewrite,
+                            )
                         }
-                        Err(serde_error) => match metadata.content_policy {
-                            ContentPolicy::IgnoreSerdeErrors => Ok(()),
-                            ContentPolicy::PromoteSerdeErrors => Err(serde_error.into()),
-                        },
+                        Err(e) => (Err(e), false),
                     },
-                    Err(e) => Err(TableError::FileOpError(e)),
                 }
-            } else {
-                match metadata.extension_policy {
-                    ExtensionPolicy::OnlyJsonFiles => Err(TableError::JsonError),
-                    ExtensionPolicy::IgnoreNonJson => Ok(()),
+            };
+            let (info, migrated) = parse(&bytes);
+            is_modified |= migrated;
+            // entries that aren't json are given another chance as jsonc
+            let (info, preserved) = match (info, metadata.syntax_policy) {
+                (Err(e), SyntaxPolicy::Jsonc) => match parse(&jsonc::strip(&bytes)) {
+                    (Ok(info), migrated) => {
+                        is_modified |= migrated;
+                        (Ok(info), !migrated)
+                    }
+                    (Err(_), _) => (Err(e), false),
+                },
+                (info, _) => (info, false),
+            };
+            // and then as json5
+            #[cfg(feature = "json5")]
+            let (info, preserved) = match (info, metadata.json5_policy) {
+                (Err(e), Json5Policy::Disabled) => (Err(e), false),
+                (Err(e), policy) => match json5_to_json(&bytes) {
+                    Some(json) => {
+                        let (info, migrated) = parse(&json);
+                        is_modified |= migrated || policy == Json5Policy::ConvertToJson;
+                        (info, !migrated && policy == Json5Policy::Preserve)
+                    }
+                    None => (Err(e), false),
+                },
+                (info, _) => (info, preserved),
+            };
+            match info {
+                Ok(info) => {
+                    // preserved entries are taken as already written in the
+                    // format of the table, so that they are only written when
+                    // they change
+                    let written = match preserved {
+                        true => Some(content_hash(&metadata.to_vec(&info)?)),
+                        false => written,
+                    };
+                    event!(trace, key, elapsed = ?started.elapsed(), "loaded entry");
+                    let version = match metadata.concurrency_policy {
+                        ConcurrencyPolicy::LastWriteWins => 0,
+                        ConcurrencyPolicy::Optimistic => {
+                            concurrency::read_version(&storage, &metadata, key)?
+                        }
+                    };
+                    let element = TableElement {
+                        info,
+                        written,
+                        version,
+                    };
+                    content.insert(key.into(), element);
                 }
+                Err(serde_error) => match metadata.content_policy {
+                    ContentPolicy::IgnoreSerdeErrors => {
+                        metrics.parse_failures += 1;
+                        event!(warn, key, error = %serde_error, "skipped entry");
+                    }
+                    ContentPolicy::PromoteSerdeErrors => {
+                        event!(error, key, error = %serde_error, "entry can't be deserialized");
+                        return Err(serde_error.into());
+                    }
+                },
             }
-        })?;
+            report(done + 1);
+        }
         Ok(Table {
-            metadata,
-            dir: dir.as_ref().to_path_buf(),
+            storage,
             content,
-            is_modified: false,
+            metadata,
+            is_modified,
+            unique_constraints: Vec::new(),
+            metrics,
+            stamper: None,
+            drop_failure: DropFailure::Panic,
+            merge: None,
+            expiry: None,
+            temporary: None,
+            frozen: BTreeSet::new(),
+            evicted: BTreeMap::new(),
+            #[cfg(feature = "git")]
+            git_template: None,
         })
     }
 
-    /// It appends an element to the table and opens a file `{dir}/{fname}.json`
+    /// It appends an element to the table and creates a file `{dir}/{fname}.json`
     /// when the table has been created with write policy.
-    /// It doesn't write back the file, it only opens it, creating it.
+    /// It doesn't write back the file, it only creates it.
     ///
     /// # Errors
     /// 1. If you don't have permission to write
     /// 2. If you cant create a new file
     /// 3. If an element without a file already exists with the same name
-    /// can only happen if while executing your aplication you deleted a file
-    pub fn push(&mut self, fname: &str, info_elem: T) -> Result<(), TableError> {
+    ///    can only happen if while executing your aplication you deleted a file
+    /// 4. If the element violates a unique constraint
+    /// 5. If the file can't be synced as the durability policy requires
+    pub fn push(&mut self, fname: &str, mut info_elem: T) -> Result<(), TableError> {
         self.mod_permissions()?;
-        let mut f_elem_name = self.dir.clone();
-        f_elem_name.push(format!("{}.json", fname));
-        let f_elem = File::options()
-            .read(true)
-            .write(true)
-            .create_new(true)
-            .open(&f_elem_name)?;
-        let element = TableElement {
-            file: f_elem,
-            info: info_elem,
-        };
-        if let Some(e) = self.content.insert(fname.into(), element) {
-            drop(e.file);
-            fs::remove_file(f_elem_name)?;
+        let fname = &*self.metadata.normalization_policy.normalize(fname);
+        if let Some(stamp) = self.stamper {
+            stamp(&mut info_elem, unix_millis(), true);
+        }
+        self.check_unique_push(&info_elem)?;
+        if self.evicted.contains_key(fname) {
+            return Err(TableError::PushError(fname.into()));
+        }
+        let name = self.metadata.entry_name(fname);
+        self.create_item(&name)?;
+        let element = TableElement::new(info_elem);
+        if self.content.insert(fname.into(), element).is_some() {
+            self.storage.delete(&name)?;
             return Err(TableError::PushError(fname.into()));
         }
+        long_keys::record_key(&mut self.storage, &self.metadata, fname)?;
         self.is_modified = true;
+        self.metadata
+            .durability_policy
+            .apply(&self.storage, [name.as_str()])?;
+        self.audit(serde_json::json!({"op": "push", "key": fname}))?;
+        Ok(())
+    }
+
+    /// Append the elements of an iterator of names and elements, which don't
+    /// need to be Clone nor Copy
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with an individual `push`, in which case
+    ///    the elements before it are kept
+    pub fn append_pairs<Q, I>(&mut self, pairs: I) -> Result<(), TableError>
+    where
+        Q: AsRef<str>,
+        I: IntoIterator<Item = (Q, T)>,
+    {
+        for (fname, element) in pairs {
+            self.push(fname.as_ref(), element)?;
+        }
+        Ok(())
+    }
+
+    /// Append an array of items, all of them or none. When a `push` fails,
+    /// the elements pushed before it are popped again, so neither the table
+    /// nor its directory keep any of them
+    ///
+    /// # Errors
+    /// 1. Whenever the length of names and elements is not the same
+    /// 2. Whenever there is an error with an individual `push`, which is the
+    ///    one returned even if some `pop` of the rollback fails too
+    pub fn append_or_rollback<Q: AsRef<str>>(
+        &mut self,
+        fnames: &[Q],
+        elements: Vec<T>,
+    ) -> Result<(), TableError> {
+        if elements.len() != fnames.len() {
+            return Err(TableError::AppendLengthError);
+        }
+        for (pushed, (element, fname)) in elements.into_iter().zip(fnames).enumerate() {
+            if let Err(e) = self.push(fname.as_ref(), element) {
+                for fname in fnames[..pushed].iter().rev() {
+                    // the error of the push is more telling than the ones of
+                    // the rollback
+                    let _ = self.pop(fname.as_ref());
+                }
+                return Err(e);
+            }
+        }
         Ok(())
     }
 
@@ -181,16 +906,29 @@ where
     ///
     /// # Errors
     /// 1. If you don't have permission to write
-    /// 2. You try to delete a non existing element
-    /// 2. If you cant delete the file
+    /// 2. The element is frozen
+    /// 3. You try to delete a non existing element
+    /// 4. If you cant delete the file
     pub fn pop(&mut self, fname: &str) -> Result<(), TableError> {
         self.mod_permissions()?;
+        self.check_frozen(fname)?;
+        let fname = &*self.metadata.normalization_policy.normalize(fname);
+        self.restore(fname)?;
         self.is_modified = true;
         match self.content.remove(fname) {
             Some(_) => {
-                let mut f_elem = self.dir.clone();
-                f_elem.push(format!("{}.json", fname));
-                fs::remove_file(f_elem).map_err(|err| err.into())
+                let name = self.metadata.entry_name(fname);
+                if self.metadata.symlink_policy == SymlinkPolicy::FollowAndPopTarget {
+                    if let Some(target) = self.storage.link_target(&name)? {
+                        fs::remove_file(target)?;
+                    }
+                }
+                self.storage.delete(&name)?;
+                long_keys::forget_key(&mut self.storage, &self.metadata, fname)?;
+                if self.metadata.concurrency_policy == ConcurrencyPolicy::Optimistic {
+                    concurrency::remove_version(&mut self.storage, &self.metadata, fname)?;
+                }
+                self.audit(serde_json::json!({"op": "pop", "key": fname}))
             }
             None => Err(TableError::PopError(fname.to_string())),
         }
@@ -202,27 +940,98 @@ where
     ///
     /// # Errors
     /// 1. If you don't have permission to write
-    /// 2. The element doesn't exist
-    /// 2. If you can't create the `.json_soft_delete` file
-    /// 3. If you have serialization problems
-    /// 4, If you cant `pop` the element
+    /// 2. The element is frozen
+    /// 3. The element doesn't exist
+    /// 4. If you can't create the `.json_soft_delete` file
+    /// 5. If you have serialization problems
+    /// 6. If you cant `pop` the element
     pub fn soft_pop(&mut self, fname: &str, alt_name: Option<&str>) -> Result<(), TableError> {
         self.mod_permissions()?;
+        self.check_frozen(fname)?;
+        let fname = &*self.metadata.normalization_policy.normalize(fname);
+        self.restore(fname)?;
         match self.content.get(fname) {
             Some(content) => {
-                let mut f_elem = self.dir.clone();
-                f_elem.push(format!("{}.json_soft_delete", alt_name.unwrap_or(fname)));
-                let file = File::options().write(true).create_new(true).open(f_elem)?;
-                serde_json::to_writer_pretty(file, &content.info)?;
+                let soft_name = format!(
+                    "{}.{SOFT_DELETE_EXTENSION}",
+                    self.metadata.item_stem(alt_name.unwrap_or(fname))
+                );
+                let soft_content = self.metadata.to_vec(&content.info)?;
+                self.create_item(&soft_name)?;
+                self.storage.write(&soft_name, &soft_content)?;
                 self.pop(fname)?;
                 Ok(())
             }
-            None => {
-                Err(TableError::PopError(fname.to_string()))
-            }
+            None => Err(TableError::PopError(fname.to_string())),
         }
     }
 
+    /// Rename a element
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element is frozen
+    /// 3. If you try to rename a non existing element
+    /// 4. If there is already an element or a file with the new name
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        self.check_frozen(old_name)?;
+        let old_name = &*self.metadata.normalization_policy.normalize(old_name);
+        let new_name = &*self.metadata.normalization_policy.normalize(new_name);
+        self.restore(old_name)?;
+        if !self.content.contains_key(old_name) {
+            return Err(TableError::PopError(old_name.to_string()));
+        }
+        if self.content.contains_key(new_name) || self.evicted.contains_key(new_name) {
+            return Err(TableError::PushError(new_name.to_string()));
+        }
+        // creating the new file first keeps the rename from overwriting files
+        // that are not part of the table
+        self.storage.create(&self.metadata.entry_name(new_name))?;
+        self.storage.rename(
+            &self.metadata.entry_name(old_name),
+            &self.metadata.entry_name(new_name),
+        )?;
+        long_keys::forget_key(&mut self.storage, &self.metadata, old_name)?;
+        long_keys::record_key(&mut self.storage, &self.metadata, new_name)?;
+        // the element has just been checked, therefore the unwrap
+        let element = self.content.remove(old_name).unwrap();
+        self.content.insert(new_name.into(), element);
+        if self.metadata.concurrency_policy == ConcurrencyPolicy::Optimistic {
+            concurrency::rename_version(&mut self.storage, &self.metadata, old_name, new_name)?;
+        }
+        self.is_modified = true;
+        self.audit(serde_json::json!({"op": "rename", "key": old_name, "new_key": new_name}))
+    }
+
+    /// Convert every element of the table and write the results into a new
+    /// table of `U` in `dest_dir`. The new table has the same policies, unless
+    /// this one is read only, in which case it gets the default write policy.
+    /// The original table is left untouched, and its evicted elements are read
+    /// without being brought back into it. Those that can't be read are left
+    /// out
+    ///
+    /// # Errors
+    /// 1. The new table can't be created
+    /// 2. Some converted element can't be pushed or written
+    pub fn map_into<U, Q, F>(&self, dest_dir: Q, f: F) -> Result<Table<U>, TableError>
+    where
+        U: Serialize + DeserializeOwned,
+        Q: AsRef<Path>,
+        F: Fn(&T) -> U,
+    {
+        let mut metadata = self.metadata;
+        if metadata.rw_policy == RWPolicy::ReadOnly {
+            metadata.rw_policy = RWPolicy::default();
+        }
+        let mut table = Table::new(dest_dir, metadata)?;
+        for (name, element) in self.all_elements() {
+            table.push(name, f(&element.info))?;
+        }
+        table.write_back()?;
+        Ok(table)
+    }
+
     /// Returns true when a mutable reference has been taken in the past or when
     /// some item(s) has been pushed popped or appended. If after an operation
     /// there is a `write_back` it will return false again.
@@ -234,64 +1043,427 @@ where
     }
 
     /// Get the names of the files aka the table's primary keys
-    pub fn get_table_keys(&self) -> Keys<String, TableElement<T>> {
+    pub fn get_table_keys(&self) -> Keys<'_, Arc<str>, TableElement<T>> {
         self.content.keys()
     }
 
     /// An iterator over names and elements
-    pub fn iter(&self) -> Iter<String, TableElement<T>> {
+    pub fn iter(&self) -> Iter<'_, Arc<str>, TableElement<T>> {
         self.content.iter()
     }
 
+    /// An iterator over names and mutable elements
+    pub fn iter_mut(&mut self) -> IterMut<'_, Arc<str>, TableElement<T>> {
+        self.is_modified = true;
+        self.content.iter_mut()
+    }
+
+    /// A page of at most `limit` elements, skipping the first `offset` ones.
+    /// The elements are ordered by key, so pages are stable as long as the
+    /// table doesn't change
+    pub fn page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Take<Skip<Iter<'_, Arc<str>, TableElement<T>>>> {
+        self.content.iter().skip(offset).take(limit)
+    }
+
+    /// A page of at most `limit` elements whose keys come after `key`, which
+    /// is usually the last key of the previous page. Unlike `page`, it doesn't
+    /// go through the skipped elements, and it's not affected by changes in
+    /// the previous pages
+    pub fn page_after(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> Take<Range<'_, Arc<str>, TableElement<T>>> {
+        self.content
+            .range::<str, _>((Bound::Excluded(key), Bound::Unbounded))
+            .take(limit)
+    }
+
     /// Get the values stored in the table
-    pub fn get_table_content(&self) -> Values<String, TableElement<T>> {
+    pub fn get_table_content(&self) -> Values<'_, Arc<str>, TableElement<T>> {
         self.content.values()
     }
 
     /// Get the values stored in the table in a convenient mutable reference
-    pub fn get_mut_table_content(&mut self) -> ValuesMut<String, TableElement<T>> {
+    pub fn get_mut_table_content(&mut self) -> ValuesMut<'_, Arc<str>, TableElement<T>> {
         self.is_modified = true;
         self.content.values_mut()
     }
 
+    /// An iterator over the information of the elements
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.content.values().map(|element| &element.info)
+    }
+
+    /// An iterator over the mutable information of the elements
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.is_modified = true;
+        self.content.values_mut().map(|element| &mut element.info)
+    }
+
     /// Get an individual element of the table by key
     pub fn get_element(&self, entry_name: &str) -> Option<&TableElement<T>> {
-        self.content.get(entry_name)
+        let key = self.metadata.normalization_policy.normalize(entry_name);
+        self.content
+            .get(&*key)
+            .or_else(|| self.evicted_element(&key))
     }
 
-    /// Get an individual mutable element of the table by key
+    /// Get an individual mutable element of the table by key. Frozen elements
+    /// are never given
     pub fn get_mut_element(&mut self, entry_name: &str) -> Option<&mut TableElement<T>> {
+        if self.is_frozen(entry_name) {
+            return None;
+        }
+        let key = self.metadata.normalization_policy.normalize(entry_name);
+        self.restore(&key).ok()?;
         self.is_modified = true;
-        self.content.get_mut(entry_name)
+        self.content.get_mut(&*key)
+    }
+
+    /// Get several mutable elements at once, in the order of `keys`. Gives
+    /// `None` if some key is missing, frozen or repeated
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        keys: [&str; N],
+    ) -> Option<[&mut TableElement<T>; N]> {
+        // keys that normalize to the same one are repeated too
+        let keys = keys.map(|key| {
+            self.metadata
+                .normalization_policy
+                .normalize(key)
+                .into_owned()
+        });
+        for (i, key) in keys.iter().enumerate() {
+            if self.get_element(key).is_none() || self.is_frozen(key) || keys[..i].contains(key) {
+                return None;
+            }
+            self.restore(key).ok()?;
+        }
+        self.is_modified = true;
+        let mut elements: [Option<&mut TableElement<T>>; N] = std::array::from_fn(|_| None);
+        for (key, element) in self.content.iter_mut() {
+            if let Some(i) = keys.iter().position(|k| **k == **key) {
+                elements[i] = Some(element);
+            }
+        }
+        // every key was checked to be in the table, therefore the unwrap
+        Some(elements.map(Option::unwrap))
     }
 
     /// Write the changes in the corresponding files,
     ///
     /// # Errors
     /// 1. If you don't have permission to write
-    /// 2. There are problems with serialization
+    /// 2. A unique constraint is violated, in which case nothing is written
+    /// 3. There are problems with serialization
+    /// 4. The files can't be synced as the durability policy requires
+    /// 5. The changes can't be committed, when committing with git
     pub fn write_back(&mut self) -> Result<(), TableError> {
+        self.write_back_with(None)
+    }
+
+    /// Write the changes like `write_back`, checking `cancel` before writing
+    /// every file. When it's set the write back stops, leaving some files
+    /// written and the rest untouched, and the table stays modified so that
+    /// the next write back writes every file again
+    ///
+    /// # Errors
+    /// 1. The write back was cancelled
+    /// 2. Whenever there's an error with the `write_back`
+    pub fn write_back_cancellable(&mut self, cancel: &AtomicBool) -> Result<(), TableError> {
+        self.write_back_with(Some(cancel))
+    }
+
+    /// Write the changes of a table that can be written and drop it, so that
+    /// the errors of the write back can be handled instead of panicking on
+    /// drop. Whatever happens, nothing more is written when it's dropped
+    ///
+    /// # Errors
+    /// 1. Whenever there's an error with the `write_back`
+    pub fn close(mut self) -> Result<(), TableError> {
+        let result = match self.metadata.rw_policy {
+            RWPolicy::Write(_) => self.write_back(),
+            RWPolicy::ReadOnly => Ok(()),
+        };
+        self.is_modified = false;
+        result
+    }
+
+    fn write_back_with(&mut self, cancel: Option<&AtomicBool>) -> Result<(), TableError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("write_back", dir = ?self.storage.dir()).entered();
+        let whole_table = self.metadata.atomicity_policy == AtomicityPolicy::WholeTable;
+        match self.stage(cancel, whole_table)? {
+            Some(staged) => self.commit_staged(staged),
+            None => Ok(()),
+        }
+    }
+
+    /// The first half of a write back, that writes the modified elements
+    /// into their items, or into staging items when `whole_table`. Gives
+    /// nothing when the table wasn't modified. When staging fails the staging
+    /// items are removed and the table stays modified
+    pub(crate) fn stage(
+        &mut self,
+        cancel: Option<&AtomicBool>,
+        whole_table: bool,
+    ) -> Result<Option<StagedWrite>, TableError> {
+        self.stage_range(cancel, whole_table, (Bound::Unbounded, Bound::Unbounded))
+    }
+
+    /// Like `stage`, but only the elements whose keys are in `range` are
+    /// written, so the rest must be unmodified
+    pub(crate) fn stage_range(
+        &mut self,
+        cancel: Option<&AtomicBool>,
+        whole_table: bool,
+        range: (Bound<&str>, Bound<&str>),
+    ) -> Result<Option<StagedWrite>, TableError> {
         self.mod_permissions()?;
-        if self.is_modified() {
-            self.is_modified = false;
-            for table_element in self.content.values_mut() {
-                let file = &mut table_element.file;
-                file.set_len(0)?;
-                file.seek(SeekFrom::Start(0))?;
-                serde_json::to_writer_pretty(file, &table_element.info)?;
+        self.metrics.write_backs += 1;
+        if !self.is_modified() {
+            return Ok(None);
+        }
+        let started = Instant::now();
+        self.check_unique()?;
+        self.is_modified = false;
+        let names: Vec<_> = self
+            .content
+            .range::<str, _>(range)
+            .map(|(name, _)| self.metadata.entry_name(name))
+            .collect();
+        let mut staged = StagedWrite {
+            started,
+            written: Vec::new(),
+            staged: Vec::new(),
+        };
+        let result = self.write_entries(
+            &names,
+            range,
+            cancel,
+            &mut staged.written,
+            whole_table.then_some(&mut staged.staged),
+        );
+        match result {
+            Ok(()) => Ok(Some(staged)),
+            Err(e) => {
+                self.discard_staged(staged);
+                Err(e)
+            }
+        }
+    }
+
+    /// Remove the staging items of a write back that won't be finished, so
+    /// that the table stays as it was and modified
+    pub(crate) fn discard_staged(&mut self, staged: StagedWrite) {
+        for (_, staging, _) in staged.staged {
+            // the staging items are left over otherwise, where they are
+            // ignored and overwritten by the next write back
+            let _ = self.storage.delete(&staging);
+        }
+        self.is_modified = true;
+    }
+
+    /// The second half of a write back, that moves the staging items into
+    /// place, if there are any, and syncs, logs and commits the written items.
+    /// When it fails the staging items that are left are removed and the
+    /// table stays modified
+    pub(crate) fn commit_staged(&mut self, staged: StagedWrite) -> Result<(), TableError> {
+        let StagedWrite {
+            started,
+            written,
+            staged,
+        } = staged;
+        let mut staged = staged.into_iter();
+        while let Some((key, staging, hash)) = staged.next() {
+            if let Err(e) = self.commit_entry(&key, &staging, hash) {
+                let uncommitted = std::iter::once((key, staging, hash)).chain(staged);
+                self.discard_staged(StagedWrite {
+                    started,
+                    written: Vec::new(),
+                    staged: uncommitted.collect(),
+                });
+                return Err(e);
+            }
+        }
+        let result = self.finish_commit(&written);
+        match result {
+            Ok(()) => self.metrics.last_flush = Some(started.elapsed()),
+            Err(_) => self.is_modified = true,
+        }
+        result
+    }
+
+    /// Sync, log and commit the items written by a write back
+    fn finish_commit(&mut self, written: &[String]) -> Result<(), TableError> {
+        self.metadata
+            .durability_policy
+            .apply(&self.storage, written.iter().map(String::as_str))?;
+        self.audit(serde_json::json!({"op": "write_back", "entries": written.len()}))?;
+        #[cfg(feature = "git")]
+        self.git_commit(written.len())?;
+        event!(debug, written = written.len(), "wrote back the table");
+        Ok(())
+    }
+
+    /// Move the staging item of an entry into place, keeping the replaced
+    /// content in the history and bumping its version
+    fn commit_entry(&mut self, key: &str, staging: &str, hash: u64) -> Result<(), TableError> {
+        let name = self.metadata.entry_name(key);
+        if let Some(keep) = self.kept_versions() {
+            let content = self.storage.read(staging)?;
+            history::save_version(
+                &mut self.storage,
+                &self.metadata,
+                key,
+                &name,
+                &content,
+                keep,
+            )?;
+        }
+        self.storage.rename(staging, &name)?;
+        if let Some(element) = self.content.get_mut(key) {
+            element.written = Some(hash);
+            if self.metadata.concurrency_policy == ConcurrencyPolicy::Optimistic {
+                element.version = concurrency::bump_version(
+                    &mut self.storage,
+                    &self.metadata,
+                    key,
+                    element.version,
+                )?;
             }
         }
         Ok(())
     }
 
+    /// Write the modified elements of the table whose keys are in `range`
+    /// into their items, or into staging items when `staged` is given, keeping
+    /// the names of the items written in `written`
+    fn write_entries(
+        &mut self,
+        names: &[String],
+        range: (Bound<&str>, Bound<&str>),
+        cancel: Option<&AtomicBool>,
+        written: &mut Vec<String>,
+        mut staged: Option<&mut Vec<(Arc<str>, String, u64)>>,
+    ) -> Result<(), TableError> {
+        let kept_versions = self.kept_versions();
+        let now = unix_millis();
+        let entries = self.content.range_mut::<str, _>(range);
+        for ((key, table_element), name) in entries.zip(names) {
+            if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+                event!(debug, "write back cancelled");
+                self.is_modified = true;
+                return Err(TableError::CancelledError);
+            }
+            if self.frozen.contains(&**key) {
+                continue;
+            }
+            let mut content = self.metadata.to_vec(&table_element.info)?;
+            // unchanged elements are not written, so their items keep
+            // their modification time
+            if table_element.written == Some(content_hash(&content)) {
+                continue;
+            }
+            let stored = match (self.metadata.format_policy, table_element.written) {
+                (FormatPolicy::Preserve, Some(_)) => match self.storage.read(name) {
+                    Ok(stored) => Some(stored),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                },
+                _ => None,
+            };
+            if let Some(stored) = &stored {
+                content = splice::splice(stored, &table_element.info, self.metadata.float_policy)?;
+                if table_element.written == Some(content_hash(&content)) {
+                    continue;
+                }
+            }
+            let resolved = external::resolve_edit(
+                &self.storage,
+                &self.metadata,
+                self.merge.as_ref(),
+                key,
+                table_element,
+                &mut content,
+            );
+            match resolved {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    self.is_modified = true;
+                    return Err(e);
+                }
+            }
+            if let Some(stamp) = self.stamper {
+                if self.storage.read(name)? != content {
+                    stamp(&mut table_element.info, now, false);
+                    content = match &stored {
+                        Some(stored) => {
+                            splice::splice(stored, &table_element.info, self.metadata.float_policy)?
+                        }
+                        None => self.metadata.to_vec(&table_element.info)?,
+                    };
+                }
+            }
+            match staged.as_deref_mut() {
+                // the history and the version of staged entries are only kept
+                // once they are committed
+                Some(staged) => {
+                    let staging = staging_name(name);
+                    self.storage.write(&staging, &content)?;
+                    if let Some(permissions) = self.metadata.permissions {
+                        self.storage.set_mode(&staging, permissions.file_mode)?;
+                    }
+                    staged.push((key.clone(), staging, content_hash(&content)));
+                }
+                None => {
+                    if let Some(keep) = kept_versions {
+                        history::save_version(
+                            &mut self.storage,
+                            &self.metadata,
+                            key,
+                            name,
+                            &content,
+                            keep,
+                        )?;
+                    }
+                    self.storage.write(name, &content)?;
+                    table_element.written = Some(content_hash(&content));
+                    if self.metadata.concurrency_policy == ConcurrencyPolicy::Optimistic {
+                        table_element.version = concurrency::bump_version(
+                            &mut self.storage,
+                            &self.metadata,
+                            key,
+                            table_element.version,
+                        )?;
+                    }
+                }
+            }
+            self.metrics.bytes_written += content.len() as u64;
+            written.push(name.to_string());
+        }
+        Ok(())
+    }
+
     /// the number of elements in the table
     pub fn len(&self) -> usize {
-        self.content.len()
+        self.content.len() + self.evicted.len()
     }
 
     /// Whether the table is empty
     pub fn is_empty(&self) -> bool {
-        self.content.is_empty()
+        self.content.is_empty() && self.evicted.is_empty()
+    }
+
+    /// Create an empty item with the permissions of the table
+    fn create_item(&mut self, name: &str) -> Result<(), TableError> {
+        Ok(create_item(&mut self.storage, &self.metadata, name)?)
     }
 
     /// Table has been declared with the ability to modify the file system
@@ -302,15 +1474,160 @@ where
         }
     }
 
+    /// The policies in effect for the table
+    pub fn metadata(&self) -> &TableMetadata {
+        &self.metadata
+    }
+
+    /// The directory of the table, which is empty for tables that are not
+    /// kept in a local directory
+    pub fn dir(&self) -> &Path {
+        self.storage.dir().unwrap_or(Path::new(""))
+    }
+
+    /// The storage in which the table is kept
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Store the current policies of the table in its directory, so that they
+    /// are used by default when loading it
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. If you can't write the policies
+    pub fn store_metadata(&mut self) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let metadata = serde_json::to_vec_pretty(&self.metadata)?;
+        self.storage.write(&config::metadata_name(), &metadata)?;
+        Ok(())
+    }
+
     /// Table has been declared with the ability to modify the file system
     pub fn has_mod_permissions(&self) -> bool {
         self.mod_permissions().is_ok()
     }
+
+    /// Make the table read only, writing its changes first if it could write.
+    /// Whatever happens, nothing more is written when it's dropped, as with
+    /// `close`
+    ///
+    /// # Errors
+    /// 1. Whenever there's an error with the `write_back`
+    pub fn into_read_only(mut self) -> Result<Self, TableError> {
+        let result = match self.metadata.rw_policy {
+            RWPolicy::Write(_) => self.write_back(),
+            RWPolicy::ReadOnly => Ok(()),
+        };
+        self.is_modified = false;
+        self.metadata.rw_policy = RWPolicy::ReadOnly;
+        result.map(|()| self)
+    }
+
+    /// Make a read only table writable, with the default write policy. Tables
+    /// that can already write are left as they are
+    ///
+    /// # Errors
+    /// 1. The directory of the table can't be written
+    pub fn try_into_writable(mut self) -> Result<Self, TableError> {
+        if self.metadata.rw_policy != RWPolicy::ReadOnly {
+            return Ok(self);
+        }
+        if let Some(dir) = self.storage.dir() {
+            if fs::metadata(dir)?.permissions().readonly() {
+                return Err(TableError::FileOpError(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} is read only", dir.display()),
+                )));
+            }
+        }
+        self.metadata.rw_policy = RWPolicy::default();
+        Ok(self)
+    }
 }
 
-impl<T> Table<T>
+/// The storage, elements, metadata, whether it was modified and the directory
+/// of a temporary table, as taken out of a table
+type TableParts<T, S> = (
+    S,
+    BTreeMap<Arc<str>, TableElement<T>>,
+    TableMetadata,
+    bool,
+    Option<PathBuf>,
+);
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage + Default,
+{
+    /// Take the parts of the table out of it, along with the directory to
+    /// remove when it's a temporary table. The emptied table is dropped
+    /// without writing anything back
+    fn into_parts(mut self) -> TableParts<T, S> {
+        // the evicted elements that can't be read again are lost with the table
+        let _ = self.reload_evicted();
+        let is_modified = self.is_modified;
+        self.is_modified = false;
+        (
+            std::mem::take(&mut self.storage),
+            std::mem::take(&mut self.content),
+            self.metadata,
+            is_modified,
+            self.temporary.take(),
+        )
+    }
+
+    /// Take the elements out of the table as a map of their names to them. The
+    /// emptied table is dropped without writing anything back
+    pub fn into_map(self) -> HashMap<String, T> {
+        let (_, content, _, _, temporary) = self.into_parts();
+        temporary::remove(temporary);
+        content
+            .into_iter()
+            .map(|(name, element)| (name.to_string(), element.info))
+            .collect()
+    }
+
+    /// Convert the information of every element keeping the same storage and
+    /// policies. Nothing is converted if any conversion fails
+    fn try_map_info<U, F>(mut self, f: F) -> Result<Table<U, S>, TableError>
+    where
+        U: Serialize + DeserializeOwned,
+        F: Fn(&T) -> Result<U, TableError>,
+    {
+        self.reload_evicted()?;
+        let content = self
+            .content
+            .iter()
+            .map(|(name, element)| Ok((name.clone(), TableElement::new(f(&element.info)?))))
+            .collect::<Result<_, TableError>>()?;
+        let frozen = std::mem::take(&mut self.frozen);
+        let (storage, _, metadata, is_modified, temporary) = self.into_parts();
+        Ok(Table {
+            storage,
+            content,
+            metadata,
+            is_modified,
+            unique_constraints: Vec::new(),
+            metrics: TableMetrics::default(),
+            stamper: None,
+            drop_failure: DropFailure::Panic,
+            merge: None,
+            expiry: None,
+            temporary,
+            frozen,
+            evicted: BTreeMap::new(),
+            #[cfg(feature = "git")]
+            git_template: None,
+        })
+    }
+}
+
+impl<T, S> Table<T, S>
 where
     T: Serialize + DeserializeOwned + Clone,
+    S: Storage,
 {
     /// Append an array of items when they are Clone but not Copy
     ///
@@ -332,31 +1649,12 @@ where
 
         Ok(())
     }
-
-    /// Rename a element
-    ///
-    /// # Errors
-    /// 1. If you don't have permission to write
-    /// 2. If you try to rename a non existing element
-    /// 3. If you have trouble pushing the element with the new name
-    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableError> {
-        self.mod_permissions()?;
-        self.is_modified = true;
-        let name_string = old_name.to_string();
-        let info = self
-            .get_element(old_name)
-            .ok_or(TableError::PopError(name_string))?
-            .info
-            .clone();
-        self.pop(old_name)?;
-        self.push(new_name, info)?;
-        Ok(())
-    }
 }
 
-impl<T> Table<T>
+impl<T, S> Table<T, S>
 where
     T: Serialize + DeserializeOwned + Copy,
+    S: Storage,
 {
     /// Append an array of items when they are Copy
     ///
@@ -380,38 +1678,94 @@ where
     }
 }
 
-impl<T> Index<&str> for Table<T>
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned + PartialEq,
+    S: Storage,
+{
+    /// Whether the elements of the table are the same as the ones stored in
+    /// a directory, which is loaded as a read only table with the same policies
+    ///
+    /// # Errors
+    /// 1. Whenever there's an error loading the directory
+    pub fn content_eq_dir<Q: AsRef<Path>>(&self, dir: Q) -> Result<bool, TableError> {
+        let metadata = TableMetadata {
+            rw_policy: RWPolicy::ReadOnly,
+            ..self.metadata
+        };
+        Ok(*self == Table::<T>::load(dir, Some(metadata))?)
+    }
+}
+
+impl<T, S, R> PartialEq<Table<T, R>> for Table<T, S>
+where
+    T: Serialize + DeserializeOwned + PartialEq,
+    S: Storage,
+    R: Storage,
+{
+    /// Tables are equal when they have the same keys with equal elements,
+    /// regardless of their directories, policies or storages
+    fn eq(&self, other: &Table<T, R>) -> bool {
+        self.len() == other.len()
+            && self.all_elements().all(|(name, element)| {
+                other
+                    .get_element(name)
+                    .is_some_and(|other| element.info == other.info)
+            })
+    }
+}
+
+impl<T, S> Index<&str> for Table<T, S>
 where
     T: Serialize + DeserializeOwned,
+    S: Storage,
 {
     type Output = TableElement<T>;
     fn index(&self, index: &str) -> &Self::Output {
-        &self.content[index]
+        self.get_element(index).expect("no element with that key")
     }
 }
 
-impl<T> IndexMut<&str> for Table<T>
+impl<T, S> IndexMut<&str> for Table<T, S>
 where
     T: Serialize + DeserializeOwned,
+    S: Storage,
 {
     fn index_mut(&mut self, index: &str) -> &mut Self::Output {
+        if let Err(e) = self.check_frozen(index) {
+            panic!("{e}");
+        }
+        let index = self.metadata.normalization_policy.normalize(index);
+        if let Err(e) = self.restore(&index) {
+            panic!("{e}");
+        }
         self.is_modified = true;
-        self.content.get_mut(index).unwrap()
+        self.content.get_mut(&*index).unwrap()
     }
 }
-impl<T> Drop for Table<T>
+impl<T, S> Drop for Table<T, S>
 where
     T: Serialize + DeserializeOwned,
+    S: Storage,
 {
-    /// Writes back in case the write back is set to automatic
+    /// Writes back in case the write back is set to automatic. Use `close` to
+    /// handle the errors of the write back, or `on_drop_failure` to choose
+    /// what happens with them. Temporary tables are removed instead
     ///
     /// # Panics
-    /// - When there are problems with the write back mainly when
+    /// - When there are problems with the write back, unless the thread is
+    ///   already panicking or another `DropFailure` was chosen, mainly when
     ///     - There are problems with file handles
     ///     - There are problems with serialization
     fn drop(&mut self) {
+        if self.temporary.is_some() {
+            temporary::remove(self.temporary.take());
+            return;
+        }
         if RWPolicy::Write(WriteType::Automatic) == self.metadata.rw_policy {
-            self.write_back().unwrap();
+            if let Err(error) = self.write_back() {
+                self.drop_failed(error);
+            }
         }
     }
 }
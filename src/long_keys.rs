@@ -0,0 +1,95 @@
+use crate::{entry_name, LongKeyPolicy, Storage, TableMetadata, RESERVED_PREFIX};
+use std::{borrow::Cow, io};
+
+/// A hash that stays the same between platforms and versions of Rust, since
+/// it names files that outlive the program. It's 64 bit FNV-1a
+pub(crate) fn stable_hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The name of the item in which the real key of a hashed entry is kept
+fn key_name(stem: &str) -> String {
+    format!("{RESERVED_PREFIX}_key.{stem}")
+}
+
+impl TableMetadata {
+    /// The hash that names the item of an entry instead of its key, when the
+    /// key is too long
+    fn entry_stem(&self, key: &str) -> Option<String> {
+        match self.long_key_policy {
+            LongKeyPolicy::Keep => None,
+            LongKeyPolicy::Hash(max) => {
+                (key.len() > max).then(|| format!("{:016x}", stable_hash(key)))
+            }
+        }
+    }
+
+    /// What the items that go along with an entry are named after, which is
+    /// its key, or its hash when the key is too long
+    pub(crate) fn item_stem<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        self.entry_stem(key).map_or(Cow::Borrowed(key), Cow::Owned)
+    }
+
+    /// The name of the item in which an entry is stored
+    pub(crate) fn entry_name(&self, key: &str) -> String {
+        match self.entry_stem(key) {
+            Some(stem) => format!("{stem}.json"),
+            None => entry_name(key),
+        }
+    }
+}
+
+/// The key of an entry whose item is named after a hash, if it's one
+pub(crate) fn hashed_key<S: Storage>(
+    storage: &S,
+    metadata: &TableMetadata,
+    stem: &str,
+) -> io::Result<Option<String>> {
+    if metadata.long_key_policy == LongKeyPolicy::Keep
+        || stem.len() != 16
+        || !stem.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return Ok(None);
+    }
+    match storage.read(&key_name(stem)) {
+        Ok(key) => Ok(Some(String::from_utf8_lossy(&key).into_owned())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The name and content of the item that keeps the real key of an entry
+/// whose item is named after a hash
+pub(crate) fn key_item(metadata: &TableMetadata, key: &str) -> Option<(String, Vec<u8>)> {
+    let stem = metadata.entry_stem(key)?;
+    Some((key_name(&stem), key.as_bytes().to_vec()))
+}
+
+/// Keep the real key of an entry whose item is named after a hash
+pub(crate) fn record_key<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    key: &str,
+) -> io::Result<()> {
+    match key_item(metadata, key) {
+        Some((name, content)) => storage.write(&name, &content),
+        None => Ok(()),
+    }
+}
+
+/// Remove the real key of an entry whose item is named after a hash
+pub(crate) fn forget_key<S: Storage>(
+    storage: &mut S,
+    metadata: &TableMetadata,
+    key: &str,
+) -> io::Result<()> {
+    match metadata
+        .entry_stem(key)
+        .map(|stem| storage.delete(&key_name(&stem)))
+    {
+        Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    config, content_hash, long_keys, ExtensionPolicy, FsStorage, Storage, Table, TableBuilderError,
+    TableError, RESERVED_PREFIX,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The extension given to the files of the elements removed with `soft_pop`
+pub(crate) const SOFT_DELETE_EXTENSION: &str = "json_soft_delete";
+
+/// What `vacuum` removes from a table. By default everything is removed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VacuumPolicy {
+    /// Remove temporary files, those whose name ends in `.tmp` or `~`
+    pub temp_files: bool,
+    /// Remove the files of the elements removed with `soft_pop`
+    pub soft_deleted: bool,
+    /// Remove empty entry files that are not part of the table, usually left
+    /// by pushes that were never written back
+    pub empty_entries: bool,
+}
+
+impl Default for VacuumPolicy {
+    fn default() -> Self {
+        Self {
+            temp_files: true,
+            soft_deleted: true,
+            empty_entries: true,
+        }
+    }
+}
+
+/// Whether a file of a table's directory is created and managed by the crate
+fn is_managed(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    path.is_file()
+        && (name.starts_with(RESERVED_PREFIX)
+            || matches!(extension, Some("json") | Some(SOFT_DELETE_EXTENSION)))
+}
+
+/// Copy a directory with everything in it
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for dir_entry in fs::read_dir(from)? {
+        let dir_entry = dir_entry?;
+        let to = to.join(dir_entry.file_name());
+        match dir_entry.file_type()?.is_dir() {
+            true => copy_dir(&dir_entry.path(), &to)?,
+            false => {
+                fs::copy(dir_entry.path(), to)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Remove the table in `dir`, returning the paths that were removed. The
+    /// entries and the files managed by the crate are removed, and then the
+    /// directory. Other files are an error with the stored `OnlyJsonFiles`
+    /// extension policy, in which case nothing is removed. With the
+    /// `IgnoreNonJson` policy they are left alone, and so is the directory.
+    /// With `dry_run` nothing is removed, only reported
+    ///
+    /// # Errors
+    /// 1. The stored policies can't be read
+    /// 2. The directory can't be read or some file can't be removed
+    /// 3. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
+    pub fn delete<Q: AsRef<Path>>(dir: Q, dry_run: bool) -> Result<Vec<PathBuf>, TableError> {
+        let metadata = config::read_metadata(&dir)?.unwrap_or_default();
+        let mut managed = Vec::new();
+        let mut has_foreign = false;
+        for dir_entry in fs::read_dir(&dir)? {
+            let path = dir_entry?.path();
+            if is_managed(&path) {
+                managed.push(path);
+            } else {
+                match metadata.extension_policy {
+                    ExtensionPolicy::OnlyJsonFiles => return Err(TableError::JsonError),
+                    ExtensionPolicy::IgnoreNonJson => has_foreign = true,
+                }
+            }
+        }
+        managed.sort();
+        if !has_foreign {
+            managed.push(dir.as_ref().to_path_buf());
+        }
+        if !dry_run {
+            for path in &managed {
+                match path.is_dir() {
+                    true => fs::remove_dir(path)?,
+                    false => fs::remove_file(path)?,
+                }
+            }
+        }
+        Ok(managed)
+    }
+
+    /// Move the whole directory of the table to `new_dir`, and keep working
+    /// from there. The directory is renamed when possible, and copied and then
+    /// removed when it's moved to another device
+    ///
+    /// # Errors
+    /// 1. There's already something in `new_dir`
+    /// 2. The directory can't be renamed, copied or removed
+    pub fn move_to<Q: AsRef<Path>>(&mut self, new_dir: Q) -> Result<(), TableError> {
+        let new_dir = new_dir.as_ref();
+        if fs::symlink_metadata(new_dir).is_ok() {
+            return Err(TableBuilderError::TableAlreadyExistsError.into());
+        }
+        let old_dir = self.dir().to_path_buf();
+        match fs::rename(&old_dir, new_dir) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                copy_dir(&old_dir, new_dir)?;
+                fs::remove_dir_all(&old_dir)?;
+            }
+            result => result?,
+        }
+        self.storage = FsStorage::new(new_dir);
+        Ok(())
+    }
+
+    /// The files of the directory of the table that it doesn't manage, such
+    /// as files with other extensions, directories or files whose name isn't
+    /// valid unicode
+    ///
+    /// # Errors
+    /// 1. The directory can't be read
+    pub fn foreign_files(&self) -> Result<Vec<PathBuf>, TableError> {
+        let mut foreign = Vec::new();
+        for dir_entry in fs::read_dir(self.dir())? {
+            let path = dir_entry?.path();
+            if !is_managed(&path) {
+                foreign.push(path);
+            }
+        }
+        foreign.sort();
+        Ok(foreign)
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Remove the junk that accumulates in the directory of a table, such as
+    /// files left by crashed runs, returning the names of the removed files
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The files can't be listed, read or removed
+    pub fn vacuum(&mut self, policy: VacuumPolicy) -> Result<Vec<String>, TableError> {
+        self.mod_permissions()?;
+        let mut removed = Vec::new();
+        for name in self.storage.list()? {
+            let is_junk = if name.ends_with(".tmp") || name.ends_with('~') {
+                policy.temp_files
+            } else if name.ends_with(&format!(".{SOFT_DELETE_EXTENSION}")) {
+                policy.soft_deleted
+            } else if let Some(stem) = name.strip_suffix(".json") {
+                // entries with long keys are named after their hash
+                let key = long_keys::hashed_key(&self.storage, &self.metadata, stem)?;
+                let key = key.as_deref().unwrap_or(stem);
+                policy.empty_entries
+                    && !name.starts_with(RESERVED_PREFIX)
+                    && !self.content.contains_key(key)
+                    && self.storage.read(&name)?.is_empty()
+            } else {
+                false
+            };
+            if is_junk {
+                self.storage.delete(&name)?;
+                removed.push(name);
+            }
+        }
+        removed.sort();
+        Ok(removed)
+    }
+
+    /// Rewrite every entry in the format of the table, whether it was
+    /// modified or not. Entries written in other formats are converted, and
+    /// when `sync` is set every entry is synced to durable storage afterwards.
+    /// Frozen entries are left as they are
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. A unique constraint is violated, in which case nothing is written
+    /// 3. There are problems with serialization
+    /// 4. Some entry can't be read, written or synced
+    pub fn compact(&mut self, sync: bool) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        self.check_unique()?;
+        for (name, table_element) in &mut self.content {
+            if self.frozen.contains(&**name) {
+                continue;
+            }
+            let name = self.metadata.entry_name(name);
+            let content = self.metadata.to_vec(&table_element.info)?;
+            self.storage.write(&name, &content)?;
+            table_element.written = Some(content_hash(&content));
+            if sync {
+                self.storage.sync(&name)?;
+            }
+        }
+        // the evicted elements are read one at a time and left evicted
+        let evicted: Vec<_> = self
+            .evicted
+            .keys()
+            .filter(|key| !self.frozen.contains(&***key))
+            .cloned()
+            .collect();
+        for key in evicted {
+            let name = self.metadata.entry_name(&key);
+            let content = self.metadata.to_vec(&self.read_element(&key)?.info)?;
+            self.storage.write(&name, &content)?;
+            if sync {
+                self.storage.sync(&name)?;
+            }
+        }
+        self.is_modified = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{Storage, Table, TableElement};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io::{self, Write},
+    mem,
+    sync::{Arc, OnceLock},
+};
+
+/// An approximation of the memory held by a table, in bytes
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct MemoryEstimate {
+    /// The bytes held by the keys of the elements
+    pub keys: usize,
+    /// The bytes held by the values of the elements, estimated as the size of
+    /// their compact json representation
+    pub values: usize,
+    /// The bytes of the table itself, its elements and the sets of keys that
+    /// it keeps track of
+    pub bookkeeping: usize,
+}
+
+impl MemoryEstimate {
+    /// The bytes of the whole estimate
+    pub fn total(&self) -> usize {
+        self.keys + self.values + self.bookkeeping
+    }
+}
+
+/// Counts the bytes written through it, discarding them
+struct ByteCount(usize);
+
+impl Write for ByteCount {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// An approximation of the memory held by the table, so that it can be
+    /// compared with other tables to decide which ones to drop. The values are
+    /// measured by their json representation, without building it, and those
+    /// that can't be serialized aren't counted
+    pub fn estimated_memory(&self) -> MemoryEstimate {
+        let keys = self
+            .content
+            .keys()
+            .chain(self.evicted.keys())
+            .map(|key| key.len())
+            .sum();
+        // the evicted elements only count when they have been read again
+        let values = self
+            .content
+            .values()
+            .chain(self.evicted.values().filter_map(|cell| cell.get()))
+            .map(|element| {
+                let mut count = ByteCount(0);
+                serde_json::to_writer(&mut count, &element.info).map_or(0, |_| count.0)
+            })
+            .sum();
+        let frozen: usize = self
+            .frozen
+            .iter()
+            .map(|key| key.capacity() + mem::size_of::<String>())
+            .sum();
+        let bookkeeping = mem::size_of::<Self>()
+            + self.content.len() * mem::size_of::<(Arc<str>, TableElement<T>)>()
+            + self.evicted.len() * mem::size_of::<(Arc<str>, OnceLock<TableElement<T>>)>()
+            + frozen;
+        MemoryEstimate {
+            keys,
+            values,
+            bookkeeping,
+        }
+    }
+}
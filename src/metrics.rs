@@ -0,0 +1,30 @@
+use crate::{Storage, Table};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// Counters of the work done by a table since it was opened
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct TableMetrics {
+    /// The entries read while loading the table
+    pub files_loaded: usize,
+    /// The entries that couldn't be deserialized and were ignored
+    pub parse_failures: usize,
+    /// The bytes written into entries by write backs
+    pub bytes_written: u64,
+    /// The times that a write back has been requested, whether there was
+    /// something to write or not
+    pub write_backs: usize,
+    /// How long the last write back that wrote something took
+    pub last_flush: Option<Duration>,
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// The counters of the work done by the table
+    pub fn metrics(&self) -> TableMetrics {
+        self.metrics
+    }
+}
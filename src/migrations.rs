@@ -0,0 +1,75 @@
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt};
+
+/// A function that upgrades the json of an entry from one version to the next
+type Migration = Box<dyn Fn(Value) -> Value + Send + Sync>;
+
+/// A registry of the migrations needed to upgrade entries written with older
+/// versions of a type. The version of each entry is stored in one of its
+/// fields, and entries without that field are considered to be version 0
+pub struct Migrations {
+    field: String,
+    steps: BTreeMap<u64, Migration>,
+    pub(crate) rewrite: bool,
+}
+
+impl Migrations {
+    /// Create an empty registry in which the version of the entries is stored
+    /// in `field`
+    pub fn new<S: Into<String>>(field: S) -> Self {
+        Self {
+            field: field.into(),
+            steps: BTreeMap::new(),
+            rewrite: false,
+        }
+    }
+
+    /// Register the migration that upgrades entries from version `from` to
+    /// version `from + 1`. The version field is updated after the migration,
+    /// so the migration doesn't need to do it
+    pub fn register<F>(mut self, from: u64, migration: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        self.steps.insert(from, Box::new(migration));
+        self
+    }
+
+    /// Mark the table as modified when some entry is migrated while loading,
+    /// so that the upgraded entries are written on the next write back
+    pub fn rewrite_on_write_back(mut self) -> Self {
+        self.rewrite = true;
+        self
+    }
+
+    /// The version that entries have after all the registered migrations
+    pub fn current_version(&self) -> u64 {
+        self.steps.keys().next_back().map_or(0, |last| last + 1)
+    }
+
+    /// Apply all the pending migrations to the json of an entry. It also
+    /// returns whether any migration was applied
+    pub fn migrate(&self, mut value: Value) -> (Value, bool) {
+        let mut migrated = false;
+        let mut version = value.get(&self.field).and_then(Value::as_u64).unwrap_or(0);
+        while let Some(migration) = self.steps.get(&version) {
+            value = migration(value);
+            version += 1;
+            if let Some(object) = value.as_object_mut() {
+                object.insert(self.field.clone(), version.into());
+            }
+            migrated = true;
+        }
+        (value, migrated)
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("field", &self.field)
+            .field("versions", &self.steps.keys().collect::<Vec<_>>())
+            .field("rewrite", &self.rewrite)
+            .finish()
+    }
+}
@@ -0,0 +1,38 @@
+use crate::Storage;
+use std::{io, ops::Deref};
+
+/// The content of an item, either read into memory or mapped
+pub(crate) enum Content {
+    Read(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for Content {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Read(content) => content,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(content) => content,
+        }
+    }
+}
+
+/// Read an item, mapping it instead when asked to and the storage is a local
+/// directory
+pub(crate) fn read<S: Storage>(storage: &S, name: &str, map: bool) -> io::Result<Content> {
+    #[cfg(feature = "mmap")]
+    if let (true, Some(dir)) = (map, storage.dir()) {
+        let file = std::fs::File::open(dir.join(name))?;
+        // SAFETY: the map is only read while deserializing, and the table is
+        // read only. Files changed by other processes in the meantime are
+        // documented as unsupported by `set_mmap`
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        return Ok(Content::Mapped(map));
+    }
+    #[cfg(not(feature = "mmap"))]
+    let _ = map;
+    Ok(Content::Read(storage.read(name)?))
+}
@@ -0,0 +1,78 @@
+use crate::{Storage, Table, TableError, TableMetadata};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    io::{BufRead, Write},
+    path::Path,
+};
+
+/// An element as a line of newline delimited json
+#[derive(Serialize, Deserialize)]
+struct Record<K, T> {
+    key: K,
+    value: T,
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Write the elements as newline delimited json, one
+    /// `{"key": ..., "value": ...}` object per line in the order of their
+    /// keys. Evicted elements are read to be written too
+    ///
+    /// # Errors
+    /// 1. Some element can't be serialized or written
+    pub fn export_ndjson<W: Write>(&self, mut writer: W) -> Result<(), TableError> {
+        let mut keys: Vec<_> = self.content.keys().chain(self.evicted.keys()).collect();
+        keys.sort_unstable();
+        for key in keys {
+            // evicted elements that can't be read are left out
+            let Some(element) = self.get_element(key) else {
+                continue;
+            };
+            let record = Record {
+                key: &**key,
+                value: &element.info,
+            };
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(writer.flush()?)
+    }
+}
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Create a new table with the elements of a stream of newline delimited
+    /// json, as written by `export_ndjson`, and write them. Blank lines are
+    /// skipped
+    ///
+    /// # Errors
+    /// 1. The table can't be created
+    /// 2. The stream can't be read, or some line isn't an element
+    /// 3. Some element can't be pushed or written
+    pub fn import_ndjson<Q, R>(
+        dir: Q,
+        reader: R,
+        metadata: TableMetadata,
+    ) -> Result<Self, TableError>
+    where
+        Q: AsRef<Path>,
+        R: BufRead,
+    {
+        let mut table = Table::new(dir, metadata)?;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record<String, T> = serde_json::from_str(&line)?;
+            table.push(&record.key, record.value)?;
+        }
+        table.write_back()?;
+        Ok(table)
+    }
+}
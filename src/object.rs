@@ -0,0 +1,101 @@
+use crate::Storage;
+use object_store::{path::Path as ObjectPath, ObjectStore, ObjectStoreExt, PutMode, PutPayload};
+use std::{io, sync::Arc};
+use tokio::runtime::{Builder, Runtime};
+
+/// A storage that keeps every item as an object under a prefix of an object
+/// store, such as an S3 or GCS bucket, so that the same table code can run
+/// against a local directory and against a bucket. The stores are built with
+/// the reexported `object_store` crate, with the `s3` and `gcs` features
+/// enabling their clients.
+///
+/// The operations block on a runtime owned by the storage, so it must not be
+/// used from inside an async context
+#[derive(Debug)]
+pub struct ObjectStorage {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    runtime: Runtime,
+}
+
+impl ObjectStorage {
+    /// A storage of the objects under `prefix`, which plays the role of the
+    /// directory of the table
+    ///
+    /// # Errors
+    /// 1. The runtime in which the requests are made can't be started
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: &str) -> io::Result<Self> {
+        Ok(Self {
+            store,
+            prefix: ObjectPath::from(prefix),
+            runtime: Builder::new_current_thread().enable_all().build()?,
+        })
+    }
+
+    /// The store in which the objects are kept
+    pub fn store(&self) -> &Arc<dyn ObjectStore> {
+        &self.store
+    }
+
+    fn path(&self, name: &str) -> ObjectPath {
+        self.prefix.clone().join(name)
+    }
+}
+
+impl Storage for ObjectStorage {
+    fn list(&self) -> io::Result<Vec<String>> {
+        let listing = self
+            .runtime
+            .block_on(self.store.list_with_delimiter(Some(&self.prefix)))?;
+        let objects = listing
+            .objects
+            .iter()
+            .filter_map(|object| object.location.filename().map(str::to_string));
+        let prefixes = listing
+            .common_prefixes
+            .iter()
+            .filter_map(|prefix| prefix.filename().map(|name| format!("{name}/")));
+        Ok(objects.chain(prefixes).collect())
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        self.runtime.block_on(async {
+            let object = self.store.get(&self.path(name)).await?;
+            Ok(object.bytes().await?.to_vec())
+        })
+    }
+
+    fn size(&self, name: &str) -> io::Result<u64> {
+        Ok(self
+            .runtime
+            .block_on(self.store.head(&self.path(name)))?
+            .size)
+    }
+
+    fn create(&mut self, name: &str) -> io::Result<()> {
+        self.runtime.block_on(self.store.put_opts(
+            &self.path(name),
+            PutPayload::default(),
+            PutMode::Create.into(),
+        ))?;
+        Ok(())
+    }
+
+    fn write(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        self.runtime.block_on(
+            self.store
+                .put(&self.path(name), PutPayload::from(content.to_vec())),
+        )?;
+        Ok(())
+    }
+
+    fn delete(&mut self, name: &str) -> io::Result<()> {
+        Ok(self.runtime.block_on(self.store.delete(&self.path(name)))?)
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        Ok(self
+            .runtime
+            .block_on(self.store.rename(&self.path(from), &self.path(to)))?)
+    }
+}
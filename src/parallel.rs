@@ -0,0 +1,26 @@
+use crate::{Storage, Table, TableElement};
+use rayon::{collections::btree_map, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned + Send + Sync,
+    S: Storage,
+{
+    /// A parallel iterator over names and elements
+    pub fn par_iter(&self) -> btree_map::Iter<'_, Arc<str>, TableElement<T>> {
+        self.content.par_iter()
+    }
+
+    /// A parallel iterator over the elements
+    pub fn par_values(&self) -> impl ParallelIterator<Item = &TableElement<T>> {
+        self.content.par_iter().map(|(_, element)| element)
+    }
+
+    /// A parallel iterator over the mutable elements
+    pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut TableElement<T>> {
+        self.is_modified = true;
+        self.content.par_iter_mut().map(|(_, element)| element)
+    }
+}
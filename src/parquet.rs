@@ -0,0 +1,71 @@
+use crate::{Storage, Table, TableError};
+use arrow_json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::{arrow::ArrowWriter, errors::ParquetError};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{fs::File, path::Path, sync::Arc};
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Write the elements into a parquet file, with their keys in a `key`
+    /// column and a column for every field, whose types are inferred from
+    /// the values of all the elements. It's meant for flat records, nested
+    /// fields become nested columns. Evicted elements are read to be written
+    /// too
+    ///
+    /// # Errors
+    /// 1. Some element isn't a record, or it has a field named `key`
+    /// 2. Some element can't be serialized, or the fields of the elements have
+    ///    incompatible types
+    /// 3. The file can't be written
+    pub fn export_parquet<Q: AsRef<Path>>(&self, path: Q) -> Result<(), TableError> {
+        let mut keys: Vec<_> = self.content.keys().chain(self.evicted.keys()).collect();
+        keys.sort_unstable();
+        let mut rows = Vec::new();
+        for key in keys {
+            // evicted elements that can't be read are left out
+            let Some(element) = self.get_element(key) else {
+                continue;
+            };
+            match serde_json::to_value(&element.info)? {
+                Value::Object(record) if !record.contains_key("key") => {
+                    rows.push((key, Value::Object(record)));
+                }
+                _ => {
+                    let message = format!("{key} isn't a record without a key field");
+                    return Err(ParquetError::General(message).into());
+                }
+            }
+        }
+        let inferred = infer_json_schema_from_iterator(rows.iter().map(|(_, row)| Ok(row)))
+            .map_err(ParquetError::from)?;
+        let mut fields: Vec<_> = inferred.fields().iter().cloned().collect();
+        fields.sort_by(|a, b| a.name().cmp(b.name()));
+        fields.insert(0, Arc::new(Field::new("key", DataType::Utf8, false)));
+        let schema = Arc::new(Schema::new(fields));
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|(key, mut row)| {
+                // the rows have just been checked to be objects
+                if let Value::Object(record) = &mut row {
+                    record.insert("key".to_string(), Value::String(key.to_string()));
+                }
+                row
+            })
+            .collect();
+        let mut decoder = ReaderBuilder::new(schema.clone())
+            .build_decoder()
+            .map_err(ParquetError::from)?;
+        decoder.serialize(&rows).map_err(ParquetError::from)?;
+        let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None)?;
+        if let Some(batch) = decoder.flush().map_err(ParquetError::from)? {
+            writer.write(&batch)?;
+        }
+        writer.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{Storage, Table, TableElement};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, hash::Hash};
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Split the elements in groups by the value of `f`. The elements of each
+    /// group are ordered by key. Like the rest of the queries, it reads the
+    /// evicted elements without bringing them back, leaving out those that
+    /// can't be read
+    pub fn group_by<K, F>(&self, f: F) -> HashMap<K, Vec<&TableElement<T>>>
+    where
+        K: Eq + Hash,
+        F: Fn(&T) -> K,
+    {
+        let mut groups: HashMap<K, Vec<&TableElement<T>>> = HashMap::new();
+        for (_, element) in self.all_elements() {
+            groups.entry(f(&element.info)).or_default().push(element);
+        }
+        groups
+    }
+
+    /// The first element, in key order, for which `f` holds
+    pub fn find<F>(&self, f: F) -> Option<(&str, &TableElement<T>)>
+    where
+        F: Fn(&str, &T) -> bool,
+    {
+        self.all_elements()
+            .find(|(key, element)| f(key, &element.info))
+            .map(|(key, element)| (&**key, element))
+    }
+
+    /// Whether `f` holds for some element
+    pub fn any<F>(&self, f: F) -> bool
+    where
+        F: Fn(&str, &T) -> bool,
+    {
+        self.all_elements()
+            .any(|(key, element)| f(key, &element.info))
+    }
+
+    /// Whether `f` holds for every element, which is true for empty tables
+    pub fn all<F>(&self, f: F) -> bool
+    where
+        F: Fn(&str, &T) -> bool,
+    {
+        self.all_elements()
+            .all(|(key, element)| f(key, &element.info))
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage + Default,
+{
+    /// Split the elements in groups by the value of `f`, taking them out of
+    /// the table. The elements of each group are ordered by key, and the
+    /// emptied table is dropped without writing anything back
+    pub fn into_group_by<K, F>(self, f: F) -> HashMap<K, Vec<TableElement<T>>>
+    where
+        K: Eq + Hash,
+        F: Fn(&T) -> K,
+    {
+        let (_, content, _, _, temporary) = self.into_parts();
+        crate::temporary::remove(temporary);
+        let mut groups: HashMap<K, Vec<TableElement<T>>> = HashMap::new();
+        for element in content.into_values() {
+            groups.entry(f(&element.info)).or_default().push(element);
+        }
+        groups
+    }
+}
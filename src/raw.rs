@@ -0,0 +1,36 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// A table of raw json values. It can load any directory of json files
+/// without knowing their schema beforehand
+pub type RawTable = Table<Value>;
+
+impl<S> Table<Value, S>
+where
+    S: Storage + Default,
+{
+    /// Convert a table of raw json values into a table of `T`, keeping the
+    /// same storage and policies
+    ///
+    /// # Errors
+    /// 1. Some value can't be deserialized into `T`
+    pub fn try_into_typed<T>(self) -> Result<Table<T, S>, TableError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.try_map_info(|value| Ok(T::deserialize(value)?))
+    }
+
+    /// Convert a table of `T` into a table of raw json values, keeping the same
+    /// storage and policies
+    ///
+    /// # Errors
+    /// 1. Some element can't be serialized into a json value
+    pub fn from_typed<T>(table: Table<T, S>) -> Result<Self, TableError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        table.try_map_info(|info| Ok(serde_json::to_value(info)?))
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{Storage, Table};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A column of a rendered table, given by its header and the function that
+/// gets its cells from the elements
+pub type Column<'a, T> = (&'a str, fn(&T) -> String);
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// An aligned text table with a row for every element, whose first column
+    /// is the key and the rest are given by their headers and the functions
+    /// that get them from the elements
+    pub fn render_ascii(&self, columns: &[Column<T>]) -> String {
+        let mut rows = vec![std::iter::once("key")
+            .chain(columns.iter().map(|(header, _)| *header))
+            .map(String::from)
+            .collect::<Vec<_>>()];
+        rows.extend(self.all_elements().map(|(key, element)| {
+            std::iter::once(key.to_string())
+                .chain(columns.iter().map(|(_, get)| get(&element.info)))
+                .collect()
+        }));
+        let widths: Vec<usize> = (0..=columns.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let line = |row: &[String]| {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:width$}"))
+                .collect();
+            format!("{}\n", cells.join(" | ").trim_end())
+        };
+        let separator: Vec<_> = widths.iter().map(|&width| "-".repeat(width)).collect();
+        let mut rendered = line(&rows[0]);
+        rendered.push_str(&separator.join("-+-"));
+        rendered.push('\n');
+        for row in &rows[1..] {
+            rendered.push_str(&line(row));
+        }
+        rendered
+    }
+}
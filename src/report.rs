@@ -0,0 +1,72 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+
+/// What happened to every element of a bulk append
+#[derive(Debug, Default)]
+pub struct AppendReport {
+    /// The names of the elements that were pushed, in order
+    pub written: Vec<String>,
+    /// The names of the elements that couldn't be pushed, with the reason
+    pub failed: Vec<(String, TableError)>,
+}
+
+impl AppendReport {
+    /// Whether every element was pushed
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// What happened to an element of a bulk pop
+#[derive(Debug)]
+pub enum PopOutcome {
+    /// The element was popped and its file deleted
+    Removed,
+    /// There was no element with that name
+    NotFound,
+    /// The element couldn't be popped for some other reason
+    Failed(TableError),
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Append the elements of an iterator of names and elements, pushing
+    /// every element that can be pushed, and report which ones were pushed
+    /// and why the others weren't
+    pub fn append_report<Q, I>(&mut self, pairs: I) -> AppendReport
+    where
+        Q: AsRef<str>,
+        I: IntoIterator<Item = (Q, T)>,
+    {
+        let mut report = AppendReport::default();
+        for (fname, element) in pairs {
+            let fname = fname.as_ref();
+            match self.push(fname, element) {
+                Ok(()) => report.written.push(fname.to_string()),
+                Err(e) => report.failed.push((fname.to_string(), e)),
+            }
+        }
+        report
+    }
+
+    /// Pop every element of a list of names, going through the whole list
+    /// even when some can't be popped, and report what happened to each one
+    pub fn pop_report<Q: AsRef<str>>(&mut self, fnames: &[Q]) -> BTreeMap<String, PopOutcome> {
+        fnames
+            .iter()
+            .map(|fname| {
+                let fname = fname.as_ref();
+                let outcome = match self.pop(fname) {
+                    Ok(()) => PopOutcome::Removed,
+                    Err(TableError::PopError(_)) => PopOutcome::NotFound,
+                    Err(e) => PopOutcome::Failed(e),
+                };
+                (fname.to_string(), outcome)
+            })
+            .collect()
+    }
+}
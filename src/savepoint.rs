@@ -0,0 +1,108 @@
+use crate::{long_keys, Storage, Table, TableElement, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::BTreeMap, io, sync::Arc};
+
+/// The elements of a table at some point, to which the table can be rolled
+/// back. A table that is written back manually works as a transaction until
+/// its write back, and savepoints let it undo only its latest changes
+#[derive(Debug, Clone)]
+pub struct Savepoint<T> {
+    content: BTreeMap<Arc<str>, (T, Option<u64>, u64)>,
+    is_modified: bool,
+    /// The write backs of the table when the savepoint was taken, after which
+    /// the items may no longer hold what the elements say
+    write_backs: usize,
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    S: Storage,
+{
+    /// Take a savepoint of the elements of the table. The evicted elements are
+    /// read to be kept in it too
+    pub fn savepoint(&self) -> Savepoint<T> {
+        let evicted = self
+            .evicted
+            .keys()
+            .filter_map(|key| Some((key, self.evicted_element(key)?)));
+        Savepoint {
+            content: self
+                .content
+                .iter()
+                .chain(evicted)
+                .map(|(key, element)| {
+                    let state = (element.info.clone(), element.written, element.version);
+                    (key.clone(), state)
+                })
+                .collect(),
+            is_modified: self.is_modified,
+            write_backs: self.metrics.write_backs,
+        }
+    }
+
+    /// Undo the changes made since a savepoint was taken. The elements pushed
+    /// since then are removed with their files, the popped ones get their
+    /// files back, and every element gets its information back. Changes that
+    /// were written back in between are undone on the next write back. Frozen
+    /// elements are left as they are
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The files of the pushed or popped elements can't be removed or
+    ///    created
+    pub fn rollback_to(&mut self, savepoint: &Savepoint<T>) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let pushed: Vec<_> = self
+            .content
+            .keys()
+            .chain(self.evicted.keys())
+            .filter(|key| !savepoint.content.contains_key(*key) && !self.frozen.contains(&***key))
+            .cloned()
+            .collect();
+        for key in pushed {
+            match self.storage.delete(&self.metadata.entry_name(&key)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => long_keys::forget_key(&mut self.storage, &self.metadata, &key)?,
+            }
+        }
+        let mut is_modified = savepoint.is_modified;
+        let unwritten = self.metrics.write_backs == savepoint.write_backs;
+        let mut content = BTreeMap::new();
+        for (key, (info, written, version)) in &savepoint.content {
+            if self.frozen.contains(&**key) {
+                continue;
+            }
+            let popped = !self.content.contains_key(key) && !self.evicted.contains_key(key);
+            if popped {
+                self.create_item(&self.metadata.entry_name(key))?;
+                long_keys::record_key(&mut self.storage, &self.metadata, key)?;
+            }
+            // the items of popped elements are empty, and the rest may have
+            // been written since
+            let written = match unwritten && !popped {
+                true => *written,
+                false => None,
+            };
+            is_modified |= written.is_none();
+            let element = TableElement {
+                info: info.clone(),
+                written,
+                version: *version,
+            };
+            content.insert(key.clone(), element);
+        }
+        // frozen elements are kept as they are
+        let frozen: Vec<_> = self.frozen.iter().cloned().collect();
+        for key in frozen {
+            self.restore(&key)?;
+            if let Some((key, element)) = self.content.remove_entry(key.as_str()) {
+                content.insert(key, element);
+            }
+        }
+        self.content = content;
+        self.evicted.clear();
+        self.is_modified = is_modified;
+        Ok(())
+    }
+}
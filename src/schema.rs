@@ -0,0 +1,28 @@
+use crate::{TableError, RESERVED_PREFIX};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The path of the file in which the schema version of a table is recorded
+fn schema_path(dir: &Path) -> PathBuf {
+    dir.join(format!("{RESERVED_PREFIX}_schema"))
+}
+
+/// Record the schema version of a table
+pub(crate) fn write_schema<Q: AsRef<Path>>(dir: Q, schema: &str) -> io::Result<()> {
+    fs::write(schema_path(dir.as_ref()), schema)
+}
+
+/// Check that the table was built with the expected schema version. Tables
+/// without a recorded version are assumed to be compatible
+pub(crate) fn check_schema<Q: AsRef<Path>>(dir: Q, schema: &str) -> Result<(), TableError> {
+    match fs::read_to_string(schema_path(dir.as_ref())) {
+        Ok(found) if found == schema => Ok(()),
+        Ok(found) => Err(TableError::SchemaError(schema.to_string(), found)),
+        Err(e) => match e.kind() {
+            io::ErrorKind::NotFound => Ok(()),
+            _ => Err(e.into()),
+        },
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{long_keys, MemoryStorage, Storage, Table, TableMetadata};
+use serde::{
+    de::{self, DeserializeOwned},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::BTreeMap;
+
+/// The elements of a table as a map of their keys to them, the evicted ones
+/// too
+struct Entries<'a, T, S>(&'a Table<T, S>)
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage;
+
+impl<T, S> Serialize for Entries<'_, T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        serializer.collect_map(
+            self.0
+                .all_elements()
+                .map(|(key, element)| (key, &element.info)),
+        )
+    }
+}
+
+/// A table is serialized as its policies, under `metadata`, and a map of the
+/// keys to the elements, under `entries`
+impl<T, S> Serialize for Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        let mut table = serializer.serialize_struct("Table", 2)?;
+        table.serialize_field("metadata", &self.metadata)?;
+        table.serialize_field("entries", &Entries(self))?;
+        table.end()
+    }
+}
+
+/// The serialized form of a table
+#[derive(Deserialize)]
+#[serde(bound = "T: DeserializeOwned")]
+struct SerializedTable<T> {
+    #[serde(default)]
+    metadata: TableMetadata,
+    entries: BTreeMap<String, T>,
+}
+
+/// Tables are deserialized into memory, from the form in which they are
+/// serialized
+impl<'de, T> Deserialize<'de> for Table<T, MemoryStorage>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = SerializedTable::<T>::deserialize(deserializer)?;
+        let mut items = table
+            .entries
+            .iter()
+            .map(|(key, info)| Ok((table.metadata.entry_name(key), table.metadata.to_vec(info)?)))
+            .collect::<serde_json::Result<Vec<_>>>()
+            .map_err(de::Error::custom)?;
+        items.extend(
+            table
+                .entries
+                .keys()
+                .filter_map(|key| long_keys::key_item(&table.metadata, key)),
+        );
+        Table::with_storage(MemoryStorage::with_items(items), table.metadata)
+            .map_err(de::Error::custom)
+    }
+}
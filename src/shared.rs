@@ -0,0 +1,354 @@
+use crate::{
+    create_item, long_keys, DropFailure, FsStorage, RWPolicy, Storage, Table, TableElement,
+    TableError, TableMetadata, WriteType,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock},
+};
+
+/// An element of a shared table and whether it has been modified since the
+/// last flush
+#[derive(Debug)]
+struct SharedElement<T> {
+    element: TableElement<T>,
+    is_modified: bool,
+}
+
+type Entries<T> = HashMap<String, Arc<RwLock<SharedElement<T>>>>;
+
+#[derive(Debug)]
+struct SharedInner<T: Serialize, S: Storage> {
+    storage: Mutex<S>,
+    metadata: TableMetadata,
+    entries: RwLock<Entries<T>>,
+    drop_failure: Mutex<DropFailure<T>>,
+    /// The directory of a temporary table, that is removed on drop
+    temporary: Option<PathBuf>,
+}
+
+/// A thread safe table that can be cloned and shared between threads. The
+/// elements are locked individually, so different threads can read and update
+/// different elements at the same time. Only the modified elements are written
+/// on a `flush`.
+///
+/// Unique constraints of the original table are not enforced by the shared
+/// table.
+#[derive(Debug)]
+pub struct SharedTable<T: Serialize, S: Storage = FsStorage> {
+    inner: Arc<SharedInner<T, S>>,
+}
+
+impl<T: Serialize, S: Storage> Clone for SharedTable<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage + Default,
+{
+    /// Turn the table into a thread safe shared table
+    pub fn into_shared(self) -> SharedTable<T, S> {
+        let (storage, content, metadata, is_modified, temporary) = self.into_parts();
+        let entries = content
+            .into_iter()
+            .map(|(name, element)| {
+                let element = SharedElement {
+                    element,
+                    is_modified,
+                };
+                (name.to_string(), Arc::new(RwLock::new(element)))
+            })
+            .collect();
+        SharedTable {
+            inner: Arc::new(SharedInner {
+                storage: Mutex::new(storage),
+                metadata,
+                entries: RwLock::new(entries),
+                drop_failure: Mutex::new(DropFailure::Panic),
+                temporary,
+            }),
+        }
+    }
+}
+
+impl<T, S> SharedTable<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    fn entry(&self, entry_name: &str) -> Option<Arc<RwLock<SharedElement<T>>>> {
+        self.inner
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&*self.normalize(entry_name))
+            .cloned()
+    }
+
+    /// The key in the form that the normalization policy of the table asks
+    fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        self.inner.metadata.normalization_policy.normalize(key)
+    }
+
+    fn storage(&self) -> MutexGuard<'_, S> {
+        self.inner
+            .storage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn mod_permissions(&self) -> Result<(), TableError> {
+        match self.inner.metadata.rw_policy {
+            RWPolicy::Write(_) => Ok(()),
+            RWPolicy::ReadOnly => Err(TableError::NoWritePolicyError),
+        }
+    }
+
+    /// Read an element, locking only that element while `f` runs
+    pub fn read<R, F: FnOnce(&T) -> R>(&self, entry_name: &str, f: F) -> Option<R> {
+        let entry = self.entry(entry_name)?;
+        let entry = entry.read().unwrap_or_else(PoisonError::into_inner);
+        Some(f(&entry.element.info))
+    }
+
+    /// Get a copy of an element
+    pub fn get(&self, entry_name: &str) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.read(entry_name, T::clone)
+    }
+
+    /// Update an element, locking only that element while `f` runs
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element doesn't exist
+    pub fn update<R, F: FnOnce(&mut T) -> R>(
+        &self,
+        entry_name: &str,
+        f: F,
+    ) -> Result<R, TableError> {
+        self.mod_permissions()?;
+        let entry = self
+            .entry(entry_name)
+            .ok_or_else(|| TableError::PopError(self.normalize(entry_name).into_owned()))?;
+        let mut entry = entry.write().unwrap_or_else(PoisonError::into_inner);
+        entry.is_modified = true;
+        Ok(f(&mut entry.element.info))
+    }
+
+    /// Add an element and create its file `{dir}/{fname}.json`. The file is
+    /// written on the next flush
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. If you cant create a new file
+    pub fn push(&self, fname: &str, info_elem: T) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let fname = &*self.normalize(fname);
+        let mut entries = self
+            .inner
+            .entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if entries.contains_key(fname) {
+            return Err(TableError::PushError(fname.into()));
+        }
+        let mut storage = self.storage();
+        create_item(
+            &mut *storage,
+            &self.inner.metadata,
+            &self.inner.metadata.entry_name(fname),
+        )?;
+        long_keys::record_key(&mut *storage, &self.inner.metadata, fname)?;
+        let element = SharedElement {
+            element: TableElement::new(info_elem),
+            is_modified: true,
+        };
+        entries.insert(fname.into(), Arc::new(RwLock::new(element)));
+        Ok(())
+    }
+
+    /// Remove an element and delete its file
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. You try to delete a non existing element
+    /// 3. If you cant delete the file
+    pub fn pop(&self, fname: &str) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let fname = &*self.normalize(fname);
+        self.inner
+            .entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(fname)
+            .ok_or_else(|| TableError::PopError(fname.to_string()))?;
+        let mut storage = self.storage();
+        storage.delete(&self.inner.metadata.entry_name(fname))?;
+        long_keys::forget_key(&mut *storage, &self.inner.metadata, fname)?;
+        Ok(())
+    }
+
+    /// Write the elements modified since the last flush into their files
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. There are problems with serialization
+    pub fn flush(&self) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        let entries: Vec<_> = self
+            .inner
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(name, entry)| (name.clone(), Arc::clone(entry)))
+            .collect();
+        for (name, entry) in entries {
+            let mut entry = entry.write().unwrap_or_else(PoisonError::into_inner);
+            if entry.is_modified {
+                let content = self.inner.metadata.to_vec(&entry.element.info)?;
+                self.storage()
+                    .write(&self.inner.metadata.entry_name(&name), &content)?;
+                entry.is_modified = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the modified elements and drop this handle, so that the errors
+    /// of the write back can be handled instead of panicking on drop. When
+    /// it's the last handle, nothing more is written when it's dropped,
+    /// whatever happens
+    ///
+    /// # Errors
+    /// 1. Whenever there's an error with the `flush`
+    pub fn close(self) -> Result<(), TableError> {
+        let result = match self.inner.metadata.rw_policy {
+            RWPolicy::Write(_) => self.flush(),
+            RWPolicy::ReadOnly => Ok(()),
+        };
+        if Arc::strong_count(&self.inner) == 1 {
+            for entry in self
+                .inner
+                .entries
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .values()
+            {
+                entry
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .is_modified = false;
+            }
+        }
+        result
+    }
+
+    /// Choose what happens when the automatic write back fails as the last
+    /// handle of the table is dropped. A callback is given only the elements
+    /// that couldn't be written
+    pub fn on_drop_failure(&self, drop_failure: DropFailure<T>) {
+        *self
+            .inner
+            .drop_failure
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = drop_failure;
+    }
+
+    /// Get the names of the elements
+    pub fn keys(&self) -> Vec<String> {
+        self.inner
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// The number of elements in the table
+    pub fn len(&self) -> usize {
+        self.inner
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Serialize, S: Storage> Drop for SharedInner<T, S> {
+    /// Writes back in case the write back is set to automatic, when the last
+    /// handle of the shared table is dropped. Every modified element is tried,
+    /// and the first error is handled as `on_drop_failure` chose. Temporary
+    /// tables are removed instead
+    ///
+    /// # Panics
+    /// - When there are problems with the write back, unless the thread is
+    ///   already panicking or another `DropFailure` was chosen, mainly when
+    ///     - There are problems with file handles
+    ///     - There are problems with serialization
+    fn drop(&mut self) {
+        if self.temporary.is_some() {
+            crate::temporary::remove(self.temporary.take());
+            return;
+        }
+        if RWPolicy::Write(WriteType::Automatic) != self.metadata.rw_policy {
+            return;
+        }
+        let entries = std::mem::take(
+            self.entries
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        let storage = self
+            .storage
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut failure = None;
+        let mut unsaved = Vec::new();
+        for (name, entry) in entries {
+            // no handle is left, so nothing else holds the element
+            let Ok(entry) = Arc::try_unwrap(entry) else {
+                continue;
+            };
+            let entry = entry.into_inner().unwrap_or_else(PoisonError::into_inner);
+            if !entry.is_modified {
+                continue;
+            }
+            let written = self
+                .metadata
+                .to_vec(&entry.element.info)
+                .map_err(TableError::from)
+                .and_then(|content| Ok(storage.write(&self.metadata.entry_name(&name), &content)?));
+            if let Err(error) = written {
+                failure.get_or_insert(error);
+                unsaved.push((name, entry.element.info));
+            }
+        }
+        if let Some(error) = failure {
+            std::mem::take(
+                self.drop_failure
+                    .get_mut()
+                    .unwrap_or_else(PoisonError::into_inner),
+            )
+            .handle(error, || unsaved);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{Storage, Table, TableMetadata};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::hash_map::{HashMap, Iter, Keys},
+    ops::Index,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// An owned, read only copy of the information of a table at some point. It
+/// holds no files, so it can be freely moved to other threads while the
+/// original table keeps changing
+#[derive(Debug, Clone)]
+pub struct TableSnapshot<T> {
+    dir: PathBuf,
+    metadata: TableMetadata,
+    content: HashMap<Arc<str>, T>,
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    S: Storage,
+{
+    /// Copy the current information of the table into a snapshot
+    pub fn snapshot(&self) -> TableSnapshot<T> {
+        TableSnapshot {
+            dir: self.dir().to_path_buf(),
+            metadata: self.metadata,
+            content: self
+                .content
+                .iter()
+                .map(|(name, element)| (name.clone(), element.info.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl<T> TableSnapshot<T> {
+    /// The directory of the table the snapshot was taken from
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The policies of the table the snapshot was taken from
+    pub fn metadata(&self) -> &TableMetadata {
+        &self.metadata
+    }
+
+    /// Get the names of the elements
+    pub fn keys(&self) -> Keys<'_, Arc<str>, T> {
+        self.content.keys()
+    }
+
+    /// An iterator over names and elements
+    pub fn iter(&self) -> Iter<'_, Arc<str>, T> {
+        self.content.iter()
+    }
+
+    /// Get an individual element by key
+    pub fn get(&self, entry_name: &str) -> Option<&T> {
+        self.content.get(entry_name)
+    }
+
+    /// The number of elements in the snapshot
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Whether the snapshot is empty
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+}
+
+impl<T> Index<&str> for TableSnapshot<T> {
+    type Output = T;
+    fn index(&self, index: &str) -> &Self::Output {
+        &self.content[index]
+    }
+}
@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+/// A value of a json document, with the bytes of the document that it spans.
+/// Objects keep their fields in the order in which they are written
+pub(crate) enum Node {
+    Leaf(Range<usize>),
+    Object(Range<usize>, Vec<(String, Node)>),
+    Array(Range<usize>, Vec<Node>),
+}
+
+impl Node {
+    pub(crate) fn span(&self) -> Range<usize> {
+        match self {
+            Self::Leaf(span) | Self::Object(span, _) | Self::Array(span, _) => span.clone(),
+        }
+    }
+}
+
+/// A parser of json documents that tolerates comments and trailing commas,
+/// so that documents written in jsonc can be handled too
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.text[self.pos..];
+            if rest.first().is_some_and(u8::is_ascii_whitespace) {
+                self.pos += 1;
+            } else if rest.starts_with(b"//") {
+                while self.peek().is_some_and(|byte| byte != b'\n') {
+                    self.pos += 1;
+                }
+            } else if rest.starts_with(b"/*") {
+                self.pos += 2;
+                while self.pos < self.text.len() && !self.text[self.pos..].starts_with(b"*/") {
+                    self.pos += 1;
+                }
+                self.pos = (self.pos + 2).min(self.text.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek()? == b'}' {
+                        break;
+                    }
+                    let key = self.string()?;
+                    let key = serde_json::from_slice(&self.text[key]).ok()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b'}' => break,
+                        _ => return None,
+                    }
+                }
+                self.pos += 1;
+                Some(Node::Object(start..self.pos, fields))
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek()? == b']' {
+                        break;
+                    }
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b']' => break,
+                        _ => return None,
+                    }
+                }
+                self.pos += 1;
+                Some(Node::Array(start..self.pos, items))
+            }
+            b'"' => self.string().map(Node::Leaf),
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|byte| !b",:]}/".contains(&byte) && !byte.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                (self.pos > start).then_some(Node::Leaf(start..self.pos))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        Some(start..self.pos)
+    }
+}
+
+/// Parse a json document, keeping the spans of its values. Comments and
+/// trailing commas are allowed
+pub(crate) fn parse(text: &[u8]) -> Option<Node> {
+    Parser { text, pos: 0 }.value()
+}
+
+/// Escape a key to be part of a json pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// The json pointer of the first key that is repeated in an object, if any
+fn find_duplicate(node: &Node, path: &str) -> Option<String> {
+    match node {
+        Node::Leaf(_) => None,
+        Node::Array(_, items) => items
+            .iter()
+            .enumerate()
+            .find_map(|(i, item)| find_duplicate(item, &format!("{path}/{i}"))),
+        Node::Object(_, fields) => fields.iter().enumerate().find_map(|(i, (key, item))| {
+            let path = format!("{path}/{}", escape(key));
+            match fields[..i].iter().any(|(other, _)| other == key) {
+                true => Some(path),
+                false => find_duplicate(item, &path),
+            }
+        }),
+    }
+}
+
+/// The json pointer of the first key that is repeated in an object of a json
+/// document. Documents that can't be parsed have none
+pub(crate) fn duplicate_key(text: &[u8]) -> Option<String> {
+    find_duplicate(&parse(text)?, "")
+}
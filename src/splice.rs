@@ -0,0 +1,60 @@
+use crate::{
+    spans::{self, Node},
+    FloatPolicy, FormatPolicy,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::ops::Range;
+
+/// Collect the replacements that turn the values of `node` into `new`,
+/// replacing the smallest values that differ
+fn diff(text: &[u8], node: &Node, new: &Value, edits: &mut Vec<(Range<usize>, Value)>) {
+    match (node, new) {
+        (Node::Object(_, fields), Value::Object(map))
+            if fields.len() == map.len()
+                && map
+                    .keys()
+                    .all(|key| fields.iter().any(|(field, _)| field == key)) =>
+        {
+            for (key, node) in fields {
+                diff(text, node, &map[key], edits);
+            }
+        }
+        (Node::Array(_, items), Value::Array(values)) if items.len() == values.len() => {
+            for (node, value) in items.iter().zip(values) {
+                diff(text, node, value, edits);
+            }
+        }
+        (Node::Leaf(span), new)
+            if serde_json::from_slice::<Value>(&text[span.clone()])
+                .is_ok_and(|old| old == *new) => {}
+        (node, new) => edits.push((node.span(), new.clone())),
+    }
+}
+
+/// Serialize an element by replacing the values that changed in the content
+/// that its item has, so that the rest keeps its formatting and comments.
+/// Content that can't be parsed is replaced by pretty json. Floats are rounded
+/// as the float policy says
+pub(crate) fn splice<T: Serialize>(
+    stored: &[u8],
+    info: &T,
+    floats: FloatPolicy,
+) -> serde_json::Result<Vec<u8>> {
+    let mut new = serde_json::to_value(info)?;
+    floats.round_value(&mut new);
+    let Some(root) = spans::parse(stored) else {
+        return FormatPolicy::Pretty.to_vec(&new);
+    };
+    let mut edits = Vec::new();
+    diff(stored, &root, &new, &mut edits);
+    let mut spliced = Vec::with_capacity(stored.len());
+    let mut copied = 0;
+    for (span, value) in edits {
+        spliced.extend_from_slice(&stored[copied..span.start]);
+        spliced.extend(serde_json::to_vec(&value)?);
+        copied = span.end;
+    }
+    spliced.extend_from_slice(&stored[copied..]);
+    Ok(spliced)
+}
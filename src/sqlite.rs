@@ -0,0 +1,132 @@
+use crate::{Storage, Table, TableError};
+use rusqlite::{types::Value as SqlValue, Connection};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::BTreeSet, path::Path};
+
+/// How the elements are laid out in the columns of a SQLite table
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SqliteLayout {
+    /// A `value` column with the json of each element
+    #[default]
+    Json,
+    /// A column for every field of the elements, with the fields of nested
+    /// objects joined by dots, as in `address.city`. Arrays are kept as json,
+    /// and elements that aren't objects go into a `value` column. Fields named
+    /// `key` are left out, as they would clash with the keys
+    Flattened,
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Write the elements into a new table of a SQLite database, created if it
+    /// doesn't exist, with their keys in a `key` column that is the primary
+    /// key. Evicted elements are read to be written too
+    ///
+    /// # Errors
+    /// 1. The database can't be opened, or the table already exists in it
+    /// 2. Some element can't be serialized or written
+    pub fn export_sqlite<Q: AsRef<Path>>(
+        &self,
+        path: Q,
+        table_name: &str,
+        layout: SqliteLayout,
+    ) -> Result<(), TableError> {
+        let mut keys: Vec<_> = self.content.keys().chain(self.evicted.keys()).collect();
+        keys.sort_unstable();
+        let mut rows = Vec::new();
+        for key in keys {
+            // evicted elements that can't be read are left out
+            let Some(element) = self.get_element(key) else {
+                continue;
+            };
+            let value = serde_json::to_value(&element.info)?;
+            let mut row = Map::new();
+            match (layout, value) {
+                (SqliteLayout::Flattened, Value::Object(object)) => flatten("", object, &mut row),
+                (_, value) => {
+                    row.insert("value".to_string(), value);
+                }
+            }
+            rows.push((key, row));
+        }
+        let columns: BTreeSet<String> = rows
+            .iter()
+            .flat_map(|(_, row)| row.keys())
+            .filter(|column| *column != "key")
+            .cloned()
+            .collect();
+        let mut connection = Connection::open(path)?;
+        let transaction = connection.transaction()?;
+        let definitions: String = columns
+            .iter()
+            .map(|column| format!(", {}", quote(column)))
+            .collect();
+        transaction.execute(
+            &format!(
+                "CREATE TABLE {} (key TEXT PRIMARY KEY{definitions})",
+                quote(table_name)
+            ),
+            [],
+        )?;
+        let names: String = columns
+            .iter()
+            .map(|column| format!(", {}", quote(column)))
+            .collect();
+        let placeholders = ", ?".repeat(columns.len());
+        {
+            let mut insert = transaction.prepare(&format!(
+                "INSERT INTO {} (key{names}) VALUES (?{placeholders})",
+                quote(table_name)
+            ))?;
+            for (key, mut row) in rows {
+                let params = std::iter::once(SqlValue::Text(key.to_string())).chain(
+                    columns
+                        .iter()
+                        .map(|column| sql_value(row.remove(column).unwrap_or(Value::Null))),
+                );
+                insert.execute(rusqlite::params_from_iter(params))?;
+            }
+        }
+        Ok(transaction.commit()?)
+    }
+}
+
+/// Move the fields of an object into `row`, the ones of nested objects with
+/// their paths joined by dots
+fn flatten(prefix: &str, object: Map<String, Value>, row: &mut Map<String, Value>) {
+    for (field, value) in object {
+        let column = match prefix {
+            "" => field,
+            prefix => format!("{prefix}.{field}"),
+        };
+        match value {
+            Value::Object(object) => flatten(&column, object, row),
+            value => {
+                row.insert(column, value);
+            }
+        }
+    }
+}
+
+/// The SQLite value of a json value. Arrays and objects are kept as json
+fn sql_value(value: Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(boolean) => SqlValue::Integer(boolean.into()),
+        Value::Number(number) => match number.as_i64() {
+            Some(int) => SqlValue::Integer(int),
+            None => number.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+        },
+        Value::String(string) => SqlValue::Text(string),
+        value => SqlValue::Text(value.to_string()),
+    }
+}
+
+/// Quote a SQLite identifier
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
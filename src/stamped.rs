@@ -0,0 +1,62 @@
+use crate::{Storage, Table};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Update the timestamps of an element, given the current time and whether
+/// the element is being created
+pub(crate) type Stamper<T> = fn(&mut T, u64, bool);
+
+/// The current time in milliseconds since the unix epoch
+pub(crate) fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// An element with the times in which it was created and last updated, in
+/// milliseconds since the unix epoch, which the table maintains once
+/// `manage_timestamps` is called. The fields of `T` are stored next to the
+/// timestamps, so `T` has to be a struct or a map
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stamped<T> {
+    /// The element that you actually want stored/read
+    #[serde(flatten)]
+    pub info: T,
+    /// When the element was pushed
+    #[serde(default)]
+    pub created_at: u64,
+    /// When the element was last written with changes
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl<T> Stamped<T> {
+    /// Wrap an element, whose timestamps are set when it's pushed
+    pub fn new(info: T) -> Self {
+        Self {
+            info,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn stamp(&mut self, now: u64, is_created: bool) {
+        if is_created {
+            self.created_at = now;
+        }
+        self.updated_at = now;
+    }
+}
+
+impl<T, S> Table<Stamped<T>, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Keep the timestamps of the elements up to date. Pushed elements get
+    /// both timestamps set, and write backs update the timestamp of the
+    /// elements whose content changed, which requires reading their files
+    pub fn manage_timestamps(&mut self) {
+        self.stamper = Some(Stamped::stamp);
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Where the items of a table are kept. An item is a named blob of bytes: the
+/// entries are the items whose name ends in `.json`, and the items whose name
+/// starts with `.table` are managed by the crate itself. Implementing this
+/// trait is enough to keep a `Table` somewhere other than a local directory
+pub trait Storage {
+    /// The names of all the items. Items that can't hold bytes, such as
+    /// directories, should be listed with a trailing `/`
+    ///
+    /// # Errors
+    /// 1. The items can't be listed
+    fn list(&self) -> io::Result<Vec<String>>;
+
+    /// The content of an item
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist or can't be read
+    fn read(&self, name: &str) -> io::Result<Vec<u8>>;
+
+    /// Create an empty item
+    ///
+    /// # Errors
+    /// 1. The item already exists or can't be created
+    fn create(&mut self, name: &str) -> io::Result<()>;
+
+    /// Replace the content of an item, creating it if it doesn't exist
+    ///
+    /// # Errors
+    /// 1. The item can't be written
+    fn write(&mut self, name: &str, content: &[u8]) -> io::Result<()>;
+
+    /// Add content to the end of an item, creating it if it doesn't exist.
+    /// Storages that can't append can rely on this default, which rewrites
+    /// the whole item
+    ///
+    /// # Errors
+    /// 1. The item can't be read or written
+    fn append(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        let mut current = match self.read(name) {
+            Ok(current) => current,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        current.extend_from_slice(content);
+        self.write(name, &current)
+    }
+
+    /// A writer that replaces the content of an item, creating it if it
+    /// doesn't exist. The content is only complete once the writer is
+    /// flushed. Storages that can't stream can rely on this default, which
+    /// collects the content and writes it all when flushed
+    ///
+    /// # Errors
+    /// 1. The item can't be opened for writing
+    fn writer(&mut self, name: &str) -> io::Result<Box<dyn Write + '_>> {
+        Ok(Box::new(ItemWriter {
+            storage: self,
+            name: name.to_string(),
+            content: Vec::new(),
+        }))
+    }
+
+    /// A reader of the content of an item. Storages that can't stream can rely
+    /// on this default, which reads the whole item upfront
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist or can't be read
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(io::Cursor::new(self.read(name)?)))
+    }
+
+    /// Remove an item
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist or can't be removed
+    fn delete(&mut self, name: &str) -> io::Result<()>;
+
+    /// Rename an item
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist, or it can't be renamed
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()>;
+
+    /// The size of an item in bytes. Storages that can tell it without reading
+    /// the item should do so
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist or can't be read
+    fn size(&self, name: &str) -> io::Result<u64> {
+        Ok(self.read(name)?.len() as u64)
+    }
+
+    /// When an item was last modified, if the storage keeps track of it
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist or can't be read
+    fn modified(&self, _name: &str) -> io::Result<Option<SystemTime>> {
+        Ok(None)
+    }
+
+    /// Make sure that the content of an item has reached durable storage.
+    /// Storages that are durable on every write don't need to do anything
+    ///
+    /// # Errors
+    /// 1. The item can't be synced
+    fn sync(&self, _name: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Like `sync`, but only the content of the item needs to be synced, not
+    /// its metadata
+    ///
+    /// # Errors
+    /// 1. The item can't be synced
+    fn sync_data(&self, name: &str) -> io::Result<()> {
+        self.sync(name)
+    }
+
+    /// Make sure that the list of items has reached durable storage, so that
+    /// created, removed and renamed items are kept
+    ///
+    /// # Errors
+    /// 1. The list of items can't be synced
+    fn sync_dir(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Set the permissions of an item as a unix mode. Storages without
+    /// permissions don't need to do anything
+    ///
+    /// # Errors
+    /// 1. The permissions can't be set
+    fn set_mode(&mut self, _name: &str, _mode: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The path that an item links to, if it's a symbolic link. Storages
+    /// without links don't need to do anything
+    ///
+    /// # Errors
+    /// 1. The item doesn't exist, or its link can't be read
+    fn link_target(&self, _name: &str) -> io::Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
+    /// The local directory in which the items are kept, if there's one
+    fn dir(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Collects the content of an item to write it whole, for storages that can't
+/// stream
+struct ItemWriter<'a, S: ?Sized> {
+    storage: &'a mut S,
+    name: String,
+    content: Vec<u8>,
+}
+
+impl<S: Storage + ?Sized> Write for ItemWriter<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.content.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.storage.write(&self.name, &self.content)
+    }
+}
+
+/// The default storage, that keeps every item as a file of a directory
+#[derive(Debug, Default, Clone)]
+pub struct FsStorage {
+    dir: PathBuf,
+}
+
+impl FsStorage {
+    /// A storage in a directory, that must exist when it's used
+    pub fn new<Q: AsRef<Path>>(dir: Q) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Storage for FsStorage {
+    fn list(&self) -> io::Result<Vec<String>> {
+        fs::read_dir(&self.dir)?
+            .map(|dir_entry| {
+                let dir_entry = dir_entry?;
+                let name = dir_entry.file_name().to_string_lossy().into_owned();
+                Ok(match dir_entry.file_type()?.is_dir() {
+                    true => format!("{name}/"),
+                    false => name,
+                })
+            })
+            .collect()
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.dir.join(name))
+    }
+
+    fn create(&mut self, name: &str) -> io::Result<()> {
+        File::options()
+            .write(true)
+            .create_new(true)
+            .open(self.dir.join(name))?;
+        Ok(())
+    }
+
+    fn write(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        fs::write(self.dir.join(name), content)
+    }
+
+    fn writer(&mut self, name: &str) -> io::Result<Box<dyn Write + '_>> {
+        Ok(Box::new(BufWriter::new(File::create(self.dir.join(name))?)))
+    }
+
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(BufReader::new(File::open(self.dir.join(name))?)))
+    }
+
+    fn append(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        File::options()
+            .append(true)
+            .create(true)
+            .open(self.dir.join(name))?
+            .write_all(content)
+    }
+
+    fn delete(&mut self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.dir.join(name))
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        fs::rename(self.dir.join(from), self.dir.join(to))
+    }
+
+    fn size(&self, name: &str) -> io::Result<u64> {
+        Ok(fs::metadata(self.dir.join(name))?.len())
+    }
+
+    fn modified(&self, name: &str) -> io::Result<Option<SystemTime>> {
+        Ok(Some(fs::metadata(self.dir.join(name))?.modified()?))
+    }
+
+    fn sync(&self, name: &str) -> io::Result<()> {
+        File::open(self.dir.join(name))?.sync_all()
+    }
+
+    fn set_mode(&mut self, name: &str, mode: u32) -> io::Result<()> {
+        set_mode(&self.dir.join(name), mode)
+    }
+
+    fn link_target(&self, name: &str) -> io::Result<Option<PathBuf>> {
+        let path = self.dir.join(name);
+        match fs::symlink_metadata(&path)?.is_symlink() {
+            // relative links are relative to the directory of the link
+            true => Ok(Some(self.dir.join(fs::read_link(path)?))),
+            false => Ok(None),
+        }
+    }
+
+    fn sync_data(&self, name: &str) -> io::Result<()> {
+        File::open(self.dir.join(name))?.sync_data()
+    }
+
+    #[cfg(unix)]
+    fn sync_dir(&self) -> io::Result<()> {
+        File::open(&self.dir)?.sync_all()
+    }
+
+    fn dir(&self) -> Option<&Path> {
+        Some(&self.dir)
+    }
+}
+
+/// Set the permissions of a file or directory as a unix mode. Elsewhere it's
+/// only made read only when the mode has no write permission
+pub(crate) fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    let permissions = {
+        use std::os::unix::fs::PermissionsExt;
+        fs::Permissions::from_mode(mode)
+    };
+    #[cfg(not(unix))]
+    let permissions = {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_readonly(mode & 0o222 == 0);
+        permissions
+    };
+    fs::set_permissions(path, permissions)
+}
+
+/// A storage that keeps every item in memory, so nothing survives the table
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStorage {
+    items: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    /// An empty storage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A storage with some items, given as pairs of name and content
+    pub fn with_items<I, S, B>(items: I) -> Self
+    where
+        I: IntoIterator<Item = (S, B)>,
+        S: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        Self {
+            items: items
+                .into_iter()
+                .map(|(name, content)| (name.into(), content.into()))
+                .collect(),
+        }
+    }
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{name} doesn't exist"))
+}
+
+impl Storage for MemoryStorage {
+    fn list(&self) -> io::Result<Vec<String>> {
+        Ok(self.items.keys().cloned().collect())
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        self.items.get(name).cloned().ok_or_else(|| not_found(name))
+    }
+
+    fn create(&mut self, name: &str) -> io::Result<()> {
+        if self.items.contains_key(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{name} already exists"),
+            ));
+        }
+        self.items.insert(name.to_string(), Vec::new());
+        Ok(())
+    }
+
+    fn write(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        self.items.insert(name.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, name: &str) -> io::Result<()> {
+        self.items
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| not_found(name))
+    }
+
+    fn size(&self, name: &str) -> io::Result<u64> {
+        self.items
+            .get(name)
+            .map(|content| content.len() as u64)
+            .ok_or_else(|| not_found(name))
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        let content = self.items.remove(from).ok_or_else(|| not_found(from))?;
+        self.items.insert(to.to_string(), content);
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    config, entry_keys, ContentPolicy, FsStorage, Storage, Table, TableError, TableMetadata,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{marker::PhantomData, path::Path, vec};
+
+/// An iterator over the elements of a table that reads, deserializes and
+/// yields them one at a time, without ever holding the whole table
+#[derive(Debug)]
+pub struct TableStream<T, S = FsStorage> {
+    storage: S,
+    metadata: TableMetadata,
+    keys: vec::IntoIter<String>,
+    data: PhantomData<T>,
+}
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Go through the elements of the table in `dir` one file at a time, with
+    /// the policies stored in the table. Only the names of the files are read
+    /// upfront
+    ///
+    /// # Errors
+    /// 1. The stored policies can't be read
+    /// 2. The directory can't be read
+    /// 3. There was a non .json file in a table with the `OnlyJsonFiles` extension policy
+    pub fn stream<Q: AsRef<Path>>(dir: Q) -> Result<TableStream<T>, TableError> {
+        let metadata = config::read_metadata(&dir)?.unwrap_or_default();
+        TableStream::with_storage(FsStorage::new(dir), metadata)
+    }
+}
+
+impl<T, S> TableStream<T, S>
+where
+    T: DeserializeOwned,
+    S: Storage,
+{
+    /// Go through the elements kept in any storage one at a time
+    ///
+    /// # Errors
+    /// 1. The items of the storage can't be listed
+    /// 2. There was a non .json item in a table with the `OnlyJsonFiles` extension policy
+    pub fn with_storage(storage: S, metadata: TableMetadata) -> Result<Self, TableError> {
+        let keys = entry_keys(&storage, &metadata)?;
+        Ok(Self {
+            storage,
+            metadata,
+            keys: keys.into_iter(),
+            data: PhantomData,
+        })
+    }
+}
+
+impl<T, S> Iterator for TableStream<T, S>
+where
+    T: DeserializeOwned,
+    S: Storage,
+{
+    type Item = Result<(String, T), TableError>;
+
+    /// The next element. Elements that can't be deserialized are skipped with
+    /// the `IgnoreSerdeErrors` policy, and yielded as errors otherwise
+    fn next(&mut self) -> Option<Self::Item> {
+        for key in self.keys.by_ref() {
+            let content = match self.storage.read(&self.metadata.entry_name(&key)) {
+                Ok(content) => content,
+                Err(e) => return Some(Err(e.into())),
+            };
+            match self.metadata.parse(&content) {
+                Ok((info, _)) => return Some(Ok((key, info))),
+                Err(serde_error) => match self.metadata.content_policy {
+                    ContentPolicy::IgnoreSerdeErrors => continue,
+                    ContentPolicy::PromoteSerdeErrors => return Some(Err(serde_error.into())),
+                },
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.keys.len()))
+    }
+}
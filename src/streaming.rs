@@ -0,0 +1,137 @@
+use crate::{staging_name, Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    hash::{DefaultHasher, Hasher},
+    io::{self, Read, Write},
+};
+
+/// Hashes what is written through it the same way as `content_hash`
+struct HashingWriter<W> {
+    inner: W,
+    hasher: DefaultHasher,
+    len: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.write(&buf[..written]);
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Hashes what is read through it the same way as `content_hash`
+struct HashingReader<R> {
+    inner: R,
+    hasher: DefaultHasher,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.write(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Write a single element into its file, serializing it straight through a
+    /// buffered writer instead of into memory first, which suits elements with
+    /// huge values. The element is streamed into a staging item that is then
+    /// moved over its file, and like in a write back the unique constraints
+    /// are checked first, and the history, the version and the audit log are
+    /// kept. It isn't committed with git. Canonical tables still serialize the
+    /// element in memory, and tables that keep history read it back to save it
+    ///
+    /// # Errors
+    /// 1. If you don't have permission to write
+    /// 2. The element doesn't exist
+    /// 3. The element is frozen
+    /// 4. A unique constraint is violated, in which case nothing is written
+    /// 5. There are problems with serialization or with the file
+    pub fn write_entry(&mut self, key: &str) -> Result<(), TableError> {
+        self.mod_permissions()?;
+        self.check_frozen(key)?;
+        let key = self
+            .metadata
+            .normalization_policy
+            .normalize(key)
+            .into_owned();
+        self.restore(&key)?;
+        let Some(element) = self.content.get(key.as_str()) else {
+            return Err(TableError::MissingKeyError(key));
+        };
+        self.check_unique()?;
+        let name = self.metadata.entry_name(&key);
+        let staging = staging_name(&name);
+        let mut writer = HashingWriter {
+            inner: self.storage.writer(&staging)?,
+            hasher: DefaultHasher::new(),
+            len: 0,
+        };
+        let streamed = self
+            .metadata
+            .to_writer(&mut writer, &element.info)
+            .map_err(TableError::from)
+            .and_then(|()| Ok(writer.flush()?));
+        let (hash, len) = (writer.hasher.finish(), writer.len);
+        drop(writer);
+        let committed = streamed
+            .and_then(|()| match self.metadata.permissions {
+                Some(permissions) => Ok(self.storage.set_mode(&staging, permissions.file_mode)?),
+                None => Ok(()),
+            })
+            .and_then(|()| self.commit_entry(&key, &staging, hash));
+        if let Err(e) = committed {
+            // the staging item is left over otherwise, where it's ignored and
+            // overwritten by the next write
+            let _ = self.storage.delete(&staging);
+            return Err(e);
+        }
+        self.metrics.bytes_written += len;
+        self.metadata
+            .durability_policy
+            .apply(&self.storage, [name.as_str()])?;
+        self.audit(serde_json::json!({"op": "write_entry", "key": key}))
+    }
+
+    /// Read a single element again from its file, deserializing it as it's
+    /// read instead of reading the whole file first, and replace the one in
+    /// the table. The file is read as strict json, whatever the syntax policy
+    ///
+    /// # Errors
+    /// 1. The element doesn't exist
+    /// 2. There are problems with deserialization or with the file
+    pub fn reload_entry(&mut self, key: &str) -> Result<(), TableError> {
+        let key = self
+            .metadata
+            .normalization_policy
+            .normalize(key)
+            .into_owned();
+        self.restore(&key)?;
+        if !self.content.contains_key(key.as_str()) {
+            return Err(TableError::MissingKeyError(key));
+        }
+        let mut reader = HashingReader {
+            inner: self.storage.reader(&self.metadata.entry_name(&key))?,
+            hasher: DefaultHasher::new(),
+        };
+        let info = serde_json::from_reader(&mut reader)?;
+        let hash = reader.hasher.finish();
+        drop(reader);
+        // the element has just been checked, therefore the unwrap
+        let element = self.content.get_mut(key.as_str()).unwrap();
+        element.info = info;
+        element.written = Some(hash);
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{content_hash, Storage, Table, TableMetadata};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, path::PathBuf};
+
+/// The most keys listed by a summary
+const SUMMARY_KEYS: usize = 10;
+
+/// A short description of a table, to be printed in logs instead of the whole
+/// table
+#[derive(Debug, Clone)]
+pub struct TableSummary {
+    /// The directory of the table, if it's kept in a local directory
+    pub dir: Option<PathBuf>,
+    /// The policies of the table
+    pub metadata: TableMetadata,
+    /// The number of elements
+    pub entries: usize,
+    /// The number of elements that changed since they were last read or
+    /// written
+    pub dirty: usize,
+    /// The first keys of the table
+    pub keys: Vec<String>,
+}
+
+impl fmt::Display for TableSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.dir {
+            Some(dir) => write!(f, "Table at {}", dir.display())?,
+            None => write!(f, "Table in memory")?,
+        }
+        let metadata = &self.metadata;
+        write!(
+            f,
+            " ({:?}, {:?}, {:?}, {:?} format): {} entries, {} dirty",
+            metadata.rw_policy,
+            metadata.extension_policy,
+            metadata.content_policy,
+            metadata.format_policy,
+            self.entries,
+            self.dirty,
+        )?;
+        if !self.keys.is_empty() {
+            write!(f, ", keys: {}", self.keys.join(", "))?;
+        }
+        if self.entries > self.keys.len() {
+            write!(f, " and {} more", self.entries - self.keys.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// A short description of the table, which is also how it's displayed
+    pub fn summary(&self) -> TableSummary {
+        let dirty = self
+            .content
+            .values()
+            .filter(|element| match self.metadata.to_vec(&element.info) {
+                Ok(content) => element.written != Some(content_hash(&content)),
+                Err(_) => true,
+            })
+            .count();
+        TableSummary {
+            dir: self.storage.dir().map(PathBuf::from),
+            metadata: self.metadata,
+            entries: self.len(),
+            dirty,
+            keys: self
+                .all_keys()
+                .take(SUMMARY_KEYS)
+                .map(|key| key.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl<T, S> fmt::Display for Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
@@ -18,6 +18,57 @@ pub enum TableError {
     PushError(String),
     /// Tried to pop a non existant key,
     PopError(String),
+    /// A json patch couldn't be applied to an element
+    PatchError(json_patch::PatchError),
+    /// The table was built for a different schema version, the expected one
+    /// and the one found
+    SchemaError(String, String),
+    /// A new table couldn't be created
+    BuilderError(TableBuilderError),
+    /// The key of an element isn't valid
+    KeyError(String),
+    /// A unique constraint was violated, its name and the repeated value
+    UniqueError(String, String),
+    /// A reference between tables is broken, the referencing field and the
+    /// missing key
+    ReferenceError(String, String),
+    /// There is no table with that name and type in the group
+    GroupError(String),
+    /// The thread that owns the table is gone
+    WorkerError,
+    /// An entry is a symbolic link, which the symlink policy forbids
+    SymlinkError(String),
+    /// An entry that was asked for doesn't exist
+    MissingKeyError(String),
+    /// The table has more entries than the maximum that can be loaded
+    TooManyEntriesError(usize),
+    /// The file of an entry is bigger than the size policy allows
+    SizeError(String),
+    /// The operation was cancelled before it finished
+    CancelledError,
+    /// A version of an element isn't kept, the key and the version
+    VersionError(String, u64),
+    /// An element was written elsewhere since it was read, the key and its
+    /// current version
+    ConflictError(String, u64),
+    /// The file of an entry was edited elsewhere since it was read
+    ExternalEditError(String),
+    /// An object of an entry repeats a key, the entry and the json pointer of
+    /// the key
+    DuplicateKeyError(String, String),
+    /// Several entries have the same key once it's normalized
+    NormalizationError(String),
+    /// A frozen element was going to be changed
+    FrozenError(String),
+    /// A table of a group failed to commit its write back, the names of the
+    /// tables that were committed before it, its name and the error
+    PartialCommitError(Vec<String>, String, Box<TableError>),
+    /// There was an error with a SQLite database
+    #[cfg(feature = "sqlite")]
+    SqliteError(rusqlite::Error),
+    /// There was an error while writing a parquet file
+    #[cfg(feature = "parquet")]
+    ParquetError(parquet::errors::ParquetError),
 }
 
 impl fmt::Display for TableError {
@@ -43,6 +94,61 @@ impl fmt::Display for TableError {
             }
             Self::PopError(s) => {
                 write!(f, "File {s}.json doesn't exist in the table")
+            }
+            Self::PatchError(e) => write!(f, "{e}"),
+            Self::BuilderError(e) => write!(f, "{e}"),
+            Self::KeyError(s) => write!(f, "{s} is not a valid key for the table"),
+            Self::ReferenceError(field, value) => {
+                write!(f, "{field} references {value}, which doesn't exist")
+            }
+            Self::WorkerError => write!(f, "The thread that owns the table is gone"),
+            Self::SymlinkError(s) => write!(f, "File {s}.json is a symbolic link"),
+            Self::VersionError(key, version) => {
+                write!(f, "Version {version} of {key} isn't kept")
+            }
+            Self::ConflictError(key, version) => {
+                write!(f, "{key} was changed elsewhere, it's at version {version}")
+            }
+            Self::ExternalEditError(s) => write!(f, "File {s}.json was edited elsewhere"),
+            Self::DuplicateKeyError(s, path) => {
+                write!(f, "File {s}.json repeats the key at {path}")
+            }
+            Self::NormalizationError(s) => {
+                write!(
+                    f,
+                    "Several files are {s}.json once their names are normalized"
+                )
+            }
+            Self::FrozenError(s) => write!(f, "File {s}.json is frozen"),
+            Self::PartialCommitError(committed, failed, e) => write!(
+                f,
+                "The write back of the table {failed} failed after the tables [{}] were written: {e}",
+                committed.join(", ")
+            ),
+            #[cfg(feature = "sqlite")]
+            Self::SqliteError(e) => write!(f, "{e}"),
+            #[cfg(feature = "parquet")]
+            Self::ParquetError(e) => write!(f, "{e}"),
+            Self::CancelledError => write!(f, "The operation was cancelled"),
+            Self::SizeError(s) => write!(f, "File {s}.json is too big to be loaded"),
+            Self::TooManyEntriesError(max) => {
+                write!(f, "The table has more than the maximum of {max} entries")
+            }
+            Self::MissingKeyError(s) => write!(f, "File {s}.json was asked for but doesn't exist"),
+            Self::GroupError(s) => {
+                write!(
+                    f,
+                    "There is no table {s} of the requested type in the group"
+                )
+            }
+            Self::UniqueError(name, value) => {
+                write!(f, "The value {value} of {name} is repeated in the table")
+            }
+            Self::SchemaError(expected, found) => {
+                write!(
+                    f,
+                    "The table was created for a different type or version, expected {expected} but found {found}"
+                )
             } // _ => write!(f, "Weird error with a Table"),
         }
     }
@@ -62,6 +168,32 @@ impl From<serde_json::Error> for TableError {
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for TableError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::SqliteError(e)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for TableError {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        Self::ParquetError(e)
+    }
+}
+
+impl From<TableBuilderError> for TableError {
+    fn from(e: TableBuilderError) -> Self {
+        Self::BuilderError(e)
+    }
+}
+
+impl From<json_patch::PatchError> for TableError {
+    fn from(e: json_patch::PatchError) -> Self {
+        Self::PatchError(e)
+    }
+}
+
 /// Error trying to create a new table
 #[derive(Debug)]
 pub enum TableBuilderError {
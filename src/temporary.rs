@@ -0,0 +1,44 @@
+use crate::{Table, TableBuilderError, TableMetadata};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Tells apart the temporary tables created by the same process
+static TEMPORARY_TABLES: AtomicU64 = AtomicU64::new(0);
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Create a table in a new directory of the temporary directory of the
+    /// system. The directory is removed with everything in it when the table,
+    /// or the shared table made from it, is dropped, even while panicking, and
+    /// nothing is written back then
+    ///
+    /// # Errors
+    /// 1. The directory can't be created
+    pub fn temporary() -> Result<Self, TableBuilderError> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let count = TEMPORARY_TABLES.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("json_tables_{}_{nanos}_{count}", process::id()));
+        let mut table = Table::new(&dir, TableMetadata::default())?;
+        table.temporary = Some(dir);
+        Ok(table)
+    }
+}
+
+/// Remove the directory of a temporary table, if it's one
+pub(crate) fn remove(temporary: Option<PathBuf>) {
+    if let Some(dir) = temporary {
+        // there's nowhere to report a failure to remove it from, and the
+        // system cleans its temporary directory eventually
+        let _ = fs::remove_dir_all(dir);
+    }
+}
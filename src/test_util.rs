@@ -0,0 +1,76 @@
+use crate::{MemoryStorage, Storage, Table, TableError, TableMetadata};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Push `count` elements made by `f` from their index, under the ids that
+    /// follow the largest id in the table, and write them back
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `push_id` or the `write_back`
+    pub fn populate<F>(&mut self, count: usize, mut f: F) -> Result<(), TableError>
+    where
+        F: FnMut(usize) -> T,
+    {
+        let first = self.max_id().map_or(0, |id| id + 1);
+        for (i, id) in (first..).take(count).enumerate() {
+            self.push_id(id, f(i))?;
+        }
+        self.write_back()
+    }
+}
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Create a table in `dir` with the default policies and `count` elements
+    /// made by `f` from their index, which is also their key
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `new` or the `populate`
+    pub fn seed<Q, F>(dir: Q, count: usize, f: F) -> Result<Self, TableError>
+    where
+        Q: AsRef<Path>,
+        F: FnMut(usize) -> T,
+    {
+        let mut table = Table::new(dir, TableMetadata::default())?;
+        table.populate(count, f)?;
+        Ok(table)
+    }
+
+    /// Like `seed`, but in a temporary table that is removed when dropped
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `temporary` or the `populate`
+    pub fn seed_temporary<F>(count: usize, f: F) -> Result<Self, TableError>
+    where
+        F: FnMut(usize) -> T,
+    {
+        let mut table = Table::temporary()?;
+        table.populate(count, f)?;
+        Ok(table)
+    }
+}
+
+impl<T> Table<T, MemoryStorage>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Like `seed`, but in a table kept in memory
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `populate`
+    pub fn seed_in_memory<F>(count: usize, f: F) -> Result<Self, TableError>
+    where
+        F: FnMut(usize) -> T,
+    {
+        let mut table = Table::in_memory();
+        table.populate(count, f)?;
+        Ok(table)
+    }
+}
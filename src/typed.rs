@@ -0,0 +1,153 @@
+use crate::{FsStorage, Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+/// Types that can be used as the keys of a `TypedTable`. A key is turned into
+/// the name of its file without the `.json` extension, and back
+pub trait TableKey: Sized {
+    /// The name of the file of the key, without the `.json` extension
+    fn to_key(&self) -> String;
+
+    /// The key of a file name, or `None` if the name isn't a key of this type.
+    /// It must give back the same key for every name made by `to_key`
+    fn from_key(key: &str) -> Option<Self>;
+}
+
+impl TableKey for String {
+    fn to_key(&self) -> String {
+        self.clone()
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Some(key.to_string())
+    }
+}
+
+macro_rules! integer_keys {
+    ($($int:ty),*) => {
+        $(
+            impl TableKey for $int {
+                fn to_key(&self) -> String {
+                    self.to_string()
+                }
+
+                /// Only the canonical representation of the number is a key,
+                /// so that `007` and `7` aren't mistaken for the same key
+                fn from_key(key: &str) -> Option<Self> {
+                    key.parse()
+                        .ok()
+                        .filter(|int: &$int| int.to_string() == key)
+                }
+            }
+        )*
+    };
+}
+
+integer_keys!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A table whose elements are accessed by keys of type `K` instead of strings.
+/// The names of the files that aren't keys of that type are still in the
+/// table, but they are skipped by the typed accessors
+#[derive(Debug)]
+pub struct TypedTable<K, T, S = FsStorage>
+where
+    K: TableKey,
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    table: Table<T, S>,
+    key: PhantomData<K>,
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Access the elements of the table by keys of type `K`
+    pub fn typed<K: TableKey>(self) -> TypedTable<K, T, S> {
+        TypedTable {
+            table: self,
+            key: PhantomData,
+        }
+    }
+}
+
+impl<K, T, S> TypedTable<K, T, S>
+where
+    K: TableKey,
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Add an element and create its file
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `push` of the table
+    pub fn push(&mut self, key: &K, info_elem: T) -> Result<(), TableError> {
+        self.table.push(&key.to_key(), info_elem)
+    }
+
+    /// Remove an element and delete its file
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `pop` of the table
+    pub fn pop(&mut self, key: &K) -> Result<(), TableError> {
+        self.table.pop(&key.to_key())
+    }
+
+    /// Get the information of an element
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.table
+            .get_element(&key.to_key())
+            .map(|element| &element.info)
+    }
+
+    /// Get the mutable information of an element
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.table
+            .get_mut_element(&key.to_key())
+            .map(|element| &mut element.info)
+    }
+
+    /// Whether there's an element with that key
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.table.get_element(&key.to_key()).is_some()
+    }
+
+    /// The keys of the elements, in the order of their file names
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.table.all_keys().filter_map(|key| K::from_key(key))
+    }
+
+    /// An iterator over the keys and information of the elements, in the
+    /// order of their file names
+    pub fn iter(&self) -> impl Iterator<Item = (K, &T)> {
+        self.table
+            .content
+            .iter()
+            .filter_map(|(key, element)| Some((K::from_key(key)?, &element.info)))
+    }
+
+    /// Write the changes in the corresponding files
+    ///
+    /// # Errors
+    /// 1. Whenever there is an error with the `write_back` of the table
+    pub fn write_back(&mut self) -> Result<(), TableError> {
+        self.table.write_back()
+    }
+
+    /// The underlying table
+    pub fn table(&self) -> &Table<T, S> {
+        &self.table
+    }
+
+    /// The underlying table, to use the rest of its API
+    pub fn table_mut(&mut self) -> &mut Table<T, S> {
+        &mut self.table
+    }
+
+    /// Go back to accessing the elements by strings
+    pub fn into_inner(self) -> Table<T, S> {
+        self.table
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{Storage, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// A field derived from the elements of a table that must be unique
+pub(crate) struct Unique<T> {
+    name: String,
+    extract: Box<dyn Fn(&T) -> String + Send + Sync>,
+}
+
+impl<T> Unique<T> {
+    pub(crate) fn new<S, F>(name: S, extract: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&T) -> String + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            extract: Box::new(extract),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Unique<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unique").field("name", &self.name).finish()
+    }
+}
+
+impl<T, S> Table<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Storage,
+{
+    /// Enforce that the field called `name`, as extracted by `extract`, is
+    /// unique among the elements of the table. Pushes that would violate the
+    /// constraint are rejected, and since elements can be changed through
+    /// mutable references, the constraint is also checked on every write back
+    ///
+    /// # Errors
+    /// 1. The elements of the table already violate the constraint
+    pub fn unique<N, F>(&mut self, name: N, extract: F) -> Result<(), TableError>
+    where
+        N: Into<String>,
+        F: Fn(&T) -> String + Send + Sync + 'static,
+    {
+        self.add_unique(Unique::new(name, extract))
+    }
+
+    /// Add a constraint, checking that the table doesn't violate it already
+    pub(crate) fn add_unique(&mut self, constraint: Unique<T>) -> Result<(), TableError> {
+        self.unique_constraints.push(constraint);
+        self.check_unique().inspect_err(|_| {
+            self.unique_constraints.pop();
+        })
+    }
+
+    /// Check every unique constraint of the table
+    ///
+    /// # Errors
+    /// 1. Some constraint is violated
+    pub fn check_unique(&self) -> Result<(), TableError> {
+        for constraint in &self.unique_constraints {
+            let mut seen = HashSet::new();
+            for (_, element) in self.all_elements() {
+                let value = (constraint.extract)(&element.info);
+                if seen.contains(&value) {
+                    return Err(TableError::UniqueError(constraint.name.clone(), value));
+                }
+                seen.insert(value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that pushing an element wouldn't violate any unique constraint
+    pub(crate) fn check_unique_push(&self, info: &T) -> Result<(), TableError> {
+        for constraint in &self.unique_constraints {
+            let value = (constraint.extract)(info);
+            if self
+                .all_elements()
+                .any(|(_, element)| (constraint.extract)(&element.info) == value)
+            {
+                return Err(TableError::UniqueError(constraint.name.clone(), value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the groups of elements with identical content, for example to
+    /// deduplicate data imported from several sources. Each group has the
+    /// sorted keys of two or more elements, and the groups are sorted by
+    /// their first key
+    ///
+    /// # Errors
+    /// 1. Some element can't be serialized
+    pub fn find_duplicates(&self) -> Result<Vec<Vec<String>>, TableError> {
+        let mut groups: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+        for (key, element) in self.all_elements() {
+            // going through a value sorts the fields of maps, so that equal
+            // elements always serialize the same way
+            let content = serde_json::to_vec(&serde_json::to_value(&element.info)?)?;
+            groups.entry(content).or_default().push(key.to_string());
+        }
+        let mut duplicates: Vec<_> = groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect();
+        duplicates.sort();
+        Ok(duplicates)
+    }
+}
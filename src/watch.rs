@@ -0,0 +1,153 @@
+use crate::{is_reserved, Table, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// A change of an entry file of a table
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TableEvent {
+    /// A new entry file appeared
+    Created(Arc<str>),
+    /// An entry file changed its content
+    Modified(Arc<str>),
+    /// An entry file disappeared
+    Removed(Arc<str>),
+}
+
+/// What's known of a file to detect its changes
+type FileState = (Option<SystemTime>, u64);
+
+/// Watches the directory of a table for changes in its entry files by polling
+/// it. Every change is reported, including the ones made by the table itself
+#[derive(Debug)]
+pub struct TableWatcher {
+    dir: PathBuf,
+    files: HashMap<Arc<str>, FileState>,
+}
+
+impl TableWatcher {
+    /// Start watching a directory from its current state
+    ///
+    /// # Errors
+    /// 1. The directory can't be read
+    pub fn new<Q: AsRef<Path>>(dir: Q) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let files = scan(&dir)?;
+        Ok(Self { dir, files })
+    }
+
+    /// The changes since the previous poll, or since the watcher was created
+    ///
+    /// # Errors
+    /// 1. The directory can't be read
+    pub fn poll(&mut self) -> io::Result<Vec<TableEvent>> {
+        let files = scan(&self.dir)?;
+        let mut events = Vec::new();
+        for (name, state) in &files {
+            match self.files.get(name) {
+                None => events.push(TableEvent::Created(name.clone())),
+                Some(old) if old != state => events.push(TableEvent::Modified(name.clone())),
+                Some(_) => {}
+            }
+        }
+        for name in self.files.keys() {
+            if !files.contains_key(name) {
+                events.push(TableEvent::Removed(name.clone()));
+            }
+        }
+        self.files = files;
+        Ok(events)
+    }
+}
+
+/// The state of the entry files of a directory
+fn scan(dir: &Path) -> io::Result<HashMap<Arc<str>, FileState>> {
+    let mut files = HashMap::new();
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if is_reserved(&path) || Some(OsStr::new("json")) != path.extension() {
+            continue;
+        }
+        let (Some(name), Ok(metadata)) = (
+            path.file_stem().and_then(OsStr::to_str),
+            fs::metadata(&path),
+        ) else {
+            continue;
+        };
+        if metadata.is_file() {
+            files.insert(name.into(), (metadata.modified().ok(), metadata.len()));
+        }
+    }
+    Ok(files)
+}
+
+impl<T> Table<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Start watching the directory of the table for changes
+    ///
+    /// # Errors
+    /// 1. The directory can't be read
+    pub fn watcher(&self) -> Result<TableWatcher, TableError> {
+        Ok(TableWatcher::new(self.dir())?)
+    }
+
+    /// A stream of the changes in the directory of the table, checked every
+    /// `period`. It needs to be polled inside a tokio runtime with the time
+    /// driver enabled. Keep in mind that the directory is read synchronously,
+    /// which is fine for the short tables this crate is meant for
+    ///
+    /// # Errors
+    /// 1. The directory can't be read
+    #[cfg(feature = "async")]
+    pub fn changes(
+        &self,
+        period: std::time::Duration,
+    ) -> Result<impl futures_core::Stream<Item = TableEvent>, TableError> {
+        Ok(ChangeStream {
+            watcher: self.watcher()?,
+            interval: tokio::time::interval(period),
+            pending: std::collections::VecDeque::new(),
+        })
+    }
+}
+
+/// The stream of changes of a table
+#[cfg(feature = "async")]
+struct ChangeStream {
+    watcher: TableWatcher,
+    interval: tokio::time::Interval,
+    pending: std::collections::VecDeque<TableEvent>,
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for ChangeStream {
+    type Item = TableEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<TableEvent>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.pending.pop_front() {
+                return std::task::Poll::Ready(Some(event));
+            }
+            match this.interval.poll_tick(cx) {
+                std::task::Poll::Ready(_) => match this.watcher.poll() {
+                    Ok(events) => this.pending.extend(events),
+                    // the directory is gone, so there won't be more changes
+                    Err(_) => return std::task::Poll::Ready(None),
+                },
+                std::task::Poll::Pending => return std::task::Poll::Pending,
+            }
+        }
+    }
+}
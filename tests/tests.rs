@@ -1,8 +1,15 @@
 //! All tests are integration tests, because this way I get a feel for the
 //! ergonomics of the crate
+#![allow(clippy::assertions_on_constants)]
 
 #[cfg(test)]
-use json_tables::{Deserialize, Serialize, Table, TableBuilderError, TableError};
+use json_tables::{
+    AtomicityPolicy, ConcurrencyPolicy, ContentPolicy, Deserialize, DurabilityPolicy, Expiry,
+    ExtensionPolicy, ExternalEditPolicy, FormatPolicy, HiddenFilePolicy, IdPolicy, Keyed,
+    LongKeyPolicy, MemoryStorage, Migrations, NormalizationPolicy, RWPolicy, RawTable, Serialize,
+    SizePolicy, Stamped, SymlinkPolicy, Table, TableBuilderError, TableError, TableEvent,
+    TableGroup, TableMetadata,
+};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 struct ExampleStruct {
@@ -20,6 +27,12 @@ struct SimplifiedStruct {
     float: f64,
 }
 
+impl Keyed for SimplifiedStruct {
+    fn key(&self) -> String {
+        format!("id_{}", self.int)
+    }
+}
+
 #[test]
 fn err_load_table_doesnt_exist() {
     match Table::<ExampleStruct>::builder("tests/doesnt_exist").load() {
@@ -52,7 +65,7 @@ fn create_automatic_table() {
             .len()
     );
     std::fs::remove_file("tests/create_table/hola.json").unwrap();
-    std::fs::remove_dir("tests/create_table").unwrap();
+    std::fs::remove_dir_all("tests/create_table").unwrap();
 }
 
 #[test]
@@ -75,7 +88,7 @@ fn create_manual_table() {
             .len()
     );
     std::fs::remove_file("tests/create_table_2/hola.json").unwrap();
-    std::fs::remove_dir("tests/create_table_2").unwrap();
+    std::fs::remove_dir_all("tests/create_table_2").unwrap();
     {
         let mut table = Table::<ExampleStruct>::builder("tests/create_table_2")
             .set_manual_write()
@@ -94,7 +107,7 @@ fn create_manual_table() {
             .len()
     );
     std::fs::remove_file("tests/create_table_2/hola.json").unwrap();
-    std::fs::remove_dir("tests/create_table_2").unwrap();
+    std::fs::remove_dir_all("tests/create_table_2").unwrap();
 }
 
 #[test]
@@ -201,7 +214,7 @@ fn keys() {
         a = a
             .clone()
             .into_iter()
-            .filter(|&y| y.to_string() != *x)
+            .filter(|&y| y.to_string() != **x)
             .collect();
         a.len() == l - 1
     }));
@@ -227,7 +240,7 @@ fn iter() {
         .unwrap();
     assert!(table
         .iter()
-        .all(|(string, element)| { *string == element.info.int.to_string() }));
+        .all(|(string, element)| { **string == element.info.int.to_string() }));
 }
 
 #[test]
@@ -486,7 +499,11 @@ fn soft_del() {
         .load()
         .unwrap();
     assert_eq!(table.len(), 4);
-    std::fs::rename("tests/delete/another_name.json_soft_delete", "tests/delete/0.json").unwrap();
+    std::fs::rename(
+        "tests/delete/another_name.json_soft_delete",
+        "tests/delete/0.json",
+    )
+    .unwrap();
     let table = Table::<SimplifiedStruct>::builder("tests/delete")
         .load()
         .unwrap();
@@ -516,3 +533,3439 @@ fn soft_del_err() {
     };
     std::fs::rename("tests/delete_2/0.json_soft_delete", "tests/delete_2/0.json").unwrap();
 }
+
+#[test]
+fn pointer() {
+    let table = Table::<ExampleStruct>::builder("tests/normal")
+        .load()
+        .unwrap();
+    assert_eq!(table.get_pointer("1", "/int").unwrap().unwrap(), 1);
+    assert_eq!(table.get_pointer("1", "/tuple/0").unwrap().unwrap(), 0);
+    assert!(table.get_pointer("1", "/not_a_field").unwrap().is_none());
+    assert!(table.get_pointer("100", "/int").unwrap().is_none());
+    let query = table.query_pointer("/int").unwrap();
+    assert_eq!(query.len(), 5);
+    assert!(query
+        .iter()
+        .all(|(name, value)| *value == name.parse::<i64>().unwrap()));
+    assert!(table.query_pointer("/not_a_field").unwrap().is_empty());
+}
+
+#[test]
+fn json_patch() {
+    let mut table = Table::<ExampleStruct>::builder("tests/normal")
+        .set_manual_write()
+        .load()
+        .unwrap();
+    let patch = serde_json::json!([
+        {"op": "test", "path": "/int", "value": 1},
+        {"op": "replace", "path": "/string", "value": "patched"},
+        {"op": "add", "path": "/vector/-", "value": 1.5}
+    ]);
+    table.patch("1", &patch).unwrap();
+    assert!(table.is_modified());
+    assert_eq!(table["1"].info.string, "patched");
+    assert_eq!(table["1"].info.vector, vec![1.5]);
+    match table.patch("1", &serde_json::json!([{"op": "remove", "path": "/int"}])) {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    assert_eq!(table["1"].info.int, 1);
+    match table.patch(
+        "1",
+        &serde_json::json!([{"op": "test", "path": "/int", "value": 2}]),
+    ) {
+        Err(TableError::PatchError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    match table.patch("100", &patch) {
+        Err(TableError::PopError(e)) => assert_eq!(e, "100"),
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn json_merge_patch() {
+    let mut table = Table::<ExampleStruct>::builder("tests/normal")
+        .set_manual_write()
+        .load()
+        .unwrap();
+    table
+        .merge_patch(
+            "2",
+            &serde_json::json!({"string": "merged", "tuple": [3, 0.5]}),
+        )
+        .unwrap();
+    assert!(table.is_modified());
+    assert_eq!(table["2"].info.string, "merged");
+    assert_eq!(table["2"].info.tuple, (3, 0.5));
+    assert_eq!(table["2"].info.int, 2);
+    match table.merge_patch("2", &serde_json::json!({"int": null})) {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    assert_eq!(table["2"].info.int, 2);
+    match table.merge_patch("100", &serde_json::json!({})) {
+        Err(TableError::PopError(e)) => assert_eq!(e, "100"),
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn schema_version() {
+    {
+        let mut table = Table::<SimplifiedStruct>::builder("tests/schema")
+            .set_schema_version("1")
+            .build()
+            .unwrap();
+        table.push("0", SimplifiedStruct::default()).unwrap();
+    }
+    let table = Table::<SimplifiedStruct>::builder("tests/schema")
+        .set_schema_version("1")
+        .set_read_non_json_is_error()
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 1);
+    match Table::<SimplifiedStruct>::builder("tests/schema")
+        .set_schema_version("2")
+        .load()
+    {
+        Err(TableError::SchemaError(expected, found)) => {
+            assert_eq!(expected, "2");
+            assert_eq!(found, "1");
+        }
+        _ => assert!(false),
+    };
+    assert!(Table::<SimplifiedStruct>::builder("tests/schema")
+        .load()
+        .is_ok());
+    std::fs::remove_dir_all("tests/schema").unwrap();
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn type_fingerprint() {
+    mod v1 {
+        #[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+        pub struct Item {
+            pub int: i32,
+        }
+    }
+    mod v2 {
+        #[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+        pub struct Item {
+            pub int: i32,
+            pub name: String,
+        }
+    }
+    mod moved {
+        #[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+        pub struct Item {
+            pub int: i32,
+        }
+    }
+    Table::<v1::Item>::builder("tests/schema_2")
+        .set_type_fingerprint()
+        .build()
+        .unwrap();
+    // the same fields in another module have the same schema
+    assert!(Table::<moved::Item>::builder("tests/schema_2")
+        .set_type_fingerprint()
+        .load()
+        .is_ok());
+    match Table::<v2::Item>::builder("tests/schema_2")
+        .set_type_fingerprint()
+        .load()
+    {
+        Err(TableError::SchemaError(_, _)) => assert!(true),
+        _ => assert!(false),
+    };
+    // tables without a recorded schema are taken as compatible
+    assert!(Table::<v2::Item>::builder("tests/")
+        .set_type_fingerprint()
+        .load()
+        .is_ok());
+    std::fs::remove_dir_all("tests/schema_2").unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionedStruct {
+    version: u64,
+    int: i32,
+    name: String,
+}
+
+fn versioned_migrations() -> Migrations {
+    Migrations::new("version")
+        .register(0, |mut v0| {
+            v0["int"] = v0["integer"].take();
+            v0.as_object_mut().unwrap().remove("integer");
+            v0
+        })
+        .register(1, |mut v1| {
+            v1["name"] = "unnamed".into();
+            v1
+        })
+}
+
+#[test]
+fn migrations() {
+    assert_eq!(versioned_migrations().current_version(), 2);
+    match Table::<VersionedStruct>::builder("tests/migrations").load() {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    let table = Table::<VersionedStruct>::builder("tests/migrations")
+        .set_migrations(versioned_migrations())
+        .load()
+        .unwrap();
+    assert!(!table.is_modified());
+    assert!(table.iter().all(|(_, element)| element.info.version == 2));
+    assert_eq!(table["0"].info.int, 3);
+    assert_eq!(table["0"].info.name, "unnamed");
+    assert_eq!(table["1"].info.name, "unnamed");
+    assert_eq!(table["2"].info.name, "five");
+    drop(table);
+    std::fs::create_dir("tests/migrations_2").unwrap();
+    std::fs::write("tests/migrations_2/0.json", r#"{"integer": 3}"#).unwrap();
+    let table = Table::<VersionedStruct>::builder("tests/migrations_2")
+        .set_migrations(versioned_migrations().rewrite_on_write_back())
+        .load()
+        .unwrap();
+    assert!(table.is_modified());
+    drop(table);
+    let table = Table::<VersionedStruct>::builder("tests/migrations_2")
+        .load()
+        .unwrap();
+    assert_eq!(table["0"].info.version, 2);
+    std::fs::remove_dir_all("tests/migrations_2").unwrap();
+}
+
+#[test]
+fn raw_tables() {
+    let table = RawTable::builder("tests/mixed").load().unwrap();
+    assert_eq!(table.len(), 2);
+    match table.try_into_typed::<ExampleStruct>() {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    let table = RawTable::builder("tests/normal")
+        .set_manual_write()
+        .load()
+        .unwrap();
+    assert_eq!(table["0"].info["some"], "extra fields");
+    let table = table.try_into_typed::<ExampleStruct>().unwrap();
+    assert_eq!(table.len(), 5);
+    assert!(table.has_mod_permissions());
+    assert_eq!(table["3"].info.int, 3);
+    let table = RawTable::from_typed(table).unwrap();
+    assert_eq!(table["3"].info["int"], 3);
+    assert!(table.get_pointer("0", "/some").unwrap().is_none());
+}
+
+#[test]
+fn map_into() {
+    let table = Table::<ExampleStruct>::builder("tests/normal")
+        .set_read_only()
+        .load()
+        .unwrap();
+    let mapped = table
+        .map_into("tests/map_into", |element| SimplifiedStruct {
+            int: element.int * 2,
+            float: element.float,
+        })
+        .unwrap();
+    assert_eq!(mapped.len(), 5);
+    assert!(!mapped.is_modified());
+    drop(mapped);
+    let mapped = Table::<SimplifiedStruct>::builder("tests/map_into")
+        .load()
+        .unwrap();
+    assert!(mapped
+        .iter()
+        .all(|(name, element)| { element.info.int == 2 * name.parse::<i32>().unwrap() }));
+    match table.map_into("tests/map_into", |element| element.clone()) {
+        Err(TableError::BuilderError(TableBuilderError::TableAlreadyExistsError)) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/map_into").unwrap();
+}
+
+#[test]
+fn keyed() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/keyed")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table
+        .push_keyed(SimplifiedStruct { int: 1, float: 1.0 })
+        .unwrap();
+    table
+        .append_keyed((2..5).map(|int| SimplifiedStruct { int, float: 0.0 }))
+        .unwrap();
+    assert_eq!(table.len(), 4);
+    assert_eq!(table["id_3"].info.int, 3);
+    match table.push_keyed(SimplifiedStruct::default()) {
+        Ok(()) => assert!(true),
+        _ => assert!(false),
+    };
+    match table.push_keyed(SimplifiedStruct::default()) {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/keyed").unwrap();
+}
+
+#[test]
+fn push_auto() {
+    {
+        let mut table = Table::<SimplifiedStruct>::builder("tests/push_auto")
+            .build()
+            .unwrap();
+        table.push("1", SimplifiedStruct::default()).unwrap();
+        assert_eq!(table.push_auto(SimplifiedStruct::default()).unwrap(), "0");
+        assert_eq!(table.push_auto(SimplifiedStruct::default()).unwrap(), "2");
+    }
+    let mut table = Table::<SimplifiedStruct>::builder("tests/push_auto")
+        .set_read_non_json_is_error()
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 3);
+    table.pop("2").unwrap();
+    assert_eq!(table.push_auto(SimplifiedStruct::default()).unwrap(), "3");
+    drop(table);
+    let mut table = Table::<SimplifiedStruct>::builder("tests/push_auto")
+        .set_read_only()
+        .load()
+        .unwrap();
+    match table.push_auto(SimplifiedStruct::default()) {
+        Err(TableError::NoWritePolicyError) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/push_auto").unwrap();
+}
+
+#[test]
+fn unique_constraints() {
+    let mut table = Table::<ExampleStruct>::builder("tests/normal")
+        .set_manual_write()
+        .unique("int", |element| element.int.to_string())
+        .load()
+        .unwrap();
+    match table.push("100", ExampleStruct::default()) {
+        Err(TableError::UniqueError(name, value)) => {
+            assert_eq!(name, "int");
+            assert_eq!(value, "0");
+        }
+        _ => assert!(false),
+    };
+    assert!(!std::path::Path::new("tests/normal/100.json").exists());
+    match table.unique("float", |element| element.float.to_string()) {
+        Err(TableError::UniqueError(name, _)) => assert_eq!(name, "float"),
+        _ => assert!(false),
+    };
+    table["1"].info.int = 0;
+    match table.write_back() {
+        Err(TableError::UniqueError(name, _)) => assert_eq!(name, "int"),
+        _ => assert!(false),
+    };
+    match Table::<ExampleStruct>::builder("tests/normal")
+        .unique("float", |element| element.float.to_string())
+        .load()
+    {
+        Err(TableError::UniqueError(_, value)) => assert_eq!(value, "0"),
+        _ => assert!(false),
+    };
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Order {
+    customer_id: String,
+    amount: u32,
+}
+
+#[test]
+fn table_group_references() {
+    let mut group = TableGroup::new();
+    let mut customers = Table::<SimplifiedStruct>::builder("tests/group_customers")
+        .build()
+        .unwrap();
+    customers.push("ana", SimplifiedStruct::default()).unwrap();
+    group.insert("customers", customers);
+    group.insert(
+        "orders",
+        Table::<Order>::builder("tests/group_orders")
+            .build()
+            .unwrap(),
+    );
+    group.references("orders", "customer_id", "customers", |order: &Order| {
+        order.customer_id.clone()
+    });
+    let order = Order {
+        customer_id: "ana".into(),
+        amount: 3,
+    };
+    group.push("orders", "0", order.clone()).unwrap();
+    match group.push(
+        "orders",
+        "1",
+        Order {
+            customer_id: "bob".into(),
+            amount: 1,
+        },
+    ) {
+        Err(TableError::ReferenceError(field, value)) => {
+            assert_eq!(field, "orders.customer_id");
+            assert_eq!(value, "bob");
+        }
+        _ => assert!(false),
+    };
+    match group.push("missing", "0", order.clone()) {
+        Err(TableError::GroupError(name)) => assert_eq!(name, "missing"),
+        _ => assert!(false),
+    };
+    assert!(group.get::<Order>("customers").is_none());
+    assert_eq!(group.get::<Order>("orders").unwrap().len(), 1);
+    assert!(group.check_references().is_empty());
+    group.write_back().unwrap();
+    group
+        .get_mut::<SimplifiedStruct>("customers")
+        .unwrap()
+        .pop("ana")
+        .unwrap();
+    let broken = group.check_references();
+    assert_eq!(broken.len(), 1);
+    assert_eq!(broken[0].key, "0");
+    assert_eq!(broken[0].target, "customers");
+    match group.write_back() {
+        Err(TableError::ReferenceError(_, value)) => assert_eq!(value, "ana"),
+        _ => assert!(false),
+    };
+    let orders = group.remove::<Order>("orders").unwrap();
+    assert_eq!(orders["0"].info.amount, 3);
+    assert_eq!(group.names().count(), 1);
+    drop(orders);
+    drop(group);
+    std::fs::remove_dir_all("tests/group_customers").unwrap();
+    std::fs::remove_dir_all("tests/group_orders").unwrap();
+}
+
+#[test]
+fn stored_metadata() {
+    {
+        let mut table = Table::<SimplifiedStruct>::builder("tests/stored_metadata")
+            .set_manual_write()
+            .set_read_non_json_is_error()
+            .build()
+            .unwrap();
+        table.push("0", SimplifiedStruct::default()).unwrap();
+        table.write_back().unwrap();
+    }
+    std::fs::write("tests/stored_metadata/notes.txt", "").unwrap();
+    match Table::<SimplifiedStruct>::load("tests/stored_metadata", None) {
+        Err(TableError::JsonError) => assert!(true),
+        _ => assert!(false),
+    };
+    match Table::<SimplifiedStruct>::builder("tests/stored_metadata")
+        .set_read_only()
+        .load()
+    {
+        Err(TableError::JsonError) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_file("tests/stored_metadata/notes.txt").unwrap();
+    let mut table = Table::<SimplifiedStruct>::load("tests/stored_metadata", None).unwrap();
+    assert_eq!(table.len(), 1);
+    table.push("1", SimplifiedStruct::default()).unwrap();
+    drop(table);
+    // manual write back was stored, so the file is empty
+    match Table::<SimplifiedStruct>::builder("tests/stored_metadata").load() {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/stored_metadata").unwrap();
+}
+
+#[test]
+fn builder_from_path_buf() {
+    let dir = std::path::PathBuf::from("tests").join("normal");
+    let table = Table::<ExampleStruct>::builder(&dir).load().unwrap();
+    assert_eq!(table.len(), 5);
+    let table = json_tables::TableBuilder::<ExampleStruct>::new(dir.as_path())
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 5);
+}
+
+#[test]
+fn builder_with_metadata() {
+    let metadata = TableMetadata {
+        rw_policy: RWPolicy::ReadOnly,
+        extension_policy: ExtensionPolicy::OnlyJsonFiles,
+        ..Default::default()
+    };
+    let builder = Table::<ExampleStruct>::builder("tests/mixed")
+        .set_manual_write()
+        .with_metadata(metadata);
+    assert_eq!(builder.metadata(), metadata);
+    assert_eq!(builder.dir(), std::path::Path::new("tests/mixed"));
+    match builder.load() {
+        Err(TableError::JsonError) => assert!(true),
+        _ => assert!(false),
+    };
+    let builder = Table::<ExampleStruct>::builder("tests/normal")
+        .with_metadata(metadata)
+        .set_ignore_de_errors();
+    assert_eq!(
+        builder.metadata().content_policy,
+        ContentPolicy::IgnoreSerdeErrors
+    );
+    let table = builder.load().unwrap();
+    assert_eq!(table.metadata().rw_policy, RWPolicy::ReadOnly);
+    assert!(!table.has_mod_permissions());
+    assert_eq!(table.dir(), std::path::Path::new("tests/normal"));
+}
+
+#[test]
+fn open_or_create() {
+    {
+        let mut table = Table::<SimplifiedStruct>::builder("tests/open_or_create")
+            .open_or_create()
+            .unwrap();
+        assert!(table.is_empty());
+        table.push("0", SimplifiedStruct::default()).unwrap();
+    }
+    let table = Table::<SimplifiedStruct>::builder("tests/open_or_create")
+        .open_or_create()
+        .unwrap();
+    assert_eq!(table.len(), 1);
+    match Table::<SimplifiedStruct>::builder("tests/open_or_create_2")
+        .set_read_only()
+        .open_or_create()
+    {
+        Err(TableError::BuilderError(TableBuilderError::CreateWithoutWriteError)) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/open_or_create").unwrap();
+}
+
+#[test]
+fn snapshot() {
+    let mut table = Table::<ExampleStruct>::builder("tests/normal")
+        .set_manual_write()
+        .load()
+        .unwrap();
+    let snapshot = table.snapshot();
+    table["1"].info.string = "changed".into();
+    let handle = std::thread::spawn(move || {
+        assert_eq!(snapshot.len(), 5);
+        assert_eq!(snapshot.dir(), std::path::Path::new("tests/normal"));
+        assert_eq!(snapshot["1"].string, "");
+        assert!(snapshot.get("100").is_none());
+        snapshot.iter().map(|(_, element)| element.int).sum::<i32>()
+    });
+    assert_eq!(handle.join().unwrap(), 10);
+    assert_eq!(table.snapshot()["1"].string, "changed");
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+struct ComparableStruct {
+    int: i32,
+    float: f64,
+}
+
+#[test]
+fn table_equality() {
+    let table = Table::<ComparableStruct>::builder("tests/simplified_1")
+        .set_read_only()
+        .load()
+        .unwrap();
+    let mut other = Table::<ComparableStruct>::builder("tests/simplified_1")
+        .set_manual_write()
+        .load()
+        .unwrap();
+    assert!(table == other);
+    assert!(table.content_eq_dir("tests/simplified_1").unwrap());
+    other["0"].info.float = 7.0;
+    assert!(table != other);
+    assert!(!other.content_eq_dir("tests/simplified_1").unwrap());
+    let mixed = Table::<ComparableStruct>::builder("tests/mixed")
+        .load()
+        .unwrap();
+    assert!(table != mixed);
+}
+
+#[test]
+fn shared_table() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/shared")
+        .build()
+        .unwrap();
+    table
+        .append(&["0", "1", "2", "3"], &[SimplifiedStruct::default(); 4])
+        .unwrap();
+    let shared = table.into_shared();
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    shared
+                        .update(&i.to_string(), |element| element.int += 1)
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+    assert_eq!(shared.read("2", |element| element.int), Some(100));
+    assert_eq!(shared.get("3").unwrap().int, 100);
+    shared.push("4", SimplifiedStruct::default()).unwrap();
+    match shared.push("4", SimplifiedStruct::default()) {
+        Err(TableError::PushError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    shared.pop("0").unwrap();
+    match shared.update("0", |element| element.int += 1) {
+        Err(TableError::PopError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    assert_eq!(shared.len(), 4);
+    shared.flush().unwrap();
+    let table = Table::<SimplifiedStruct>::builder("tests/shared")
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 4);
+    assert_eq!(table["1"].info.int, 100);
+    assert_eq!(table["4"].info.int, 0);
+    drop(shared);
+    std::fs::remove_dir_all("tests/shared").unwrap();
+}
+
+#[test]
+fn table_handle() {
+    let table = Table::<SimplifiedStruct>::builder("tests/handle")
+        .build()
+        .unwrap();
+    let (handle, worker) = table.spawn();
+    handle.push("0", SimplifiedStruct::default()).unwrap();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                for _ in 0..25 {
+                    handle.update("0", |element| element.int += 1).unwrap();
+                }
+            })
+        })
+        .collect();
+    threads.into_iter().for_each(|t| t.join().unwrap());
+    assert_eq!(handle.get("0").unwrap().unwrap().int, 100);
+    assert!(handle.get("1").unwrap().is_none());
+    match handle.update("1", |element| element.int += 1) {
+        Err(TableError::PopError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    assert_eq!(handle.len().unwrap(), 1);
+    assert!(handle.with(|table| table.is_modified()).unwrap());
+    drop(handle);
+    worker.join().unwrap();
+    let table = Table::<SimplifiedStruct>::builder("tests/handle")
+        .load()
+        .unwrap();
+    assert_eq!(table["0"].info.int, 100);
+    std::fs::remove_dir_all("tests/handle").unwrap();
+}
+
+#[test]
+fn watcher() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/watcher")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("0", SimplifiedStruct::default()).unwrap();
+    let mut watcher = table.watcher().unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    table.write_back().unwrap();
+    std::fs::write("tests/watcher/1.json", "{}").unwrap();
+    std::fs::write("tests/watcher/notes.txt", "").unwrap();
+    let mut events = watcher.poll().unwrap();
+    events.sort_by_key(|event| format!("{event:?}"));
+    assert_eq!(
+        events,
+        vec![
+            TableEvent::Created("1".into()),
+            TableEvent::Modified("0".into())
+        ]
+    );
+    std::fs::remove_file("tests/watcher/1.json").unwrap();
+    assert_eq!(
+        watcher.poll().unwrap(),
+        vec![TableEvent::Removed("1".into())]
+    );
+    std::fs::remove_dir_all("tests/watcher").unwrap();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn change_stream() {
+    use std::future::poll_fn;
+    use std::pin::pin;
+    let table = Table::<SimplifiedStruct>::builder("tests/change_stream")
+        .build()
+        .unwrap();
+    let mut changes = pin!(table.changes(std::time::Duration::from_millis(10)).unwrap());
+    std::fs::write("tests/change_stream/0.json", "{}").unwrap();
+    let next = poll_fn(|cx| futures_core::Stream::poll_next(changes.as_mut(), cx)).await;
+    assert_eq!(next, Some(TableEvent::Created("0".into())));
+    std::fs::remove_dir_all("tests/change_stream").unwrap();
+}
+
+/// Code that is generic over the storage takes tables on disk and in memory
+fn total<S: json_tables::Storage>(table: &Table<SimplifiedStruct, S>) -> i32 {
+    table.values().map(|info| info.int).sum()
+}
+
+#[test]
+fn in_memory_table() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    assert_eq!(table.dir(), std::path::Path::new(""));
+    assert_eq!(total(&table), 0);
+    assert_eq!(
+        total(&Table::<SimplifiedStruct>::builder("tests/").load().unwrap()),
+        0
+    );
+    table
+        .push_keyed(SimplifiedStruct { int: 3, float: 0.0 })
+        .unwrap();
+    assert_eq!(table.push_auto(SimplifiedStruct::default()).unwrap(), "0");
+    assert_eq!(table.push_auto(SimplifiedStruct::default()).unwrap(), "1");
+    match table.push("0", SimplifiedStruct::default()) {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    table.rename("1", "2").unwrap();
+    table["2"].info.int = 2;
+    table.soft_pop("0", None).unwrap();
+    table.write_back().unwrap();
+    table.store_metadata().unwrap();
+    assert_eq!(table.len(), 2);
+    assert_eq!(table["2"].info.int, 2);
+    assert!(!std::path::Path::new("2.json").exists());
+    assert!(!std::path::Path::new(".table.json").exists());
+    let shared = table.into_shared();
+    shared.push("4", SimplifiedStruct::default()).unwrap();
+    shared.pop("4").unwrap();
+    shared.flush().unwrap();
+    assert_eq!(shared.len(), 2);
+}
+
+#[test]
+fn custom_storage() {
+    let storage = MemoryStorage::with_items([
+        ("a.json", r#"{"int": 1, "float": 1.0}"#),
+        ("b.json", r#"{"int": 2, "float": 2.0}"#),
+        ("notes.txt", "not an entry"),
+    ]);
+    let mut table =
+        Table::<SimplifiedStruct, _>::with_storage(storage, TableMetadata::default()).unwrap();
+    assert_eq!(table.len(), 2);
+    table["a"].info.int = 10;
+    table.write_back().unwrap();
+    let copy = Table::<SimplifiedStruct, _>::with_storage(
+        table.storage().clone(),
+        TableMetadata::default(),
+    )
+    .unwrap();
+    assert_eq!(copy["a"].info.int, 10);
+    let metadata = TableMetadata {
+        extension_policy: ExtensionPolicy::OnlyJsonFiles,
+        ..Default::default()
+    };
+    match Table::<SimplifiedStruct, _>::with_storage(table.storage().clone(), metadata) {
+        Err(TableError::JsonError) => assert!(true),
+        _ => assert!(false),
+    };
+}
+
+#[cfg(feature = "object_store")]
+#[test]
+fn object_storage() {
+    use json_tables::{object_store::memory::InMemory, ObjectStorage};
+    let store = std::sync::Arc::new(InMemory::new());
+    let storage = ObjectStorage::new(store.clone(), "tables/simplified").unwrap();
+    let mut table =
+        Table::<SimplifiedStruct, _>::with_storage(storage, TableMetadata::default()).unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table["a"].info.int = 4;
+    table.rename("b", "c").unwrap();
+    match table.push("a", SimplifiedStruct::default()) {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    drop(table);
+    let storage = ObjectStorage::new(store, "tables/simplified").unwrap();
+    let table =
+        Table::<SimplifiedStruct, _>::with_storage(storage, TableMetadata::default()).unwrap();
+    assert_eq!(table.len(), 2);
+    assert_eq!(table["a"].info.int, 4);
+    assert!(table.get_element("b").is_none());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_loading() {
+    let mapped = Table::<ExampleStruct>::builder("tests/normal")
+        .set_read_only()
+        .set_mmap()
+        .load()
+        .unwrap();
+    let read = Table::<ExampleStruct>::builder("tests/normal")
+        .set_read_only()
+        .load()
+        .unwrap();
+    assert_eq!(mapped.len(), read.len());
+    for (name, element) in read.iter() {
+        assert_eq!(mapped[name].info.int, element.info.int);
+    }
+}
+
+#[test]
+fn lazy_table() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/lazy")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table
+        .append_keyed((0..10).map(|int| SimplifiedStruct { int, float: 0.0 }))
+        .unwrap();
+    table.write_back().unwrap();
+    let size = std::fs::metadata("tests/lazy/id_0.json").unwrap().len() as usize;
+    let mut lazy = Table::<SimplifiedStruct>::builder("tests/lazy")
+        .set_manual_write()
+        .load_lazy(3 * size)
+        .unwrap();
+    assert_eq!(lazy.len(), 10);
+    assert_eq!(lazy.cached_len(), 0);
+    for int in 0..10 {
+        let key = format!("id_{int}");
+        assert_eq!(lazy.get(&key).unwrap().unwrap().int, int);
+        assert!(lazy.cached_size() <= lazy.budget());
+    }
+    assert_eq!(lazy.cached_len(), 3);
+    lazy.get_mut("id_0").unwrap().unwrap().float = 1.0;
+    for int in 1..10 {
+        lazy.get(&format!("id_{int}")).unwrap();
+    }
+    assert!(lazy.get("id_10").unwrap().is_none());
+    assert_eq!(lazy.get("id_0").unwrap().unwrap().float, 1.0);
+    lazy.pop("id_1").unwrap();
+    match lazy.pop("id_1") {
+        Err(TableError::PopError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    lazy.write_back().unwrap();
+    drop(lazy);
+    let table = Table::<SimplifiedStruct>::load("tests/lazy", None).unwrap();
+    assert_eq!(table.len(), 9);
+    assert_eq!(table["id_0"].info.float, 1.0);
+    std::fs::remove_dir_all("tests/lazy").unwrap();
+}
+
+#[test]
+fn stream_table() {
+    let mut streamed = Table::<SimplifiedStruct>::stream("tests/mixed")
+        .unwrap()
+        .map(|element| element.unwrap())
+        .collect::<Vec<_>>();
+    streamed.sort_by(|a, b| a.0.cmp(&b.0));
+    let table = Table::<SimplifiedStruct>::builder("tests/mixed")
+        .set_read_only()
+        .load()
+        .unwrap();
+    assert_eq!(streamed.len(), table.len());
+    for (name, info) in &streamed {
+        assert_eq!(info.int, table[name].info.int);
+    }
+    match Table::<u32>::stream("tests/mixed").unwrap().next() {
+        Some(Err(TableError::SerdeError(_))) => assert!(true),
+        _ => assert!(false),
+    };
+    assert_eq!(
+        Table::<u32>::stream("tests/mixed").unwrap().size_hint(),
+        (0, Some(2))
+    );
+}
+
+fn page_keys<'a, V: 'a>(page: impl Iterator<Item = (&'a std::sync::Arc<str>, V)>) -> Vec<String> {
+    page.map(|(key, _)| key.to_string()).collect()
+}
+
+#[test]
+fn pagination() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append_keyed((0..7).map(|int| SimplifiedStruct { int, float: 0.0 }))
+        .unwrap();
+    assert_eq!(page_keys(table.page(0, 3)), ["id_0", "id_1", "id_2"]);
+    assert_eq!(page_keys(table.page(6, 3)), ["id_6"]);
+    assert!(table.page(7, 3).next().is_none());
+    assert_eq!(page_keys(table.page_after("id_2", 2)), ["id_3", "id_4"]);
+    table.pop("id_3").unwrap();
+    assert_eq!(page_keys(table.page_after("id_2", 2)), ["id_4", "id_5"]);
+    assert_eq!(page_keys(table.page_after("", 1)), ["id_0"]);
+}
+
+#[test]
+fn list_keys() {
+    assert_eq!(Table::<u32>::list_keys("tests/mixed").unwrap(), ["0", "1"]);
+    match Table::<u32>::list_keys("tests/nonexistent") {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn table_exists() {
+    assert!(Table::<u32>::exists("tests/mixed"));
+    assert!(!Table::<u32>::is_table("tests/mixed"));
+    assert!(!Table::<u32>::exists("tests/mixed/0.json"));
+    assert!(!Table::<u32>::exists("tests/exists"));
+    let table = Table::<u32>::builder("tests/exists").build().unwrap();
+    assert!(Table::<u32>::exists("tests/exists"));
+    assert!(Table::<u32>::is_table("tests/exists"));
+    drop(table);
+    std::fs::remove_dir_all("tests/exists").unwrap();
+}
+
+#[test]
+fn delete_table() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/delete_table")
+        .set_read_non_json_is_error()
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b"], &[SimplifiedStruct::default(); 2])
+        .unwrap();
+    table.soft_pop("b", None).unwrap();
+    drop(table);
+    let removed = Table::<SimplifiedStruct>::delete("tests/delete_table", true).unwrap();
+    assert_eq!(removed.len(), 4);
+    assert!(Table::<SimplifiedStruct>::exists("tests/delete_table"));
+    std::fs::write("tests/delete_table/notes.txt", "").unwrap();
+    match Table::<SimplifiedStruct>::delete("tests/delete_table", false) {
+        Err(TableError::JsonError) => assert!(true),
+        _ => assert!(false),
+    };
+    assert!(std::path::Path::new("tests/delete_table/a.json").exists());
+    std::fs::remove_file("tests/delete_table/notes.txt").unwrap();
+    let removed = Table::<SimplifiedStruct>::delete("tests/delete_table", false).unwrap();
+    assert_eq!(
+        removed.last().unwrap(),
+        std::path::Path::new("tests/delete_table")
+    );
+    assert!(!std::path::Path::new("tests/delete_table").exists());
+}
+
+#[test]
+fn move_table() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/move_from")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.move_to("tests/move_to").unwrap();
+    assert!(!Table::<SimplifiedStruct>::exists("tests/move_from"));
+    assert_eq!(table.dir(), std::path::Path::new("tests/move_to"));
+    table["a"].info.int = 3;
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    match table.move_to("tests/mixed") {
+        Err(TableError::BuilderError(TableBuilderError::TableAlreadyExistsError)) => assert!(true),
+        _ => assert!(false),
+    };
+    let table = Table::<SimplifiedStruct>::load("tests/move_to", None).unwrap();
+    assert_eq!(table.len(), 2);
+    assert_eq!(table["a"].info.int, 3);
+    std::fs::remove_dir_all("tests/move_to").unwrap();
+}
+
+#[test]
+fn vacuum() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append(&["a", "b", "c"], &[SimplifiedStruct::default(); 3])
+        .unwrap();
+    table.soft_pop("b", None).unwrap();
+    let mut junk = Table::<SimplifiedStruct, MemoryStorage>::with_storage(
+        MemoryStorage::with_items([
+            ("a.json", ""),
+            ("b.json", "{\"int\": 0, \"float\": 0.0}"),
+            ("c.json.tmp", ""),
+            ("d.json~", ""),
+            ("e.json_soft_delete", ""),
+            ("notes.txt", ""),
+        ]),
+        TableMetadata {
+            content_policy: ContentPolicy::IgnoreSerdeErrors,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let policy = json_tables::VacuumPolicy {
+        soft_deleted: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        junk.vacuum(policy).unwrap(),
+        ["a.json", "c.json.tmp", "d.json~"]
+    );
+    assert_eq!(
+        junk.vacuum(Default::default()).unwrap(),
+        ["e.json_soft_delete"]
+    );
+    assert_eq!(
+        table.vacuum(Default::default()).unwrap(),
+        ["b.json_soft_delete"]
+    );
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn foreign_files() {
+    let table = Table::<SimplifiedStruct>::builder("tests/foreign_files")
+        .build()
+        .unwrap();
+    assert!(table.foreign_files().unwrap().is_empty());
+    std::fs::write("tests/foreign_files/a.json", "{\"int\": 0, \"float\": 0.0}").unwrap();
+    std::fs::write("tests/foreign_files/notes.txt", "").unwrap();
+    std::fs::write("tests/foreign_files/b.JSON", "").unwrap();
+    std::fs::create_dir("tests/foreign_files/nested").unwrap();
+    assert_eq!(
+        table.foreign_files().unwrap(),
+        [
+            std::path::Path::new("tests/foreign_files/b.JSON"),
+            std::path::Path::new("tests/foreign_files/nested"),
+            std::path::Path::new("tests/foreign_files/notes.txt"),
+        ]
+    );
+    std::fs::remove_dir_all("tests/foreign_files").unwrap();
+}
+
+#[test]
+fn compact_table() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/compact")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    assert!(std::fs::read_to_string("tests/compact/a.json")
+        .unwrap()
+        .contains('\n'));
+    drop(table);
+    let metadata = TableMetadata {
+        format_policy: FormatPolicy::Compact,
+        ..Default::default()
+    };
+    let mut table = Table::<SimplifiedStruct>::load("tests/compact", Some(metadata)).unwrap();
+    table.compact(true).unwrap();
+    assert_eq!(
+        std::fs::read_to_string("tests/compact/a.json").unwrap(),
+        "{\"int\":0,\"float\":0.0}"
+    );
+    std::fs::remove_dir_all("tests/compact").unwrap();
+}
+
+#[test]
+fn find_duplicates() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    let other = SimplifiedStruct { int: 1, float: 2.0 };
+    table
+        .append(
+            &["e", "d", "c", "b", "a"],
+            &[
+                other,
+                SimplifiedStruct::default(),
+                other,
+                SimplifiedStruct { int: 3, float: 0.0 },
+                SimplifiedStruct::default(),
+            ],
+        )
+        .unwrap();
+    assert_eq!(
+        table.find_duplicates().unwrap(),
+        [vec!["a", "d"], vec!["c", "e"]]
+    );
+    table.pop("a").unwrap();
+    assert_eq!(table.find_duplicates().unwrap(), [vec!["c", "e"]]);
+}
+
+#[test]
+fn group_by() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append(
+            &["a", "b", "c", "d"],
+            &[
+                SimplifiedStruct { int: 1, float: 0.0 },
+                SimplifiedStruct { int: 2, float: 1.0 },
+                SimplifiedStruct { int: 3, float: 0.0 },
+                SimplifiedStruct { int: 4, float: 1.0 },
+            ],
+        )
+        .unwrap();
+    let groups = table.group_by(|element| element.int % 2 == 0);
+    assert_eq!(groups.len(), 2);
+    let ints: Vec<_> = groups[&true]
+        .iter()
+        .map(|element| element.info.int)
+        .collect();
+    assert_eq!(ints, [2, 4]);
+    let groups = table.into_group_by(|element| element.float as i32);
+    let ints: Vec<_> = groups[&0].iter().map(|element| element.info.int).collect();
+    assert_eq!(ints, [1, 3]);
+    assert_eq!(groups[&1].len(), 2);
+}
+
+#[test]
+fn find_any_all() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    assert!(table.find(|_, _| true).is_none());
+    assert!(!table.any(|_, _| true));
+    assert!(table.all(|_, _| false));
+    table
+        .append(
+            &["a", "b", "c"],
+            &[
+                SimplifiedStruct { int: 1, float: 0.0 },
+                SimplifiedStruct { int: 2, float: 0.0 },
+                SimplifiedStruct { int: 3, float: 0.0 },
+            ],
+        )
+        .unwrap();
+    let (key, element) = table.find(|_, element| element.int > 1).unwrap();
+    assert_eq!(key, "b");
+    assert_eq!(element.info.int, 2);
+    assert!(table.find(|key, _| key == "d").is_none());
+    assert!(table.any(|key, element| key == "c" && element.int == 3));
+    assert!(!table.any(|_, element| element.int > 3));
+    assert!(table.all(|_, element| element.float == 0.0));
+    assert!(!table.all(|key, _| key != "a"));
+}
+
+#[test]
+fn get_many_mut() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append(
+            &["a", "b", "c"],
+            &[
+                SimplifiedStruct { int: 5, float: 0.0 },
+                SimplifiedStruct { int: 0, float: 0.0 },
+                SimplifiedStruct { int: 1, float: 0.0 },
+            ],
+        )
+        .unwrap();
+    let [c, a] = table.get_many_mut(["c", "a"]).unwrap();
+    a.info.int -= 2;
+    c.info.int += 2;
+    assert_eq!(table["a"].info.int, 3);
+    assert_eq!(table["c"].info.int, 3);
+    assert!(table.is_modified());
+    assert!(table.get_many_mut(["a", "a"]).is_none());
+    assert!(table.get_many_mut(["a", "d"]).is_none());
+}
+
+#[test]
+fn iter_mut() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append(&["1", "2", "3"], &[SimplifiedStruct::default(); 3])
+        .unwrap();
+    for (key, element) in table.iter_mut() {
+        element.info.int = key.parse().unwrap();
+    }
+    assert!(table.is_modified());
+    let ints: Vec<_> = table.iter().map(|(_, element)| element.info.int).collect();
+    assert_eq!(ints, [1, 2, 3]);
+}
+
+#[test]
+fn value_iterators() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append(&["a", "b"], &[SimplifiedStruct::default(); 2])
+        .unwrap();
+    for (int, info) in table.values_mut().enumerate() {
+        info.int = int as i32 + 1;
+    }
+    let ints: Vec<_> = table.values().map(|info| info.int).collect();
+    assert_eq!(ints, [1, 2]);
+}
+
+#[test]
+fn durability_policy() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/durability")
+        .set_durability(DurabilityPolicy::SyncFileAndDir)
+        .set_manual_write()
+        .build()
+        .unwrap();
+    assert_eq!(
+        table.metadata().durability_policy,
+        DurabilityPolicy::SyncFileAndDir
+    );
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table["a"].info.int = 1;
+    table.write_back().unwrap();
+    drop(table);
+    let metadata = TableMetadata {
+        durability_policy: DurabilityPolicy::Flush,
+        ..Default::default()
+    };
+    let mut table = Table::<SimplifiedStruct>::load("tests/durability", Some(metadata)).unwrap();
+    assert_eq!(table["a"].info.int, 1);
+    table["a"].info.int = 2;
+    table.write_back().unwrap();
+    std::fs::remove_dir_all("tests/durability").unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn table_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = |path: &str| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let mut table = Table::<SimplifiedStruct>::builder("tests/permissions")
+        .set_permissions(0o600, 0o700)
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b"], &[SimplifiedStruct::default(); 2])
+        .unwrap();
+    table.soft_pop("b", None).unwrap();
+    assert_eq!(mode("tests/permissions"), 0o700);
+    assert_eq!(mode("tests/permissions/.table.json"), 0o600);
+    assert_eq!(mode("tests/permissions/a.json"), 0o600);
+    assert_eq!(mode("tests/permissions/b.json_soft_delete"), 0o600);
+    let shared = table.into_shared();
+    shared.push("c", SimplifiedStruct::default()).unwrap();
+    assert_eq!(mode("tests/permissions/c.json"), 0o600);
+    drop(shared);
+    std::fs::remove_dir_all("tests/permissions").unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_policy() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/symlinks")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b"], &[SimplifiedStruct::default(); 2])
+        .unwrap();
+    table.write_back().unwrap();
+    std::os::unix::fs::symlink("a.json", "tests/symlinks/c.json").unwrap();
+    std::os::unix::fs::symlink("b.json", "tests/symlinks/d.json").unwrap();
+    drop(table);
+    let table = Table::<SimplifiedStruct>::builder("tests/symlinks")
+        .set_symlink_policy(SymlinkPolicy::Ignore)
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 2);
+    drop(table);
+    match Table::<SimplifiedStruct>::builder("tests/symlinks")
+        .set_symlink_policy(SymlinkPolicy::Error)
+        .load()
+    {
+        Err(TableError::SymlinkError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    let mut table = Table::<SimplifiedStruct>::load("tests/symlinks", None).unwrap();
+    assert_eq!(table.len(), 4);
+    table.pop("c").unwrap();
+    assert!(std::path::Path::new("tests/symlinks/a.json").exists());
+    drop(table);
+    let mut table = Table::<SimplifiedStruct>::builder("tests/symlinks")
+        .set_symlink_policy(SymlinkPolicy::FollowAndPopTarget)
+        .load()
+        .unwrap();
+    table.pop("d").unwrap();
+    assert!(!std::path::Path::new("tests/symlinks/b.json").exists());
+    drop(table);
+    std::fs::remove_dir_all("tests/symlinks").unwrap();
+}
+
+#[test]
+fn hidden_file_policy() {
+    let storage = MemoryStorage::with_items([
+        ("a.json", "{\"int\": 0, \"float\": 0.0}"),
+        (".#a.json", "{\"int\": 1, \"float\": 0.0}"),
+        ("._a.json", "{\"int\": 2, \"float\": 0.0}"),
+        (".goutputstream-XYZ", ""),
+    ]);
+    let metadata = TableMetadata {
+        extension_policy: ExtensionPolicy::OnlyJsonFiles,
+        ..Default::default()
+    };
+    match Table::<SimplifiedStruct, _>::with_storage(storage.clone(), metadata) {
+        Err(TableError::JsonError) => assert!(true),
+        _ => assert!(false),
+    };
+    let metadata = TableMetadata {
+        hidden_file_policy: HiddenFilePolicy::Skip,
+        ..metadata
+    };
+    let table = Table::<SimplifiedStruct, _>::with_storage(storage, metadata).unwrap();
+    assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn filter_keys() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/filter_keys")
+        .build()
+        .unwrap();
+    table
+        .append(
+            &["2023-12", "2024-01", "2024-02", "2024-10", "notes"],
+            &[SimplifiedStruct::default(); 5],
+        )
+        .unwrap();
+    drop(table);
+    let keys = |pattern: &str| {
+        let table = Table::<SimplifiedStruct>::builder("tests/filter_keys")
+            .filter_keys(pattern)
+            .load()
+            .unwrap();
+        table
+            .get_table_keys()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(keys("2024-*"), ["2024-01", "2024-02", "2024-10"]);
+    assert_eq!(keys("2024-0?"), ["2024-01", "2024-02"]);
+    assert_eq!(keys("*-1*"), ["2023-12", "2024-10"]);
+    assert_eq!(keys("notes"), ["notes"]);
+    assert!(keys("2025*").is_empty());
+    assert_eq!(keys("*").len(), 5);
+    std::fs::remove_dir_all("tests/filter_keys").unwrap();
+}
+
+#[test]
+fn only_keys() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/only_keys")
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b", "c", "d"], &[SimplifiedStruct::default(); 4])
+        .unwrap();
+    drop(table);
+    let table = Table::<SimplifiedStruct>::builder("tests/only_keys")
+        .only_keys(&["c", "a"])
+        .load()
+        .unwrap();
+    let keys: Vec<_> = table.get_table_keys().map(|key| &**key).collect();
+    assert_eq!(keys, ["a", "c"]);
+    drop(table);
+    match Table::<SimplifiedStruct>::builder("tests/only_keys")
+        .only_keys(&["a", "e"])
+        .load()
+    {
+        Err(TableError::MissingKeyError(key)) => assert_eq!(key, "e"),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/only_keys").unwrap();
+}
+
+#[test]
+fn max_entries() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/max_entries")
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b", "c"], &[SimplifiedStruct::default(); 3])
+        .unwrap();
+    drop(table);
+    let table = Table::<SimplifiedStruct>::builder("tests/max_entries")
+        .set_max_entries(3)
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 3);
+    drop(table);
+    match Table::<SimplifiedStruct>::builder("tests/max_entries")
+        .set_max_entries(2)
+        .load()
+    {
+        Err(TableError::TooManyEntriesError(2)) => assert!(true),
+        _ => assert!(false),
+    };
+    std::fs::remove_dir_all("tests/max_entries").unwrap();
+}
+
+#[test]
+fn size_policy() {
+    let storage = MemoryStorage::with_items([
+        ("a.json", "{\"int\": 0, \"float\": 0.0}".to_string()),
+        (
+            "b.json",
+            format!("{{\"int\": 0, \"float\": 0.0{}}}", " ".repeat(100)),
+        ),
+    ]);
+    let metadata = TableMetadata {
+        size_policy: SizePolicy::Skip(50),
+        ..Default::default()
+    };
+    let table = Table::<SimplifiedStruct, _>::with_storage(storage.clone(), metadata).unwrap();
+    assert!(table.get_element("a").is_some());
+    assert!(table.get_element("b").is_none());
+    let metadata = TableMetadata {
+        size_policy: SizePolicy::Error(50),
+        ..Default::default()
+    };
+    match Table::<SimplifiedStruct, _>::with_storage(storage.clone(), metadata) {
+        Err(TableError::SizeError(key)) => assert_eq!(key, "b"),
+        _ => assert!(false),
+    };
+    let table = Table::<SimplifiedStruct, _>::with_storage(storage, Default::default()).unwrap();
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn load_progress() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/progress")
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b", "c"], &[SimplifiedStruct::default(); 3])
+        .unwrap();
+    drop(table);
+    let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = std::sync::Arc::clone(&calls);
+    Table::<SimplifiedStruct>::builder("tests/progress")
+        .on_progress(move |done, total| recorded.lock().unwrap().push((done, total)))
+        .load()
+        .unwrap();
+    assert_eq!(*calls.lock().unwrap(), [(0, 3), (1, 3), (2, 3), (3, 3)]);
+    std::fs::remove_dir_all("tests/progress").unwrap();
+}
+
+#[test]
+fn cancellation() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    let mut table = Table::<SimplifiedStruct>::builder("tests/cancel")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table
+        .append(&["a", "b", "c"], &[SimplifiedStruct::default(); 3])
+        .unwrap();
+    let cancel = std::sync::Arc::new(AtomicBool::new(true));
+    match table.write_back_cancellable(&cancel) {
+        Err(TableError::CancelledError) => assert!(true),
+        _ => assert!(false),
+    };
+    assert!(table.is_modified());
+    cancel.store(false, Ordering::Relaxed);
+    table.write_back_cancellable(&cancel).unwrap();
+    assert!(!table.is_modified());
+    drop(table);
+    cancel.store(true, Ordering::Relaxed);
+    match Table::<SimplifiedStruct>::builder("tests/cancel")
+        .set_cancel_flag(std::sync::Arc::clone(&cancel))
+        .load()
+    {
+        Err(TableError::CancelledError) => assert!(true),
+        _ => assert!(false),
+    };
+    cancel.store(false, Ordering::Relaxed);
+    let table = Table::<SimplifiedStruct>::builder("tests/cancel")
+        .set_cancel_flag(cancel)
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 3);
+    std::fs::remove_dir_all("tests/cancel").unwrap();
+}
+
+#[test]
+fn table_metrics() {
+    let storage = MemoryStorage::with_items([
+        ("a.json", "{\"int\": 0, \"float\": 0.0}"),
+        ("b.json", "not json"),
+    ]);
+    let metadata = TableMetadata {
+        content_policy: ContentPolicy::IgnoreSerdeErrors,
+        rw_policy: RWPolicy::Write(json_tables::WriteType::Manual),
+        ..Default::default()
+    };
+    let mut table = Table::<SimplifiedStruct, _>::with_storage(storage, metadata).unwrap();
+    let metrics = table.metrics();
+    assert_eq!(metrics.files_loaded, 2);
+    assert_eq!(metrics.parse_failures, 1);
+    assert_eq!(metrics.write_backs, 0);
+    assert!(metrics.last_flush.is_none());
+    table.write_back().unwrap();
+    assert_eq!(table.metrics().write_backs, 1);
+    assert_eq!(table.metrics().bytes_written, 0);
+    table["a"].info.int = 1;
+    table.write_back().unwrap();
+    let metrics = table.metrics();
+    assert_eq!(metrics.write_backs, 2);
+    assert_eq!(
+        metrics.bytes_written,
+        json_tables::Storage::read(table.storage(), "a.json")
+            .unwrap()
+            .len() as u64
+    );
+    assert!(metrics.last_flush.is_some());
+}
+
+#[test]
+fn audit_log() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/audit")
+        .set_read_non_json_is_error()
+        .set_audit_log()
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table.rename("b", "c").unwrap();
+    table.pop("a").unwrap();
+    table.write_back().unwrap();
+    drop(table);
+    let table = Table::<SimplifiedStruct>::load("tests/audit", None).unwrap();
+    let log = table.audit_log().unwrap();
+    let ops: Vec<_> = log
+        .iter()
+        .map(|line| line["op"].as_str().unwrap())
+        .collect();
+    assert_eq!(ops, ["push", "push", "rename", "pop", "write_back"]);
+    assert_eq!(log[2]["key"], "b");
+    assert_eq!(log[2]["new_key"], "c");
+    assert_eq!(log[4]["entries"], 1);
+    assert!(log.iter().all(|line| line["timestamp"].is_u64()));
+    std::fs::remove_dir_all("tests/audit").unwrap();
+}
+
+#[test]
+fn version_history() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/history")
+        .set_read_non_json_is_error()
+        .set_history(2)
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    assert!(table.history("a").unwrap().is_empty());
+    for int in 1..4 {
+        table["a"].info.int = int;
+        table.write_back().unwrap();
+    }
+    // unchanged entries don't make new versions
+    table.get_mut_element("a").unwrap();
+    table.write_back().unwrap();
+    let history: Vec<_> = table
+        .history("a")
+        .unwrap()
+        .into_iter()
+        .map(|(version, info)| (version, info.int))
+        .collect();
+    assert_eq!(history, [(2, 1), (3, 2)]);
+    match table.restore_version("a", 1) {
+        Err(TableError::VersionError(key, 1)) => assert_eq!(key, "a"),
+        _ => assert!(false),
+    };
+    table.restore_version("a", 2).unwrap();
+    assert_eq!(table["a"].info.int, 1);
+    table.write_back().unwrap();
+    drop(table);
+    let table = Table::<SimplifiedStruct>::load("tests/history", None).unwrap();
+    assert_eq!(table.len(), 1);
+    assert_eq!(table["a"].info.int, 1);
+    assert_eq!(table.history("a").unwrap().last().unwrap().1.int, 3);
+    std::fs::remove_dir_all("tests/history").unwrap();
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn git_commit() {
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg("tests/git_table")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let mut table = Table::<SimplifiedStruct>::builder("tests/git_table")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    git(&["init", "-q"]);
+    git(&["config", "user.name", "tests"]);
+    git(&["config", "user.email", "tests@example.com"]);
+    table.commit_on_write_back("Update {entries} entries");
+    table
+        .append(&["a", "b"], &[SimplifiedStruct::default(); 2])
+        .unwrap();
+    table.write_back().unwrap();
+    table["a"].info.int = 1;
+    table.write_back().unwrap();
+    // nothing changed, so there's nothing to commit
+    table.get_mut_element("a").unwrap();
+    table.write_back().unwrap();
+    let log = git(&["log", "--format=%s"]);
+    assert_eq!(log, "Update 1 entries\nUpdate 2 entries\n");
+    drop(table);
+    std::fs::remove_dir_all("tests/git_table").unwrap();
+}
+
+#[test]
+fn stamped_elements() {
+    let mut table = Table::<Stamped<SimplifiedStruct>, MemoryStorage>::in_memory();
+    table.manage_timestamps();
+    table
+        .push("a", Stamped::new(SimplifiedStruct::default()))
+        .unwrap();
+    let created_at = table["a"].info.created_at;
+    assert!(created_at > 0);
+    assert_eq!(table["a"].info.updated_at, created_at);
+    table.write_back().unwrap();
+    let updated_at = table["a"].info.updated_at;
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    table.get_mut_element("a").unwrap();
+    table.write_back().unwrap();
+    assert_eq!(table["a"].info.updated_at, updated_at);
+    table["a"].info.info.int = 1;
+    table.write_back().unwrap();
+    assert_eq!(table["a"].info.created_at, created_at);
+    assert!(table["a"].info.updated_at > updated_at);
+    let json: json_tables::Value =
+        serde_json::from_slice(&json_tables::Storage::read(table.storage(), "a.json").unwrap())
+            .unwrap();
+    assert_eq!(json["int"], 1);
+    assert_eq!(json["updated_at"], table["a"].info.updated_at);
+}
+
+#[test]
+fn expiry() {
+    use std::time::{Duration, UNIX_EPOCH};
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    assert!(table.expire_now().unwrap().is_empty());
+    // the int is the expiry time in seconds since the epoch
+    table.set_expiry(Expiry::at(|info: &SimplifiedStruct| {
+        UNIX_EPOCH + Duration::from_secs(info.int as u64)
+    }));
+    let future = i32::MAX;
+    for (key, int) in [("stale", 1), ("fresh", future)] {
+        let info = SimplifiedStruct {
+            int,
+            ..Default::default()
+        };
+        table.push(key, info).unwrap();
+    }
+    assert_eq!(table.expire_now().unwrap(), ["stale"]);
+    assert!(table.get_element("stale").is_none());
+    assert!(table.get_element("fresh").is_some());
+
+    let mut table = Table::<SimplifiedStruct>::builder("tests/expiry")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    let table = Table::<SimplifiedStruct>::builder("tests/expiry")
+        .set_manual_write()
+        .expiry(Expiry::after(Duration::from_secs(3600)).on_load())
+        .load()
+        .unwrap();
+    assert!(table.get_element("a").is_some());
+    let table = Table::<SimplifiedStruct>::builder("tests/expiry")
+        .set_manual_write()
+        .expiry(Expiry::after(Duration::ZERO).soft().on_load())
+        .load()
+        .unwrap();
+    assert!(table.get_element("a").is_none());
+    assert!(std::fs::metadata("tests/expiry/a.json_soft_delete").is_ok());
+    std::fs::remove_dir_all("tests/expiry").unwrap();
+}
+
+#[test]
+fn unchanged_write_back() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    let written = table.metrics().bytes_written;
+    assert!(written > 0);
+    table.get_mut_element("a").unwrap();
+    table.write_back().unwrap();
+    assert_eq!(table.metrics().bytes_written, written);
+    table["a"].info.int = 1;
+    table.write_back().unwrap();
+    assert_eq!(table.metrics().bytes_written, written + written / 2);
+    let json: json_tables::Value =
+        serde_json::from_slice(&json_tables::Storage::read(table.storage(), "a.json").unwrap())
+            .unwrap();
+    assert_eq!(json["int"], 1);
+}
+
+#[test]
+fn optimistic_concurrency() {
+    let mut first = Table::<SimplifiedStruct>::builder("tests/concurrency")
+        .set_optimistic_concurrency()
+        .set_manual_write()
+        .build()
+        .unwrap();
+    assert_eq!(
+        first.metadata().concurrency_policy,
+        ConcurrencyPolicy::Optimistic
+    );
+    first.push("a", SimplifiedStruct::default()).unwrap();
+    assert_eq!(first.version("a"), Some(0));
+    first.write_back().unwrap();
+    assert_eq!(first.version("a"), Some(1));
+    let mut second = Table::<SimplifiedStruct>::builder("tests/concurrency")
+        .set_manual_write()
+        .load()
+        .unwrap();
+    assert_eq!(second.version("a"), Some(1));
+    let info = SimplifiedStruct {
+        int: 2,
+        ..Default::default()
+    };
+    second.update_if_version("a", 1, info).unwrap();
+    assert_eq!(second.version("a"), Some(2));
+    match first.update_if_version("a", 1, SimplifiedStruct::default()) {
+        Err(TableError::ConflictError(key, 2)) => assert_eq!(key, "a"),
+        _ => assert!(false),
+    };
+    assert_eq!(first["a"].info.int, 0);
+    let first = Table::<SimplifiedStruct>::builder("tests/concurrency")
+        .load()
+        .unwrap();
+    assert_eq!(first["a"].info.int, 2);
+    assert_eq!(first.version("a"), Some(2));
+    drop(first);
+    // a lock left behind by a process that died, and one that is held now
+    std::fs::write("tests/concurrency/.table_lock.a", "1 0").unwrap();
+    match second.update_if_version("a", 2, SimplifiedStruct::default()) {
+        Err(TableError::ConflictError(key, 2)) => assert_eq!(key, "a"),
+        _ => assert!(false),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    std::fs::write("tests/concurrency/.table_lock.c", format!("1 {now}")).unwrap();
+    let minute = std::time::Duration::from_secs(60);
+    assert_eq!(second.clear_stale_locks(minute).unwrap(), 1);
+    second
+        .update_if_version("a", 2, SimplifiedStruct::default())
+        .unwrap();
+    std::fs::remove_file("tests/concurrency/.table_lock.c").unwrap();
+    second.rename("a", "b").unwrap();
+    second.pop("b").unwrap();
+    assert!(std::fs::read_dir("tests/concurrency")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .all(|name| !name.to_string_lossy().starts_with(".table_version")));
+    std::fs::remove_dir_all("tests/concurrency").unwrap();
+}
+
+#[test]
+fn concurrent_updates() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/concurrent_updates")
+        .set_optimistic_concurrency()
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    drop(table);
+    let load = || {
+        Table::<SimplifiedStruct>::builder("tests/concurrent_updates")
+            .set_manual_write()
+            .load()
+            .unwrap()
+    };
+    let update = |mut table: Table<SimplifiedStruct>| {
+        let mut version = table.version("a").unwrap();
+        let mut versions = Vec::new();
+        while versions.len() < 20 {
+            match table.update_if_version("a", version, SimplifiedStruct::default()) {
+                Ok(()) => versions.push(table.version("a").unwrap()),
+                Err(TableError::ConflictError(_, stored)) => version = stored,
+                Err(e) => panic!("{e}"),
+            }
+            version = table.version("a").unwrap().max(version);
+        }
+        versions
+    };
+    let (first, second) = std::thread::scope(|scope| {
+        let (first, second) = (load(), load());
+        let first = scope.spawn(|| update(first));
+        let second = scope.spawn(|| update(second));
+        (first.join().unwrap(), second.join().unwrap())
+    });
+    // every version was reached by a single update
+    let mut versions: Vec<_> = first.into_iter().chain(second).collect();
+    versions.sort();
+    assert_eq!(versions, (2..42).collect::<Vec<_>>());
+    std::fs::remove_dir_all("tests/concurrent_updates").unwrap();
+}
+
+#[test]
+fn external_edits() {
+    let edit = |int: i32| {
+        let info = SimplifiedStruct {
+            int,
+            ..Default::default()
+        };
+        std::fs::write("tests/external/a.json", serde_json::to_vec(&info).unwrap()).unwrap();
+    };
+    let open = |policy| {
+        let mut table = Table::<SimplifiedStruct>::builder("tests/external")
+            .set_external_edit_policy(policy)
+            .set_manual_write()
+            .load()
+            .unwrap();
+        table["a"].info.int = 1;
+        table
+    };
+    let mut table = Table::<SimplifiedStruct>::builder("tests/external")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+
+    let mut table = open(ExternalEditPolicy::Error);
+    edit(5);
+    match table.write_back() {
+        Err(TableError::ExternalEditError(key)) => assert_eq!(key, "a"),
+        _ => assert!(false),
+    };
+    assert!(table.is_modified());
+
+    let mut table = open(ExternalEditPolicy::PreferTheirs);
+    edit(6);
+    table.write_back().unwrap();
+    assert_eq!(table["a"].info.int, 6);
+
+    let mut table = open(ExternalEditPolicy::Merge);
+    edit(7);
+    table.set_merge(|_, ours, theirs| SimplifiedStruct {
+        int: ours.int + theirs.int,
+        ..Default::default()
+    });
+    table.write_back().unwrap();
+    assert_eq!(table["a"].info.int, 8);
+
+    let mut table = open(ExternalEditPolicy::PreferOurs);
+    edit(9);
+    table.write_back().unwrap();
+    let json: json_tables::Value =
+        serde_json::from_slice(&std::fs::read("tests/external/a.json").unwrap()).unwrap();
+    assert_eq!(json["int"], 1);
+    std::fs::remove_dir_all("tests/external").unwrap();
+}
+
+#[test]
+fn append_or_rollback() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/append_rollback")
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    match table.append_or_rollback(&["a"], Vec::new()) {
+        Err(TableError::AppendLengthError) => assert!(true),
+        _ => assert!(false),
+    };
+    let elements = vec![SimplifiedStruct::default(); 3];
+    match table.append_or_rollback(&["a", "c", "b"], elements.clone()) {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    assert_eq!(table.len(), 1);
+    assert!(std::fs::metadata("tests/append_rollback/a.json").is_err());
+    assert!(std::fs::metadata("tests/append_rollback/b.json").is_ok());
+    assert!(std::fs::metadata("tests/append_rollback/c.json").is_err());
+    table
+        .append_or_rollback(&["a", "c", "d"], elements)
+        .unwrap();
+    assert_eq!(table.len(), 4);
+    std::fs::remove_dir_all("tests/append_rollback").unwrap();
+}
+
+#[test]
+fn append_pairs() {
+    let mut table = Table::<ExampleStruct, MemoryStorage>::in_memory();
+    table
+        .append_pairs((0..3).map(|int| {
+            let element = ExampleStruct {
+                int,
+                ..Default::default()
+            };
+            (format!("id_{int}"), element)
+        }))
+        .unwrap();
+    assert_eq!(table.len(), 3);
+    assert_eq!(table["id_2"].info.int, 2);
+    match table.append_pairs([
+        ("id_3", ExampleStruct::default()),
+        ("id_0", ExampleStruct::default()),
+    ]) {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    assert!(table.get_element("id_3").is_some());
+}
+
+#[test]
+fn append_report() {
+    let mut table = Table::<ExampleStruct, MemoryStorage>::in_memory();
+    table.push("b", ExampleStruct::default()).unwrap();
+    let report = table.append_report(["a", "b", "c"].map(|key| (key, ExampleStruct::default())));
+    assert!(!report.is_complete());
+    assert_eq!(report.written, ["a", "c"]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "b");
+    assert_eq!(table.len(), 3);
+    let report = table.append_report([("d", ExampleStruct::default())]);
+    assert!(report.is_complete());
+}
+
+#[test]
+fn pop_report() {
+    let mut table = Table::<ExampleStruct, MemoryStorage>::in_memory();
+    table
+        .append_pairs(["a", "b"].map(|key| (key, ExampleStruct::default())))
+        .unwrap();
+    let report = table.pop_report(&["a", "missing", "b"]);
+    assert!(matches!(report["a"], json_tables::PopOutcome::Removed));
+    assert!(matches!(
+        report["missing"],
+        json_tables::PopOutcome::NotFound
+    ));
+    assert!(matches!(report["b"], json_tables::PopOutcome::Removed));
+    assert!(table.is_empty());
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn json5_entries() {
+    let entry = "// a comment\n{int: 1, float: 2.0,}";
+    std::fs::create_dir_all("tests/json5").unwrap();
+    std::fs::write("tests/json5/a.json", entry).unwrap();
+    std::fs::write("tests/json5/b.json", entry).unwrap();
+    // json5 is only read when it's chosen
+    assert!(Table::<SimplifiedStruct>::load("tests/json5", None).is_err());
+    let mut table = Table::<SimplifiedStruct>::builder("tests/json5")
+        .set_json5_policy(json_tables::Json5Policy::Preserve)
+        .set_manual_write()
+        .load()
+        .unwrap();
+    assert_eq!(table["a"].info.int, 1);
+    table["b"].info.int = 2;
+    table.write_back().unwrap();
+    assert_eq!(
+        std::fs::read_to_string("tests/json5/a.json").unwrap(),
+        entry
+    );
+    let json: json_tables::Value =
+        serde_json::from_slice(&std::fs::read("tests/json5/b.json").unwrap()).unwrap();
+    assert_eq!(json["int"], 2);
+
+    let mut table = Table::<SimplifiedStruct>::builder("tests/json5")
+        .set_json5_policy(json_tables::Json5Policy::ConvertToJson)
+        .set_manual_write()
+        .load()
+        .unwrap();
+    assert!(table.is_modified());
+    table.write_back().unwrap();
+    let json: json_tables::Value =
+        serde_json::from_slice(&std::fs::read("tests/json5/a.json").unwrap()).unwrap();
+    assert_eq!(json["int"], 1);
+    std::fs::remove_dir_all("tests/json5").unwrap();
+}
+
+#[test]
+fn jsonc_entries() {
+    let entry = r#"{
+    // the fields of the entry
+    "int": 1, /* an int */
+    "float": 2.0,
+    "array": [1, 2, 3, 4,],
+    "tuple": [1, 2.0],
+    "string": "not // a comment, /* nor this */",
+    "vector": [],
+}"#;
+    let storage = MemoryStorage::with_items([("a.json", entry)]);
+    let metadata = TableMetadata {
+        rw_policy: RWPolicy::Write(json_tables::WriteType::Manual),
+        ..Default::default()
+    };
+    match Table::<ExampleStruct, _>::with_storage(storage.clone(), metadata) {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+    let metadata = TableMetadata {
+        syntax_policy: json_tables::SyntaxPolicy::Jsonc,
+        ..metadata
+    };
+    // lazy tables and streams read with the same syntax
+    let mut lazy =
+        json_tables::LazyTable::<ExampleStruct, _>::with_storage(storage.clone(), metadata, 1)
+            .unwrap();
+    assert_eq!(lazy.get("a").unwrap().unwrap().array, [1, 2, 3, 4]);
+    let mut stream =
+        json_tables::TableStream::<ExampleStruct, _>::with_storage(storage.clone(), metadata)
+            .unwrap();
+    assert_eq!(stream.next().unwrap().unwrap().1.int, 1);
+    let mut table = Table::<ExampleStruct, _>::with_storage(storage, metadata).unwrap();
+    assert_eq!(table["a"].info.array, [1, 2, 3, 4]);
+    assert_eq!(table["a"].info.string, "not // a comment, /* nor this */");
+    table.get_mut_element("a").unwrap();
+    table.write_back().unwrap();
+    assert_eq!(table.metrics().bytes_written, 0);
+}
+
+#[test]
+fn preserve_format() {
+    let entry = r#"{
+  // hand written
+  "string": "kept",   "int": 1,
+  "float": 2.0,
+  "array": [1, 2, 3, 4],
+  "tuple": [1, 2.0],
+  "vector": [] /* empty for now */
+}"#;
+    std::fs::create_dir_all("tests/preserve_format").unwrap();
+    std::fs::write("tests/preserve_format/a.json", entry).unwrap();
+    let mut table = Table::<ExampleStruct>::builder("tests/preserve_format")
+        .set_preserve_format()
+        .set_lenient_syntax()
+        .set_manual_write()
+        .load()
+        .unwrap();
+    table.get_mut_element("a").unwrap();
+    table.write_back().unwrap();
+    assert_eq!(table.metrics().bytes_written, 0);
+    table["a"].info.int = 10;
+    table["a"].info.array[2] = 30;
+    table["a"].info.vector.push(0.5);
+    table.write_back().unwrap();
+    assert_eq!(
+        std::fs::read_to_string("tests/preserve_format/a.json").unwrap(),
+        entry
+            .replace("\"int\": 1", "\"int\": 10")
+            .replace("[1, 2, 3, 4]", "[1, 2, 30, 4]")
+            .replace("[] /*", "[0.5] /*")
+    );
+    table.push("b", ExampleStruct::default()).unwrap();
+    table.write_back().unwrap();
+    let json: json_tables::Value =
+        serde_json::from_slice(&std::fs::read("tests/preserve_format/b.json").unwrap()).unwrap();
+    assert_eq!(json["int"], 0);
+    std::fs::remove_dir_all("tests/preserve_format").unwrap();
+}
+
+#[test]
+fn canonical_format() {
+    let metadata = TableMetadata {
+        rw_policy: RWPolicy::Write(json_tables::WriteType::Manual),
+        format_policy: FormatPolicy::Canonical,
+        ..Default::default()
+    };
+    let mut table =
+        Table::<json_tables::Value, _>::with_storage(MemoryStorage::new(), metadata).unwrap();
+    let element = serde_json::from_str(
+        r#"{
+            "numbers": [333333333.3333333, 1E30, 4.50, 2e-3, 1e-27, -0.0, 10, 1e21, 1e-7, 0.1],
+            "string": "€$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false],
+            "€": 1,
+            "😀": 2,
+            "\r": 3,
+            "1": 4
+        }"#,
+    )
+    .unwrap();
+    table.push("a", element).unwrap();
+    table.write_back().unwrap();
+    let written = json_tables::Storage::read(table.storage(), "a.json").unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        concat!(
+            r#"{"\r":3,"1":4,"literals":[null,true,false],"#,
+            r#""numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27,0,10,1e+21,1e-7,0.1],"#,
+            r#""string":"€$\u000f\nA'B\"\\\\\"/","€":1,"😀":2}"#
+        )
+    );
+}
+
+#[test]
+fn float_policy() {
+    let noisy = SimplifiedStruct {
+        int: 1,
+        float: 0.1 + 0.2,
+    };
+    let written = |float_policy| {
+        let metadata = TableMetadata {
+            rw_policy: RWPolicy::Write(json_tables::WriteType::Manual),
+            format_policy: FormatPolicy::Compact,
+            float_policy,
+            ..Default::default()
+        };
+        let mut table =
+            Table::<SimplifiedStruct, _>::with_storage(MemoryStorage::new(), metadata).unwrap();
+        table.push("a", noisy).unwrap();
+        table.write_back().unwrap();
+        String::from_utf8(json_tables::Storage::read(table.storage(), "a.json").unwrap()).unwrap()
+    };
+    assert_eq!(
+        written(json_tables::FloatPolicy::ShortestRoundTrip),
+        r#"{"int":1,"float":0.30000000000000004}"#
+    );
+    assert_eq!(
+        written(json_tables::FloatPolicy::MaxPrecision(3)),
+        r#"{"int":1,"float":0.3}"#
+    );
+    assert_eq!(
+        written(json_tables::FloatPolicy::FixedDecimals(2)),
+        r#"{"int":1,"float":0.3}"#
+    );
+    // shared tables write with the same policy
+    let table = Table::<SimplifiedStruct>::builder("tests/shared_floats")
+        .set_compact_format()
+        .set_float_policy(json_tables::FloatPolicy::MaxPrecision(3))
+        .set_manual_write()
+        .build()
+        .unwrap();
+    let shared = table.into_shared();
+    shared.push("a", noisy).unwrap();
+    shared.flush().unwrap();
+    assert_eq!(
+        std::fs::read_to_string("tests/shared_floats/a.json").unwrap(),
+        r#"{"int":1,"float":0.3}"#
+    );
+    drop(shared);
+    std::fs::remove_dir_all("tests/shared_floats").unwrap();
+}
+
+#[test]
+fn duplicate_keys() {
+    let storage = MemoryStorage::with_items([
+        ("a.json", r#"{"int": 1, "float": 1.0}"#),
+        (
+            "b.json",
+            r#"{"int": 1, "float": 1.0, "nested": [{"a/b": 1, "a/b": 2}]}"#,
+        ),
+    ]);
+    let metadata = TableMetadata {
+        rw_policy: RWPolicy::ReadOnly,
+        ..Default::default()
+    };
+    let table = Table::<SimplifiedStruct, _>::with_storage(storage.clone(), metadata).unwrap();
+    assert_eq!(table.len(), 2);
+    let metadata = TableMetadata {
+        duplicate_key_policy: json_tables::DuplicateKeyPolicy::Error,
+        ..metadata
+    };
+    match Table::<SimplifiedStruct, _>::with_storage(storage, metadata) {
+        Err(TableError::DuplicateKeyError(key, path)) => {
+            assert_eq!(key, "b");
+            assert_eq!(path, "/nested/0/a~1b");
+        }
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn close() {
+    let mut table = Table::<SimplifiedStruct>::builder("tests/close")
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table["a"].info.int = 1;
+    table.close().unwrap();
+    let table = Table::<SimplifiedStruct>::builder("tests/close")
+        .load()
+        .unwrap();
+    assert_eq!(table["a"].info.int, 1);
+    table.close().unwrap();
+    let table = Table::<SimplifiedStruct>::builder("tests/close")
+        .set_read_only()
+        .load()
+        .unwrap();
+    table.close().unwrap();
+    std::fs::remove_dir_all("tests/close").unwrap();
+}
+
+#[test]
+fn drop_failure() {
+    use std::sync::{Arc, Mutex};
+    // two elements with the same int make the write back on drop fail
+    let failing_table = || {
+        let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+        table
+            .unique("int", |element| element.int.to_string())
+            .unwrap();
+        table.push("a", SimplifiedStruct::default()).unwrap();
+        let element = SimplifiedStruct {
+            int: 1,
+            ..Default::default()
+        };
+        table.push("b", element).unwrap();
+        table.write_back().unwrap();
+        table["b"].info.int = 0;
+        table
+    };
+    let panicking = std::panic::catch_unwind(|| drop(failing_table()));
+    assert!(panicking.is_err());
+    let mut table = failing_table();
+    table.on_drop_failure(json_tables::DropFailure::Log);
+    drop(table);
+    let rescued = Arc::new(Mutex::new(Vec::new()));
+    let mut table = failing_table();
+    let rescue = rescued.clone();
+    table.on_drop_failure(json_tables::DropFailure::Callback(Box::new(
+        move |error, unsaved| {
+            assert!(matches!(error, TableError::UniqueError(..)));
+            *rescue.lock().unwrap() = unsaved;
+        },
+    )));
+    drop(table);
+    let keys: Vec<_> = rescued
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, _)| key.clone())
+        .collect();
+    assert_eq!(keys, ["a", "b"]);
+}
+
+#[test]
+fn shared_and_lazy_drop_failure() {
+    use std::sync::{Arc, Mutex};
+    let mut table = Table::<SimplifiedStruct>::builder("tests/shared_drop")
+        .build()
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    let shared = table.into_shared();
+    shared.update("a", |element| element.int = 1).unwrap();
+    shared.clone().close().unwrap();
+    assert!(std::fs::read_to_string("tests/shared_drop/a.json")
+        .unwrap()
+        .contains("1"));
+    // the writes on drop fail once the directory is gone
+    shared.update("a", |element| element.int = 2).unwrap();
+    std::fs::remove_dir_all("tests/shared_drop").unwrap();
+    let rescued = Arc::new(Mutex::new(Vec::new()));
+    let rescue = rescued.clone();
+    shared.on_drop_failure(json_tables::DropFailure::Callback(Box::new(
+        move |_, unsaved| *rescue.lock().unwrap() = unsaved,
+    )));
+    drop(shared);
+    assert_eq!(rescued.lock().unwrap()[0].1.int, 2);
+
+    let failing_lazy = || {
+        std::fs::create_dir_all("tests/lazy_drop").unwrap();
+        std::fs::write("tests/lazy_drop/a.json", r#"{"int": 0, "float": 0.0}"#).unwrap();
+        let mut lazy = Table::<SimplifiedStruct>::builder("tests/lazy_drop")
+            .load_lazy(1024)
+            .unwrap();
+        lazy.get_mut("a").unwrap().unwrap().int = 1;
+        std::fs::remove_dir_all("tests/lazy_drop").unwrap();
+        lazy
+    };
+    assert!(std::panic::catch_unwind(|| drop(failing_lazy())).is_err());
+    let mut lazy = failing_lazy();
+    lazy.on_drop_failure(json_tables::DropFailure::Log);
+    drop(lazy);
+    match failing_lazy().close() {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn table_summary() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    for i in 0..12 {
+        table
+            .push(&format!("{i:02}"), SimplifiedStruct::default())
+            .unwrap();
+    }
+    let summary = table.summary();
+    assert_eq!(summary.entries, 12);
+    assert_eq!(summary.dirty, 12);
+    assert!(summary.dir.is_none());
+    table.write_back().unwrap();
+    table["05"].info.int = 1;
+    assert_eq!(table.summary().dirty, 1);
+    assert_eq!(
+        table.to_string(),
+        "Table in memory (Write(Automatic), IgnoreNonJson, PromoteSerdeErrors, Pretty format): \
+        12 entries, 1 dirty, keys: 00, 01, 02, 03, 04, 05, 06, 07, 08, 09 and 2 more"
+    );
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn render_ascii() {
+    let mut table = Table::<ExampleStruct, MemoryStorage>::in_memory();
+    let element = ExampleStruct {
+        int: 100,
+        string: "long string".to_string(),
+        ..Default::default()
+    };
+    table.push("a", element).unwrap();
+    table.push("bbbb", ExampleStruct::default()).unwrap();
+    let rendered = table.render_ascii(&[
+        ("int", |element| element.int.to_string()),
+        ("string", |element| element.string.clone()),
+    ]);
+    assert_eq!(
+        rendered,
+        "key  | int | string\n\
+         -----+-----+------------\n\
+         a    | 100 | long string\n\
+         bbbb | 0   |\n"
+    );
+}
+
+#[test]
+fn serialize_table() {
+    #[derive(Serialize, Deserialize)]
+    struct Document {
+        name: String,
+        table: Table<SimplifiedStruct, MemoryStorage>,
+    }
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    let element = SimplifiedStruct { int: 1, float: 0.5 };
+    table.push("a", element).unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    let json = serde_json::to_value(&table).unwrap();
+    assert_eq!(json["entries"]["a"]["int"], 1);
+    assert_eq!(json["metadata"]["format_policy"], "Pretty");
+    let document = Document {
+        name: "snapshot".to_string(),
+        table,
+    };
+    let serialized = serde_json::to_string(&document).unwrap();
+    let document: Document = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(document.name, "snapshot");
+    assert_eq!(document.table.len(), 2);
+    assert_eq!(document.table["a"].info.float, 0.5);
+    assert!(!document.table.is_modified());
+    match serde_json::from_str::<Table<SimplifiedStruct, MemoryStorage>>(r#"{"entries": 1}"#) {
+        Err(_) => assert!(true),
+        _ => assert!(false),
+    };
+}
+
+#[test]
+fn map_conversions() {
+    let map: std::collections::HashMap<_, _> = (0..3)
+        .map(|int| {
+            let element = SimplifiedStruct {
+                int,
+                ..Default::default()
+            };
+            (int.to_string(), element)
+        })
+        .collect();
+    let table = Table::from_map("tests/from_map", map, TableMetadata::default()).unwrap();
+    assert_eq!(table.len(), 3);
+    assert!(!table.is_modified());
+    drop(table);
+    let table = Table::<SimplifiedStruct>::builder("tests/from_map")
+        .load()
+        .unwrap();
+    let map = table.into_map();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["2"].int, 2);
+    std::fs::remove_dir_all("tests/from_map").unwrap();
+}
+
+#[test]
+fn typed_keys() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory().typed::<u32>();
+    table.push(&7, SimplifiedStruct::default()).unwrap();
+    table.push(&12, SimplifiedStruct::default()).unwrap();
+    table.get_mut(&7).unwrap().int = 7;
+    assert_eq!(table.get(&7).unwrap().int, 7);
+    assert!(table.contains_key(&12));
+    assert!(!table.contains_key(&8));
+    table
+        .table_mut()
+        .push("007", SimplifiedStruct::default())
+        .unwrap();
+    table
+        .table_mut()
+        .push("seven", SimplifiedStruct::default())
+        .unwrap();
+    assert_eq!(table.table().len(), 4);
+    assert_eq!(table.keys().collect::<Vec<_>>(), vec![12, 7]);
+    table.pop(&12).unwrap();
+    match table.pop(&12) {
+        Err(TableError::PopError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    let table = table.into_inner();
+    assert_eq!(table["7"].info.int, 7);
+}
+
+#[test]
+fn numeric_ids() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    for id in [9, 10, 100] {
+        table.push_id(id, SimplifiedStruct::default()).unwrap();
+    }
+    table.push("name", SimplifiedStruct::default()).unwrap();
+    assert_eq!(table.ids(), vec![9, 10, 100]);
+    assert_eq!(table.max_id(), Some(100));
+    table.get_mut_id(10).unwrap().int = 10;
+    assert_eq!(table.get_id(10).unwrap().int, 10);
+    assert!(table.get_id(11).is_none());
+    table.pop_id(100).unwrap();
+    assert_eq!(table.max_id(), Some(10));
+
+    let metadata = TableMetadata {
+        id_policy: IdPolicy::ZeroPadded(4),
+        ..Default::default()
+    };
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::with_storage(
+        MemoryStorage::with_items([
+            ("7.json", r#"{"int": 7, "float": 0.0}"#),
+            ("0012.json", r#"{"int": 12, "float": 0.0}"#),
+        ]),
+        metadata,
+    )
+    .unwrap();
+    table.push_id(9, SimplifiedStruct::default()).unwrap();
+    assert!(table.get_table_keys().any(|key| &**key == "0009"));
+    assert_eq!(table.ids(), vec![9, 12]);
+    assert!(table.get_id(7).is_none());
+}
+
+#[test]
+fn composite_keys() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory().typed::<(String, u32)>();
+    for (tenant, id) in [("acme", 1), ("acme", 2), ("acme~corp", 1), ("initech", 1)] {
+        table
+            .push(&(tenant.to_string(), id), SimplifiedStruct::default())
+            .unwrap();
+    }
+    assert!(table.table().get_element("acme%7Ecorp~1").is_some());
+    table.get_mut(&("initech".to_string(), 1)).unwrap().int = 3;
+    assert_eq!(table.get(&("initech".to_string(), 1)).unwrap().int, 3);
+    let acme: Vec<_> = table
+        .with_prefix(&"acme".to_string())
+        .map(|(key, _)| key)
+        .collect();
+    assert_eq!(acme, vec![("acme".to_string(), 1), ("acme".to_string(), 2)]);
+    assert_eq!(table.with_prefix(&"acme~corp".to_string()).count(), 1);
+    assert_eq!(table.keys().count(), 4);
+}
+
+#[test]
+fn key_normalization() {
+    let entry = r#"{"int": 1, "float": 0.0}"#;
+    let metadata = TableMetadata {
+        normalization_policy: NormalizationPolicy::Nfc,
+        ..Default::default()
+    };
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::with_storage(
+        MemoryStorage::with_items([("cafe\u{301}.json", entry)]),
+        metadata,
+    )
+    .unwrap();
+    assert!(table.get_element("caf\u{e9}").is_some());
+    assert!(table.get_element("cafe\u{301}").is_some());
+    assert!(json_tables::Storage::read(table.storage(), "caf\u{e9}.json").is_ok());
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    let [cafe, _] = table.get_many_mut(["cafe\u{301}", "b"]).unwrap();
+    cafe.info.int = 2;
+    assert_eq!(table["caf\u{e9}"].info.int, 2);
+    assert!(table.get_many_mut(["cafe\u{301}", "caf\u{e9}"]).is_none());
+    table.pop("b").unwrap();
+    match table.push("cafe\u{301}", SimplifiedStruct::default()) {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.pop("cafe\u{301}").unwrap();
+    assert!(table.is_empty());
+
+    let shared = table.into_shared();
+    shared
+        .push("cafe\u{301}", SimplifiedStruct::default())
+        .unwrap();
+    shared
+        .update("caf\u{e9}", |element| element.int = 3)
+        .unwrap();
+    assert_eq!(shared.read("cafe\u{301}", |element| element.int), Some(3));
+    match shared.push("caf\u{e9}", SimplifiedStruct::default()) {
+        Err(TableError::PushError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    shared.pop("cafe\u{301}").unwrap();
+    assert!(shared.is_empty());
+
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::with_storage(
+        MemoryStorage::with_items([("caf\u{e9}.json", entry)]),
+        TableMetadata {
+            concurrency_policy: ConcurrencyPolicy::Optimistic,
+            history_policy: json_tables::HistoryPolicy::KeepVersions(2),
+            ..metadata
+        },
+    )
+    .unwrap();
+    let patch = serde_json::json!({"int": 4});
+    table.merge_patch("cafe\u{301}", &patch).unwrap();
+    assert_eq!(
+        table.get_pointer("cafe\u{301}", "/int").unwrap(),
+        Some(serde_json::json!(4))
+    );
+    table.write_back().unwrap();
+    assert_eq!(table.version("cafe\u{301}"), Some(1));
+    let history = table.history("cafe\u{301}").unwrap();
+    table.restore_version("cafe\u{301}", history[0].0).unwrap();
+    assert_eq!(table["caf\u{e9}"].info.int, 1);
+    table
+        .update_if_version("cafe\u{301}", 1, SimplifiedStruct::default())
+        .unwrap();
+
+    match Table::<SimplifiedStruct, MemoryStorage>::with_storage(
+        MemoryStorage::with_items([("cafe\u{301}.json", entry), ("caf\u{e9}.json", entry)]),
+        metadata,
+    ) {
+        Err(TableError::NormalizationError(key)) => assert_eq!(key, "caf\u{e9}"),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn long_keys() {
+    let long_key = "k".repeat(300);
+    let mut table = Table::<SimplifiedStruct>::builder("tests/long_keys")
+        .set_long_key_limit(200)
+        .build()
+        .unwrap();
+    table.push(&long_key, SimplifiedStruct::default()).unwrap();
+    table.push("short", SimplifiedStruct::default()).unwrap();
+    table.get_mut_element(&long_key).unwrap().info.int = 4;
+    table.write_back().unwrap();
+    drop(table);
+    let mut table = Table::<SimplifiedStruct>::builder("tests/long_keys")
+        .load()
+        .unwrap();
+    assert_eq!(table.metadata().long_key_policy, LongKeyPolicy::Hash(200));
+    assert_eq!(table[long_key.as_str()].info.int, 4);
+    assert!(table.get_element("short").is_some());
+    table.rename(&long_key, "renamed").unwrap();
+    table.pop("renamed").unwrap();
+    assert_eq!(std::fs::read_dir("tests/long_keys").unwrap().count(), 2);
+    drop(table);
+    std::fs::remove_dir_all("tests/long_keys").unwrap();
+
+    // the items that go along with the entries are named after the hash too
+    let mut table = Table::<SimplifiedStruct>::builder("tests/long_keys")
+        .set_long_key_limit(100)
+        .set_history(2)
+        .set_optimistic_concurrency()
+        .set_manual_write()
+        .build()
+        .unwrap();
+    table.push(&long_key, SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    table.get_mut_element(&long_key).unwrap().info.int = 4;
+    table.write_back().unwrap();
+    assert_eq!(table.version(&long_key), Some(2));
+    assert_eq!(table.history(&long_key).unwrap().len(), 1);
+    table
+        .update_if_version(&long_key, 2, SimplifiedStruct::default())
+        .unwrap();
+    table.soft_pop(&long_key, None).unwrap();
+    drop(table);
+
+    // the empty items of pushed entries aren't junk
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::with_storage(
+        MemoryStorage::default(),
+        TableMetadata {
+            long_key_policy: LongKeyPolicy::Hash(100),
+            rw_policy: RWPolicy::Write(json_tables::WriteType::Manual),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    table.push(&long_key, SimplifiedStruct::default()).unwrap();
+    assert!(table.vacuum(Default::default()).unwrap().is_empty());
+    std::fs::remove_dir_all("tests/long_keys").unwrap();
+}
+
+#[test]
+fn temporary_table() {
+    let mut table = Table::<SimplifiedStruct>::temporary().unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    let dir = table.dir().to_path_buf();
+    assert!(dir.join("a.json").exists());
+    drop(table);
+    assert!(!dir.exists());
+
+    // the directory goes along with the elements into a shared table
+    let mut table = Table::<SimplifiedStruct>::temporary().unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    let dir = table.dir().to_path_buf();
+    let shared = table.into_shared();
+    shared.flush().unwrap();
+    assert!(dir.join("a.json").exists());
+    drop(shared);
+    assert!(!dir.exists());
+    let table = Table::<SimplifiedStruct>::temporary().unwrap();
+    let dir = table.dir().to_path_buf();
+    assert!(table.into_map().is_empty());
+    assert!(!dir.exists());
+
+    let dir = std::panic::catch_unwind(|| {
+        let table = Table::<SimplifiedStruct>::temporary().unwrap();
+        let dir = table.dir().to_path_buf();
+        if dir.exists() {
+            panic!("{}", dir.display());
+        }
+    })
+    .unwrap_err()
+    .downcast::<String>()
+    .unwrap();
+    assert!(!std::path::Path::new(dir.as_str()).exists());
+}
+
+#[test]
+fn frozen_entries() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .push("reference", SimplifiedStruct::default())
+        .unwrap();
+    table.push("other", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    match table.freeze("missing") {
+        Err(TableError::MissingKeyError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.freeze("reference").unwrap();
+    assert!(table.is_frozen("reference"));
+    assert!(table.get_mut_element("reference").is_none());
+    assert!(table.get_many_mut(["reference", "other"]).is_none());
+    match table.try_get_mut_element("reference") {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match table.pop("reference") {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match table.rename("reference", "renamed") {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    for info in table.values_mut() {
+        info.int = 5;
+    }
+    table.write_back().unwrap();
+    let stored = json_tables::Storage::read(table.storage(), "reference.json").unwrap();
+    assert!(String::from_utf8(stored).unwrap().contains("\"int\": 0"));
+    let patch = serde_json::json!({"int": 7});
+    match table.merge_patch("reference", &patch) {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    let patch = serde_json::json!([{"op": "replace", "path": "/int", "value": 7}]);
+    match table.patch("reference", &patch) {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match table.update_if_version("reference", 0, SimplifiedStruct::default()) {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.compact(false).unwrap();
+    let stored = json_tables::Storage::read(table.storage(), "reference.json").unwrap();
+    assert!(String::from_utf8(stored).unwrap().contains("\"int\": 0"));
+    let savepoint = table.savepoint();
+    table.unfreeze("reference");
+    table["reference"].info.int = 8;
+    table["other"].info.int = 9;
+    table.freeze("reference").unwrap();
+    table.rollback_to(&savepoint).unwrap();
+    assert_eq!(table["reference"].info.int, 8);
+    assert_eq!(table["other"].info.int, 5);
+    assert!(table.unfreeze("reference"));
+    assert!(!table.unfreeze("reference"));
+    table.try_get_mut_element("reference").unwrap().info.int = 6;
+    table.pop("reference").unwrap();
+}
+
+#[test]
+fn policy_transitions() {
+    let mut table =
+        Table::<SimplifiedStruct>::new("tests/transitions", TableMetadata::default()).unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.get_mut_element("a").unwrap().info.int = 3;
+    let mut table = table.into_read_only().unwrap();
+    assert!(!table.has_mod_permissions());
+    match table.push("b", SimplifiedStruct::default()) {
+        Err(TableError::NoWritePolicyError) => assert!(true),
+        _ => assert!(false),
+    }
+    let reloaded = Table::<SimplifiedStruct>::builder("tests/transitions")
+        .set_read_only()
+        .load()
+        .unwrap();
+    assert_eq!(reloaded["a"].info.int, 3);
+    let mut table = reloaded.try_into_writable().unwrap();
+    table.get_mut_element("a").unwrap().info.int = 4;
+    drop(table);
+    let table = Table::<SimplifiedStruct>::builder("tests/transitions")
+        .load()
+        .unwrap();
+    assert_eq!(table["a"].info.int, 4);
+    drop(table);
+    std::fs::remove_dir_all("tests/transitions").unwrap();
+}
+
+/// Maps with keys that aren't strings can't be written as json, so a write
+/// back fails once some pair is inserted
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Pairs {
+    pairs: std::collections::BTreeMap<(i32, i32), i32>,
+    count: i32,
+}
+
+#[test]
+fn atomic_write_back() {
+    let metadata = TableMetadata {
+        atomicity_policy: AtomicityPolicy::WholeTable,
+        ..Default::default()
+    };
+    let mut table = Table::<Pairs>::new("tests/atomic", metadata).unwrap();
+    table.push("a", Pairs::default()).unwrap();
+    table.push("b", Pairs::default()).unwrap();
+    table
+        .get_mut_element("b")
+        .unwrap()
+        .info
+        .pairs
+        .insert((1, 2), 3);
+    match table.write_back() {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    assert!(table.is_modified());
+    assert_eq!(std::fs::read("tests/atomic/a.json").unwrap().len(), 0);
+    assert_eq!(std::fs::read_dir("tests/atomic").unwrap().count(), 3);
+    table.get_mut_element("b").unwrap().info.pairs.clear();
+    table.write_back().unwrap();
+    assert!(!std::fs::read("tests/atomic/a.json").unwrap().is_empty());
+    assert_eq!(std::fs::read_dir("tests/atomic").unwrap().count(), 3);
+    drop(table);
+    std::fs::remove_dir_all("tests/atomic").unwrap();
+
+    // failed write backs keep no history nor versions
+    let metadata = TableMetadata {
+        concurrency_policy: ConcurrencyPolicy::Optimistic,
+        history_policy: json_tables::HistoryPolicy::KeepVersions(2),
+        ..metadata
+    };
+    let mut table = Table::<Pairs>::new("tests/atomic", metadata).unwrap();
+    table.push("a", Pairs::default()).unwrap();
+    table.push("b", Pairs::default()).unwrap();
+    table.write_back().unwrap();
+    let items = std::fs::read_dir("tests/atomic").unwrap().count();
+    table["a"].info.count = 1;
+    table["b"].info.pairs.insert((1, 2), 3);
+    assert!(table.write_back().is_err());
+    assert_eq!(std::fs::read_dir("tests/atomic").unwrap().count(), items);
+    assert_eq!(table.version("a"), Some(1));
+    assert!(table.history("a").unwrap().is_empty());
+    table["b"].info.pairs.clear();
+    table.write_back().unwrap();
+    assert_eq!(table.version("a"), Some(2));
+    assert_eq!(table.history("a").unwrap().len(), 1);
+    drop(table);
+    std::fs::remove_dir_all("tests/atomic").unwrap();
+
+    // a storage that can't move one of the items into place
+    use json_tables::Storage;
+    use std::sync::{Arc, Mutex};
+    struct FailingRename {
+        items: MemoryStorage,
+        fails: Arc<Mutex<Option<&'static str>>>,
+    }
+    impl Storage for FailingRename {
+        fn list(&self) -> std::io::Result<Vec<String>> {
+            self.items.list()
+        }
+        fn read(&self, name: &str) -> std::io::Result<Vec<u8>> {
+            self.items.read(name)
+        }
+        fn create(&mut self, name: &str) -> std::io::Result<()> {
+            self.items.create(name)
+        }
+        fn write(&mut self, name: &str, content: &[u8]) -> std::io::Result<()> {
+            self.items.write(name, content)
+        }
+        fn delete(&mut self, name: &str) -> std::io::Result<()> {
+            self.items.delete(name)
+        }
+        fn rename(&mut self, from: &str, to: &str) -> std::io::Result<()> {
+            match *self.fails.lock().unwrap() == Some(to) {
+                true => Err(std::io::ErrorKind::PermissionDenied.into()),
+                false => self.items.rename(from, to),
+            }
+        }
+    }
+    let fails = Arc::new(Mutex::new(None));
+    let storage = FailingRename {
+        items: MemoryStorage::default(),
+        fails: fails.clone(),
+    };
+    let metadata = TableMetadata {
+        rw_policy: RWPolicy::Write(json_tables::WriteType::Manual),
+        ..metadata
+    };
+    let mut table = Table::<SimplifiedStruct, _>::with_storage(storage, metadata).unwrap();
+    for key in ["a", "b", "c"] {
+        table.push(key, SimplifiedStruct::default()).unwrap();
+    }
+    table.write_back().unwrap();
+    table.values_mut().for_each(|element| element.int = 1);
+    *fails.lock().unwrap() = Some("b.json");
+    match table.write_back() {
+        Err(TableError::FileOpError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    assert!(table.is_modified());
+    let items = table.storage().list().unwrap();
+    assert!(!items.iter().any(|item| item.starts_with(".table_staging")));
+    let stored = |table: &Table<SimplifiedStruct, FailingRename>, name| {
+        serde_json::from_slice::<SimplifiedStruct>(&table.storage().read(name).unwrap()).unwrap()
+    };
+    assert_eq!(stored(&table, "a.json").int, 1);
+    assert_eq!(stored(&table, "c.json").int, 0);
+    *fails.lock().unwrap() = None;
+    table.write_back().unwrap();
+    assert_eq!(stored(&table, "c.json").int, 1);
+}
+
+#[test]
+fn group_atomic_flush() {
+    let mut group = TableGroup::new();
+    let mut inventory =
+        Table::<SimplifiedStruct>::new("tests/group_inventory", Default::default()).unwrap();
+    inventory
+        .push("apples", SimplifiedStruct::default())
+        .unwrap();
+    inventory.write_back().unwrap();
+    let mut pairs = Table::<Pairs>::new("tests/group_pairs", Default::default()).unwrap();
+    pairs.push("a", Pairs::default()).unwrap();
+    group.insert("inventory", inventory);
+    group.insert("pairs", pairs);
+    let stored = std::fs::read("tests/group_inventory/apples.json").unwrap();
+    group.get_mut::<SimplifiedStruct>("inventory").unwrap()["apples"]
+        .info
+        .int = 9;
+    group.get_mut::<Pairs>("pairs").unwrap()["a"]
+        .info
+        .pairs
+        .insert((1, 1), 1);
+    match group.flush_all_atomic() {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    assert_eq!(
+        std::fs::read("tests/group_inventory/apples.json").unwrap(),
+        stored
+    );
+    assert_eq!(
+        std::fs::read_dir("tests/group_inventory").unwrap().count(),
+        2
+    );
+    assert!(group
+        .get::<SimplifiedStruct>("inventory")
+        .unwrap()
+        .is_modified());
+    group.get_mut::<Pairs>("pairs").unwrap()["a"]
+        .info
+        .pairs
+        .clear();
+    group.flush_all_atomic().unwrap();
+    assert_ne!(
+        std::fs::read("tests/group_inventory/apples.json").unwrap(),
+        stored
+    );
+    drop(group);
+    std::fs::remove_dir_all("tests/group_inventory").unwrap();
+    std::fs::remove_dir_all("tests/group_pairs").unwrap();
+}
+
+#[test]
+fn group_partial_commit() {
+    let mut group = TableGroup::new();
+    let mut first =
+        Table::<SimplifiedStruct>::new("tests/group_first", Default::default()).unwrap();
+    first.push("a", SimplifiedStruct::default()).unwrap();
+    let metadata = TableMetadata {
+        audit_policy: json_tables::AuditPolicy::AppendLog,
+        ..Default::default()
+    };
+    let mut second = Table::<SimplifiedStruct>::new("tests/group_second", metadata).unwrap();
+    second.push("a", SimplifiedStruct::default()).unwrap();
+    // the audit log can't be appended to once it's a directory
+    std::fs::remove_file("tests/group_second/.table_audit.log").unwrap();
+    std::fs::create_dir("tests/group_second/.table_audit.log").unwrap();
+    group.insert("first", first);
+    group.insert("second", second);
+    // the tables are committed in the order of their names
+    match group.flush_all_atomic() {
+        Err(TableError::PartialCommitError(committed, failed, e)) => {
+            assert_eq!(committed, ["first"]);
+            assert_eq!(failed, "second");
+            assert!(matches!(*e, TableError::FileOpError(_)));
+        }
+        _ => assert!(false),
+    }
+    assert!(!group
+        .get::<SimplifiedStruct>("first")
+        .unwrap()
+        .is_modified());
+    assert!(group
+        .get::<SimplifiedStruct>("second")
+        .unwrap()
+        .is_modified());
+    let items = std::fs::read_dir("tests/group_second").unwrap();
+    assert!(!items
+        .map(|item| item.unwrap().file_name().to_string_lossy().into_owned())
+        .any(|name| name.starts_with(".table_staging")));
+    // the second table is written back as the group is dropped
+    std::fs::remove_dir("tests/group_second/.table_audit.log").unwrap();
+    drop(group);
+    std::fs::remove_dir_all("tests/group_first").unwrap();
+    std::fs::remove_dir_all("tests/group_second").unwrap();
+}
+
+#[test]
+fn savepoints() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    let start = table.savepoint();
+    table["a"].info.int = 1;
+    let phase = table.savepoint();
+    table["a"].info.int = 2;
+    table.push("c", SimplifiedStruct::default()).unwrap();
+    table.pop("b").unwrap();
+    table.rollback_to(&phase).unwrap();
+    assert_eq!(table["a"].info.int, 1);
+    assert!(table.get_element("c").is_none());
+    assert!(table.get_element("b").is_some());
+    assert!(json_tables::Storage::read(table.storage(), "c.json").is_err());
+    table.write_back().unwrap();
+    table.rollback_to(&start).unwrap();
+    assert_eq!(table["a"].info.int, 0);
+    assert!(table.is_modified());
+    table.write_back().unwrap();
+    let stored = json_tables::Storage::read(table.storage(), "a.json").unwrap();
+    assert!(String::from_utf8(stored).unwrap().contains("\"int\": 0"));
+}
+
+#[test]
+fn streamed_entries() {
+    let mut table = Table::<Vec<u64>>::temporary().unwrap();
+    table.push("big", (0..100_000).collect()).unwrap();
+    table.write_entry("big").unwrap();
+    let stored = std::fs::read(table.dir().join("big.json")).unwrap();
+    let info: Vec<u64> = serde_json::from_slice(&stored).unwrap();
+    assert_eq!(info.len(), 100_000);
+    std::fs::write(table.dir().join("big.json"), "[1, 2, 3]").unwrap();
+    table.reload_entry("big").unwrap();
+    assert_eq!(table["big"].info, vec![1, 2, 3]);
+    match table.write_entry("missing") {
+        Err(TableError::MissingKeyError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.freeze("big").unwrap();
+    match table.write_entry("big") {
+        Err(TableError::FrozenError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+
+    let mut table = Table::<Vec<u64>, MemoryStorage>::in_memory();
+    table.push("a", vec![4, 5]).unwrap();
+    table.write_entry("a").unwrap();
+    table["a"].info.clear();
+    table.reload_entry("a").unwrap();
+    assert_eq!(table["a"].info, vec![4, 5]);
+
+    // streamed entries keep their history and version, and respect the
+    // unique constraints
+    let metadata = TableMetadata {
+        concurrency_policy: ConcurrencyPolicy::Optimistic,
+        history_policy: json_tables::HistoryPolicy::KeepVersions(2),
+        ..Default::default()
+    };
+    let mut table =
+        Table::<SimplifiedStruct, _>::with_storage(MemoryStorage::default(), metadata).unwrap();
+    table
+        .unique("int", |element| element.int.to_string())
+        .unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.write_entry("a").unwrap();
+    table["a"].info.int = 1;
+    table.write_entry("a").unwrap();
+    assert_eq!(table.version("a"), Some(2));
+    assert_eq!(table.history("a").unwrap().len(), 1);
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table["b"].info.int = 1;
+    match table.write_entry("b") {
+        Err(TableError::UniqueError(..)) => assert!(true),
+        _ => assert!(false),
+    }
+    table["b"].info.int = 0;
+    let items = json_tables::Storage::list(table.storage()).unwrap();
+    assert!(!items.iter().any(|item| item.starts_with(".table_staging")));
+}
+
+#[test]
+fn memory_estimation() {
+    let mut table = Table::<Vec<u64>, MemoryStorage>::in_memory();
+    let empty = table.estimated_memory();
+    assert_eq!(empty.keys, 0);
+    assert_eq!(empty.values, 0);
+    table.push("small", vec![1]).unwrap();
+    table.push("large", (0..1000).collect()).unwrap();
+    let estimate = table.estimated_memory();
+    assert!(estimate.keys >= "small".len() + "large".len());
+    assert!(estimate.values > 1000);
+    assert!(estimate.bookkeeping > empty.bookkeeping);
+    assert_eq!(
+        estimate.total(),
+        estimate.keys + estimate.values + estimate.bookkeeping
+    );
+    table.pop("large").unwrap();
+    assert!(table.estimated_memory().total() < estimate.total());
+}
+
+#[test]
+fn evicted_entries() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table.push("b", SimplifiedStruct::default()).unwrap();
+    table.write_back().unwrap();
+    table["a"].info.int = 1;
+    table.evict("a").unwrap();
+    assert!(table.is_evicted("a"));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.get_table_keys().count(), 1);
+    assert_eq!(table["a"].info.int, 1);
+    assert!(!table.is_evicted("a"));
+    table.evict_all().unwrap();
+    assert!(table.is_evicted("a") && table.is_evicted("b"));
+    match table.push("b", SimplifiedStruct::default()) {
+        Err(TableError::PushError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.get_mut_element("b").unwrap().info.int = 2;
+    assert!(!table.is_evicted("b"));
+    assert_eq!(table.get_table_keys().count(), 1);
+    table.pop("a").unwrap();
+    assert_eq!(table.len(), 1);
+    match table.evict("a") {
+        Err(TableError::MissingKeyError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.evict("b").unwrap();
+    table.reload_evicted().unwrap();
+    assert_eq!(table.iter().next().unwrap().1.info.int, 2);
+}
+
+#[test]
+fn evicted_entries_stay_visible() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .append(&["1", "2"], &[SimplifiedStruct::default(); 2])
+        .unwrap();
+    table.write_back().unwrap();
+    table.evict("1").unwrap();
+    assert_eq!(table.find_duplicates().unwrap(), [["1", "2"]]);
+    assert!(table.unique("int", |info| info.int.to_string()).is_err());
+    table.get_mut_element("2").unwrap().info.int = 2;
+    table.evict_all().unwrap();
+    table.unique("int", |info| info.int.to_string()).unwrap();
+    match table.push("3", SimplifiedStruct::default()) {
+        Err(TableError::UniqueError(_, _)) => assert!(true),
+        _ => assert!(false),
+    }
+    table.evict_all().unwrap();
+    assert_eq!(table.get_id(2).unwrap().int, 2);
+    assert_eq!(table.ids(), [1, 2]);
+    let summary = table.summary();
+    assert_eq!(summary.entries, 2);
+    assert_eq!(summary.keys, ["1", "2"]);
+    table.evict_all().unwrap();
+    let json = serde_json::to_value(&table).unwrap();
+    assert_eq!(json["entries"]["2"]["int"], 2);
+    assert_eq!(json["entries"].as_object().unwrap().len(), 2);
+}
+
+#[test]
+fn evicted_entries_everywhere() {
+    let metadata = TableMetadata {
+        concurrency_policy: ConcurrencyPolicy::Optimistic,
+        history_policy: json_tables::HistoryPolicy::KeepVersions(2),
+        ..Default::default()
+    };
+    let mut table =
+        Table::<SimplifiedStruct, _>::with_storage(MemoryStorage::default(), metadata).unwrap();
+    table.push("a", SimplifiedStruct::default()).unwrap();
+    table
+        .push("b", SimplifiedStruct { int: 1, float: 0.0 })
+        .unwrap();
+    table.write_back().unwrap();
+    table["b"].info.int = 2;
+    table.write_back().unwrap();
+    table.evict("b").unwrap();
+
+    // queries
+    assert_eq!(table.find(|_, info| info.int == 2).unwrap().0, "b");
+    assert!(table.any(|key, _| key == "b"));
+    assert!(!table.all(|_, info| info.int == 0));
+    assert_eq!(table.group_by(|info| info.int).len(), 2);
+    assert_eq!(
+        table.get_pointer("b", "/int").unwrap(),
+        Some(serde_json::json!(2))
+    );
+    assert_eq!(table.query_pointer("/int").unwrap().len(), 2);
+    assert_eq!(table.version("b"), Some(2));
+    assert_eq!(table.history("b").unwrap().len(), 1);
+
+    // changes
+    table
+        .merge_patch("b", &serde_json::json!({"int": 3}))
+        .unwrap();
+    table.evict("b").unwrap();
+    let patch = serde_json::json!([{"op": "replace", "path": "/int", "value": 4}]);
+    table.patch("b", &patch).unwrap();
+    assert_eq!(table["b"].info.int, 4);
+    table.write_back().unwrap();
+    table.evict("b").unwrap();
+    table
+        .update_if_version("b", 3, SimplifiedStruct { int: 5, float: 0.0 })
+        .unwrap();
+    table.evict("b").unwrap();
+    let version = table.history("b").unwrap()[0].0;
+    table.restore_version("b", version).unwrap();
+    table.write_back().unwrap();
+    table.evict("b").unwrap();
+
+    // an entry written in another format is compacted while evicted
+    let mut compacted = Table::<SimplifiedStruct>::temporary().unwrap();
+    compacted.push("b", SimplifiedStruct::default()).unwrap();
+    compacted.write_back().unwrap();
+    compacted.evict("b").unwrap();
+    let name = compacted.dir().join("b.json");
+    std::fs::write(&name, r#"{"int":7,"float":0.0}"#).unwrap();
+    compacted.compact(false).unwrap();
+    assert!(std::fs::read_to_string(&name)
+        .unwrap()
+        .contains("\"int\": 7"));
+    assert!(compacted.is_evicted("b"));
+
+    // conversions
+    let mapped = table
+        .map_into("tests/evicted_map", |info| info.int)
+        .unwrap();
+    assert_eq!(mapped["b"].info, 1);
+    drop(mapped);
+    std::fs::remove_dir_all("tests/evicted_map").unwrap();
+
+    // groups
+    let mut group = TableGroup::new();
+    let mut targets = Table::<SimplifiedStruct>::temporary().unwrap();
+    targets.push("1", SimplifiedStruct::default()).unwrap();
+    targets.write_back().unwrap();
+    targets.evict("1").unwrap();
+    let mut refs = Table::<Order>::temporary().unwrap();
+    let order = |customer_id: &str| Order {
+        customer_id: customer_id.into(),
+        amount: 0,
+    };
+    refs.push("x", order("9")).unwrap();
+    refs.write_back().unwrap();
+    refs.evict("x").unwrap();
+    group.insert("targets", targets);
+    group.insert("refs", refs);
+    group.references("refs", "customer_id", "targets", |order: &Order| {
+        order.customer_id.clone()
+    });
+    group.push("refs", "y", order("1")).unwrap();
+    assert_eq!(group.check_references()[0].key, "x");
+
+    let mut table = Table::<Vec<u64>, MemoryStorage>::in_memory();
+    table.push("a", vec![1]).unwrap();
+    table.write_back().unwrap();
+    let copy =
+        Table::<Vec<u64>, _>::with_storage(table.storage().clone(), Default::default()).unwrap();
+    table.evict("a").unwrap();
+    assert!(table == copy);
+    assert!(copy == table);
+}
+
+#[test]
+fn interned_keys() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .push("a_rather_long_key", SimplifiedStruct::default())
+        .unwrap();
+    let snapshot = table.snapshot();
+    let (key, _) = table.iter().next().unwrap();
+    assert!(std::sync::Arc::ptr_eq(key, snapshot.keys().next().unwrap()));
+    table.evict("a_rather_long_key").unwrap();
+    table.reload_evicted().unwrap();
+    assert!(std::sync::Arc::ptr_eq(
+        table.get_table_keys().next().unwrap(),
+        snapshot.keys().next().unwrap()
+    ));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_iterators() {
+    use rayon::prelude::*;
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    for i in 0..100 {
+        let element = SimplifiedStruct {
+            int: i,
+            ..Default::default()
+        };
+        table.push(&i.to_string(), element).unwrap();
+    }
+    table.write_back().unwrap();
+    assert!(table
+        .par_iter()
+        .all(|(key, element)| **key == element.info.int.to_string()));
+    table
+        .par_values_mut()
+        .for_each(|element| element.info.int *= 2);
+    assert!(table.is_modified());
+    let sum: i32 = table.par_values().map(|element| element.info.int).sum();
+    assert_eq!(sum, 2 * (0..100).sum::<i32>());
+}
+
+#[test]
+fn chunked_processing() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    for key in ["a", "b", "c", "d", "e"] {
+        table.push(key, SimplifiedStruct::default()).unwrap();
+    }
+    table.freeze("c").unwrap();
+    let mut sizes = Vec::new();
+    table
+        .process_in_chunks(2, true, |batch| {
+            sizes.push(batch.len());
+            for (_, info) in batch.iter_mut() {
+                info.int += 1;
+            }
+        })
+        .unwrap();
+    assert_eq!(sizes, [2, 2]);
+    assert!(!table.is_modified());
+    let stored = json_tables::Storage::read(table.storage(), "e.json").unwrap();
+    assert!(String::from_utf8(stored).unwrap().contains("\"int\": 1"));
+    assert_eq!(table["c"].info.int, 0);
+    table
+        .process_in_chunks(10, false, |batch| {
+            assert_eq!(batch[0].0, "a");
+            batch[0].1.int = 5;
+        })
+        .unwrap();
+    assert!(table.is_modified());
+    assert_eq!(table.metrics().write_backs, 3);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn seeded_tables() {
+    let seed = |i| SimplifiedStruct {
+        int: i as i32,
+        ..Default::default()
+    };
+    let table = Table::seed("tests/seeded", 3, seed).unwrap();
+    assert_eq!(table.len(), 3);
+    assert_eq!(table["2"].info.int, 2);
+    drop(table);
+    let table = Table::<SimplifiedStruct>::builder("tests/seeded")
+        .load()
+        .unwrap();
+    assert_eq!(table.len(), 3);
+    drop(table);
+    std::fs::remove_dir_all("tests/seeded").unwrap();
+
+    let table = Table::seed_temporary(2, seed).unwrap();
+    assert!(table.dir().join("1.json").exists());
+    let mut table = Table::seed_in_memory(2, seed).unwrap();
+    table.populate(2, seed).unwrap();
+    assert_eq!(table.ids(), [0, 1, 2, 3]);
+    assert_eq!(table.get_id(3).unwrap().int, 1);
+    assert!(!table.is_modified());
+}
+
+#[test]
+fn ndjson() {
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .push("b", SimplifiedStruct { int: 2, float: 0.5 })
+        .unwrap();
+    table
+        .push("a", SimplifiedStruct { int: 1, float: 0.0 })
+        .unwrap();
+    table.write_back().unwrap();
+    table.evict("b").unwrap();
+    let mut exported = Vec::new();
+    table.export_ndjson(&mut exported).unwrap();
+    assert_eq!(
+        String::from_utf8(exported.clone()).unwrap(),
+        "{\"key\":\"a\",\"value\":{\"int\":1,\"float\":0.0}}\n\
+         {\"key\":\"b\",\"value\":{\"int\":2,\"float\":0.5}}\n"
+    );
+    exported.extend_from_slice(b"\n");
+    let imported = Table::<SimplifiedStruct>::import_ndjson(
+        "tests/ndjson",
+        &exported[..],
+        TableMetadata::default(),
+    )
+    .unwrap();
+    assert_eq!(imported.len(), 2);
+    assert_eq!(imported["b"].info.int, 2);
+    assert!(std::path::Path::new("tests/ndjson/a.json").exists());
+    drop(imported);
+    std::fs::remove_dir_all("tests/ndjson").unwrap();
+    match Table::<SimplifiedStruct>::import_ndjson(
+        "tests/ndjson_bad",
+        &b"{\"key\":\"a\"}\n"[..],
+        TableMetadata::default(),
+    ) {
+        Err(TableError::SerdeError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    std::fs::remove_dir_all("tests/ndjson_bad").unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_export() {
+    let mut table = Table::<ExampleStruct, MemoryStorage>::in_memory();
+    let element = ExampleStruct {
+        int: 7,
+        string: "seven".to_string(),
+        ..Default::default()
+    };
+    table.push("a", element).unwrap();
+    table.push("b", ExampleStruct::default()).unwrap();
+    let _ = std::fs::remove_file("tests/export.sqlite");
+    table
+        .export_sqlite(
+            "tests/export.sqlite",
+            "blobs",
+            json_tables::SqliteLayout::Json,
+        )
+        .unwrap();
+    table
+        .export_sqlite(
+            "tests/export.sqlite",
+            "columns",
+            json_tables::SqliteLayout::Flattened,
+        )
+        .unwrap();
+    match table.export_sqlite(
+        "tests/export.sqlite",
+        "blobs",
+        json_tables::SqliteLayout::Json,
+    ) {
+        Err(TableError::SqliteError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    let connection = rusqlite::Connection::open("tests/export.sqlite").unwrap();
+    let int: i64 = connection
+        .query_row(
+            "SELECT json_extract(value, '$.int') FROM blobs WHERE key = 'a'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(int, 7);
+    let string: String = connection
+        .query_row("SELECT string FROM columns WHERE key = 'a'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(string, "seven");
+    let mut nested = Table::<json_tables::Value, MemoryStorage>::in_memory();
+    nested
+        .push("n", serde_json::json!({"address": {"city": "Lugo"}}))
+        .unwrap();
+    nested
+        .export_sqlite(
+            "tests/export.sqlite",
+            "nested",
+            json_tables::SqliteLayout::Flattened,
+        )
+        .unwrap();
+    let city: String = connection
+        .query_row("SELECT \"address.city\" FROM nested", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(city, "Lugo");
+    drop(connection);
+    std::fs::remove_file("tests/export.sqlite").unwrap();
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn parquet_export() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    let mut table = Table::<SimplifiedStruct, MemoryStorage>::in_memory();
+    table
+        .push("a", SimplifiedStruct { int: 1, float: 0.5 })
+        .unwrap();
+    table
+        .push("b", SimplifiedStruct { int: 2, float: 1.5 })
+        .unwrap();
+    table.export_parquet("tests/export.parquet").unwrap();
+    let reader =
+        SerializedFileReader::new(std::fs::File::open("tests/export.parquet").unwrap()).unwrap();
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+    let columns: Vec<_> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+    assert_eq!(columns, ["key", "float", "int"]);
+    std::fs::remove_file("tests/export.parquet").unwrap();
+
+    let mut table = Table::<Vec<i32>, MemoryStorage>::in_memory();
+    table.push("a", vec![1]).unwrap();
+    match table.export_parquet("tests/export_list.parquet") {
+        Err(TableError::ParquetError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+}